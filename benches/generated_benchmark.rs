@@ -0,0 +1,63 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use simple_astar::generate::{cave, maze};
+use simple_astar::{astar_grid, Grid};
+
+/// First floor cell and the last floor cell reachable from it.
+fn far_apart_floor_cells(grid: &Grid) -> (u32, u32) {
+    let mut floor = (0..grid.cells().len() as u32).filter(|&i| grid.cells()[i as usize] > 0);
+    let start = floor.next().expect("generated map has floor");
+    let end = floor
+        .rev()
+        .find(|&end| !astar_grid(start, end, grid, false).is_empty())
+        .unwrap_or(start);
+    (start, end)
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("simple_astar backtracker maze 63 * 63", |b| {
+        let grid = maze::recursive_backtracker(31, 31, 1);
+        let end = maze::cell_index(31, 30, 30);
+        b.iter(|| {
+            astar_grid(
+                black_box(maze::cell_index(31, 0, 0)),
+                black_box(end),
+                black_box(&grid),
+                black_box(true),
+            )
+        })
+    });
+    c.bench_function("simple_astar prim maze 63 * 63", |b| {
+        let grid = maze::prim(31, 31, 1);
+        let end = maze::cell_index(31, 30, 30);
+        b.iter(|| {
+            astar_grid(
+                black_box(maze::cell_index(31, 0, 0)),
+                black_box(end),
+                black_box(&grid),
+                black_box(true),
+            )
+        })
+    });
+    c.bench_function("simple_astar cave 128 * 128", |b| {
+        let grid = cave::generate(
+            128,
+            128,
+            &cave::CaveOptions {
+                seed: 1,
+                ..cave::CaveOptions::default()
+            },
+        );
+        let (start, end) = far_apart_floor_cells(&grid);
+        b.iter(|| {
+            astar_grid(
+                black_box(start),
+                black_box(end),
+                black_box(&grid),
+                black_box(false),
+            )
+        })
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
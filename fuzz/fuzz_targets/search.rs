@@ -0,0 +1,54 @@
+//! Searches arbitrary grids and checks every returned path for legality and
+//! optimality against a Dijkstra map, or for staying within the reported
+//! bound when the heuristic was weighted. Paths cut short by a limit or
+//! budget, or kept to a number of steps, are only checked for legality.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use simple_astar::testing::{path_cost, verify_path, PathError};
+use simple_astar::{search, Grid, GridSource, SearchOptions, TerminationReason};
+
+fuzz_target!(|input: (Grid, u32, u32, SearchOptions)| {
+    let (grid, start, end, options) = input;
+    // the path is what is checked, so it has to be walked out
+    let options = SearchOptions {
+        lazy_path: false,
+        ..options
+    };
+    let start = start % grid.len();
+    let end = end % grid.len();
+    let result = search(start, end, &grid, &options);
+    let cardinal = options.cardinal_directions;
+    let cost = match result.termination {
+        TerminationReason::GoalReached
+            if result.remaining_cost == Some(0) && options.max_steps.is_none() =>
+        {
+            match verify_path(&grid, start, end, &result.path, cardinal) {
+                Err(PathError::Suboptimal { cost, optimal })
+                    if cost as u64 * 100 <= optimal as u64 * result.bound_percent as u64 =>
+                {
+                    Ok(cost)
+                }
+                checked => checked,
+            }
+        }
+        TerminationReason::Exhausted if !result.is_partial() => {
+            verify_path(&grid, start, end, &result.path, cardinal)
+        }
+        _ => path_cost(&grid, start, &result.path, cardinal),
+    }
+    .unwrap_or_else(|error| panic!("{}: {:?}", error, result.path));
+    if result.termination == TerminationReason::GoalReached {
+        let start_cost = if options.pay_for_start {
+            grid.cost(start)
+        } else {
+            0
+        };
+        assert_eq!(
+            result.cost,
+            result
+                .remaining_cost
+                .map(|remaining| start_cost + cost + remaining)
+        );
+    }
+});
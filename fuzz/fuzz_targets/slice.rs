@@ -0,0 +1,15 @@
+//! Feeds raw, possibly inconsistent slices, widths and indices to `astar`,
+//! which must never panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use simple_astar::astar;
+
+fuzz_target!(|input: (Vec<u32>, u32, u32, u32, bool)| {
+    let (cells, width, start, end, cardinal_directions) = input;
+    let width = width % 128;
+    let path = astar(start, end, &cells, width, cardinal_directions);
+    for cell in path {
+        assert!((cell as usize) < cells.len());
+    }
+});
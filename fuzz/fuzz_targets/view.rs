@@ -0,0 +1,27 @@
+//! Searches arbitrary windows of arbitrary grids and checks that results
+//! agree with searching a copy of the window.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use simple_astar::{astar_grid, Grid, GridSource};
+
+fuzz_target!(|input: (Grid, (u32, u32, u32, u32), u32, u32, bool)| {
+    let (grid, (x, y, width, height), start, end, cardinal_directions) = input;
+    let view = match grid.view(x % 70, y % 70, width % 70, height % 70) {
+        Ok(view) => view,
+        Err(_) => return,
+    };
+    if view.is_empty() {
+        return;
+    }
+    let copy = Grid::new(
+        (0..view.len()).map(|i| view.cost(i)).collect::<Vec<_>>(),
+        view.width(),
+    )
+    .unwrap();
+    let (start, end) = (start % view.len(), end % view.len());
+    assert_eq!(
+        astar_grid(start, end, &view, cardinal_directions),
+        astar_grid(start, end, &copy, cardinal_directions)
+    );
+});
@@ -0,0 +1,156 @@
+//! GDExtension bindings exposing [`simple_astar::Grid`] and path queries to
+//! GDScript.
+//!
+//! Build with `cargo build --release` from this directory, copy the library
+//! into your project next to `simple_astar.gdextension`, and use the
+//! `WeightedGrid` class from GDScript:
+//!
+//! ```gdscript
+//! var grid := WeightedGrid.create(40, 30, 1)
+//! grid.set_cost(Vector2i(3, 4), 0)
+//! var path := grid.find_path(Vector2i(0, 0), Vector2i(39, 29), false)
+//! ```
+//!
+//! Cells are addressed with `Vector2i` coordinates. Paths exclude the start
+//! and end at the goal, like everywhere else in the crate, and are empty
+//! when the goal can't be reached.
+
+// The glue generated by `#[godot_api]` trips this lint.
+#![allow(clippy::result_large_err)]
+
+use godot::prelude::*;
+use simple_astar::{search, Grid, SearchOptions, SearchResult};
+
+struct SimpleAstarExtension;
+
+#[gdextension]
+unsafe impl ExtensionLibrary for SimpleAstarExtension {}
+
+/// A grid of movement costs, where `0` marks a wall.
+#[derive(GodotClass)]
+#[class(base = RefCounted)]
+struct WeightedGrid {
+    grid: Grid,
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl IRefCounted for WeightedGrid {
+    fn init(base: Base<RefCounted>) -> Self {
+        WeightedGrid {
+            grid: Grid::filled(1, 1, 1).expect("a 1x1 grid is valid"),
+            base,
+        }
+    }
+}
+
+/// Clamps a GDScript integer into the range of cell costs.
+fn to_cost(cost: i64) -> u32 {
+    cost.clamp(0, u32::MAX as i64) as u32
+}
+
+#[godot_api]
+impl WeightedGrid {
+    /// A `width * height` grid with every cell set to `cost`, or `null`
+    /// when either dimension isn't positive.
+    #[func]
+    fn create(width: i64, height: i64, cost: i64) -> Option<Gd<Self>> {
+        if width <= 0 || height <= 0 || width * height > u32::MAX as i64 {
+            return None;
+        }
+        let grid = Grid::filled(width as u32, height as u32, to_cost(cost)).ok()?;
+        Some(Gd::from_init_fn(|base| WeightedGrid { grid, base }))
+    }
+
+    /// A grid read from row-major `costs`, or `null` when they can't be split
+    /// into rows of `width`.
+    #[func]
+    fn from_costs(costs: PackedInt32Array, width: i64) -> Option<Gd<Self>> {
+        if width <= 0 || width > u32::MAX as i64 {
+            return None;
+        }
+        let cells: Vec<u32> = costs
+            .as_slice()
+            .iter()
+            .map(|&cost| to_cost(cost as i64))
+            .collect();
+        let grid = Grid::new(cells, width as u32).ok()?;
+        Some(Gd::from_init_fn(|base| WeightedGrid { grid, base }))
+    }
+
+    #[func]
+    fn get_width(&self) -> i64 {
+        self.grid.width() as i64
+    }
+
+    #[func]
+    fn get_height(&self) -> i64 {
+        self.grid.height() as i64
+    }
+
+    fn index(&self, cell: Vector2i) -> Option<u32> {
+        if cell.x < 0 || cell.y < 0 {
+            return None;
+        }
+        let (x, y) = (cell.x as u32, cell.y as u32);
+        if x < self.grid.width() && y < self.grid.height() {
+            Some(self.grid.index(x, y))
+        } else {
+            None
+        }
+    }
+
+    fn cell(&self, index: u32) -> Vector2i {
+        let width = self.grid.width();
+        Vector2i::new((index % width) as i32, (index / width) as i32)
+    }
+
+    /// The cost of `cell`, or `-1` outside the grid.
+    #[func]
+    fn get_cost(&self, cell: Vector2i) -> i64 {
+        match self.index(cell) {
+            Some(index) => self.grid.cells()[index as usize] as i64,
+            None => -1,
+        }
+    }
+
+    /// Changes the cost of one cell, returning `false` when it lies outside
+    /// the grid. Later queries see the change straight away.
+    #[func]
+    fn set_cost(&mut self, cell: Vector2i, cost: i64) -> bool {
+        match self.index(cell) {
+            Some(index) => {
+                self.grid.cells_mut()[index as usize] = to_cost(cost);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn query(&self, from: Vector2i, to: Vector2i, cardinal_only: bool) -> Option<SearchResult> {
+        let start = self.index(from)?;
+        let end = self.index(to)?;
+        let options = SearchOptions {
+            cardinal_directions: cardinal_only,
+            ..SearchOptions::default()
+        };
+        Some(search(start, end, &self.grid, &options))
+    }
+
+    /// The cheapest path from `from` to `to`, excluding `from`.
+    #[func]
+    fn find_path(&self, from: Vector2i, to: Vector2i, cardinal_only: bool) -> Array<Vector2i> {
+        self.query(from, to, cardinal_only)
+            .map(|result| result.path.iter().map(|&index| self.cell(index)).collect())
+            .unwrap_or_default()
+    }
+
+    /// The cost of the cheapest path from `from` to `to`, or `-1` when there
+    /// is none.
+    #[func]
+    fn find_path_cost(&self, from: Vector2i, to: Vector2i, cardinal_only: bool) -> i64 {
+        self.query(from, to, cardinal_only)
+            .and_then(|result| result.cost)
+            .map_or(-1, |cost| cost as i64)
+    }
+}
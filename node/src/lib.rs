@@ -0,0 +1,103 @@
+//! Node.js bindings built with napi-rs, so a JavaScript server can run the
+//! same searches as Rust tools.
+//!
+//! Build with `npm run build` from this directory. Grids are created from a
+//! `Uint32Array` of row-major costs and paths come back as `Uint32Array`s of
+//! cell indices, excluding the start and ending at the goal:
+//!
+//! ```js
+//! const { Grid } = require('simple-astar')
+//! const grid = new Grid(new Uint32Array(40 * 30).fill(1), 40)
+//! const path = grid.findPath(0, 40 * 30 - 1)
+//! const paths = grid.findPaths(new Uint32Array([0, 10, 5, 99]))
+//! ```
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use simple_astar::{search, SearchOptions};
+
+#[napi(js_name = "Grid")]
+pub struct JsGrid {
+    grid: simple_astar::Grid,
+}
+
+#[napi]
+impl JsGrid {
+    /// Copies row-major `costs`, where `0` marks a wall. Throws when they
+    /// can't be split into rows of `width`.
+    #[napi(constructor)]
+    pub fn new(costs: Uint32Array, width: u32) -> Result<Self> {
+        let grid = simple_astar::Grid::new(costs.to_vec(), width)
+            .map_err(|error| Error::new(Status::InvalidArg, error.to_string()))?;
+        Ok(JsGrid { grid })
+    }
+
+    #[napi(getter)]
+    pub fn width(&self) -> u32 {
+        self.grid.width()
+    }
+
+    #[napi(getter)]
+    pub fn height(&self) -> u32 {
+        self.grid.height()
+    }
+
+    /// The cost of the cell at `index`, or `undefined` outside the grid.
+    #[napi]
+    pub fn get_cost(&self, index: u32) -> Option<u32> {
+        self.grid.cells().get(index as usize).copied()
+    }
+
+    /// Changes the cost of one cell, returning `false` when it lies outside
+    /// the grid.
+    #[napi]
+    pub fn set_cost(&mut self, index: u32, cost: u32) -> bool {
+        match self.grid.cells_mut().get_mut(index as usize) {
+            Some(cell) => {
+                *cell = cost;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The cheapest path from `start` to `end`, empty when there is none.
+    #[napi]
+    pub fn find_path(&self, start: u32, end: u32, cardinal_only: Option<bool>) -> Uint32Array {
+        Uint32Array::new(self.path(start, end, &options(cardinal_only)))
+    }
+
+    /// One path per `[start, end]` pair in `queries`, which must have an even
+    /// length.
+    #[napi]
+    pub fn find_paths(
+        &self,
+        queries: Uint32Array,
+        cardinal_only: Option<bool>,
+    ) -> Result<Vec<Uint32Array>> {
+        if !queries.len().is_multiple_of(2) {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "queries must be [start, end] pairs".to_string(),
+            ));
+        }
+        let options = options(cardinal_only);
+        Ok(queries
+            .chunks_exact(2)
+            .map(|pair| Uint32Array::new(self.path(pair[0], pair[1], &options)))
+            .collect())
+    }
+}
+
+impl JsGrid {
+    fn path(&self, start: u32, end: u32, options: &SearchOptions) -> Vec<u32> {
+        search(start, end, &self.grid, options).path
+    }
+}
+
+fn options(cardinal_only: Option<bool>) -> SearchOptions {
+    SearchOptions {
+        cardinal_directions: cardinal_only.unwrap_or(false),
+        ..SearchOptions::default()
+    }
+}
@@ -0,0 +1,187 @@
+use crate::artifact::{cells, valid_move, write_u32s, write_u8s, Reader};
+use crate::dijkstra::{first_moves, NO_MOVE};
+use crate::{neighbor_in, Artifact, DecodeError, GridSource};
+use std::collections::BinaryHeap;
+
+/// The cheapest cost and first move between every pair of cells of a small,
+/// static grid, so that every query is a table lookup.
+///
+/// Tables take five bytes per pair of cells, which is 80 MiB for a `64 * 64`
+/// map, and building them runs a Dijkstra search from every cell. For larger
+/// maps, see [`crate::CompressedPathDatabase`], which stores first moves
+/// alone and compresses them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllPairs {
+    width: u32,
+    height: u32,
+    cardinal_directions: bool,
+    /// `distances[from * len + to]`, with `u32::MAX` marking unreachable pairs.
+    distances: Vec<u32>,
+    moves: Vec<u8>,
+}
+
+impl AllPairs {
+    pub fn new<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> Self {
+        let len = grid.len() as usize;
+        let mut distances = vec![u32::MAX; len * len];
+        let mut moves = vec![NO_MOVE; len * len];
+        let mut frontier = BinaryHeap::new();
+        for source in 0..len {
+            let row = source * len..(source + 1) * len;
+            first_moves(
+                grid,
+                source as u32,
+                cardinal_directions,
+                &mut distances[row.clone()],
+                &mut moves[row],
+                &mut frontier,
+            );
+        }
+        AllPairs {
+            width: grid.width(),
+            height: grid.height(),
+            cardinal_directions,
+            distances,
+            moves,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn cardinal_directions(&self) -> bool {
+        self.cardinal_directions
+    }
+
+    /// Approximate heap memory used by the tables.
+    pub fn memory_bytes(&self) -> usize {
+        self.distances.len() * std::mem::size_of::<u32>() + self.moves.len()
+    }
+
+    fn pair(&self, from: u32, to: u32) -> Option<usize> {
+        let len = self.width * self.height;
+        if from < len && to < len {
+            Some(from as usize * len as usize + to as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The cost of the cheapest path from `from` to `to`, or `None` when
+    /// there is none or either lies outside the grid.
+    pub fn distance(&self, from: u32, to: u32) -> Option<u32> {
+        match self.distances[self.pair(from, to)?] {
+            u32::MAX => None,
+            distance => Some(distance),
+        }
+    }
+
+    /// The cell to step into from `from` on a cheapest route to `to`, or
+    /// `None` when `from` is `to` or `to` can't be reached.
+    pub fn next_step(&self, from: u32, to: u32) -> Option<u32> {
+        match self.moves[self.pair(from, to)?] {
+            NO_MOVE => None,
+            first => Some(neighbor_in(from, first, self.width)),
+        }
+    }
+
+    /// A cheapest path from `start` to `end`, in the same form as
+    /// [`crate::astar`] returns.
+    pub fn path(&self, start: u32, end: u32) -> Vec<u32> {
+        let mut path = Vec::new();
+        let mut current = start;
+        while let Some(next) = self.next_step(current, end) {
+            path.push(next);
+            current = next;
+        }
+        path
+    }
+}
+
+impl Artifact for AllPairs {
+    const KIND: [u8; 3] = *b"apt";
+    const VERSION: u8 = 1;
+
+    fn write_payload(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.push(self.cardinal_directions as u8);
+        write_u32s(bytes, &self.distances);
+        write_u8s(bytes, &self.moves);
+    }
+
+    fn read_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(payload);
+        let (width, height) = (reader.u32()?, reader.u32()?);
+        let cardinal_directions = reader.bool()?;
+        let len = cells(width, height)?;
+        let pairs = len.checked_mul(len).ok_or(DecodeError::Corrupt)?;
+        let distances = reader.u32s(pairs)?;
+        let moves = reader.u8s(pairs)?;
+        reader.finish()?;
+        let valid = moves
+            .iter()
+            .enumerate()
+            .all(|(pair, &first)| valid_move((pair / len.max(1)) as u32, first, width, height));
+        if !valid {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(AllPairs {
+            width,
+            height,
+            cardinal_directions,
+            distances,
+            moves,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_artifact, save_artifact, search, DecodeError, Grid, Rng, SearchOptions};
+
+    #[test]
+    fn tables_that_step_off_the_grid_are_corrupt() {
+        let grid = Grid::filled(3, 3, 1).unwrap();
+        let mut table = AllPairs::new(&grid, false);
+        // up, from the top row toward the corner
+        let pair = table.pair(1, 0).unwrap();
+        table.moves[pair] = 0;
+        let bytes = save_artifact(&table, &grid);
+        assert_eq!(
+            load_artifact::<AllPairs, _>(&bytes, &grid),
+            Err(DecodeError::Corrupt)
+        );
+    }
+
+    #[test]
+    fn lookups_match_searches() {
+        let mut rng = Rng::new(1);
+        let grid = rng.grid(12, 10, 20, 4);
+        for &cardinal_directions in [true, false].iter() {
+            let table = AllPairs::new(&grid, cardinal_directions);
+            let options = SearchOptions {
+                cardinal_directions,
+                ..SearchOptions::default()
+            };
+            assert_eq!(table.memory_bytes(), 120 * 120 * 5);
+            for start in 0..grid.len() {
+                for end in (0..grid.len()).step_by(7) {
+                    let expected = search(start, end, &grid, &options);
+                    assert_eq!(table.distance(start, end), expected.cost);
+                    assert_eq!(table.path(start, end).is_empty(), expected.path.is_empty());
+                }
+            }
+        }
+        let table = AllPairs::new(&grid, false);
+        assert_eq!(table.distance(0, grid.len()), None);
+        assert_eq!(table.next_step(5, 5), None);
+    }
+}
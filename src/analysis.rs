@@ -0,0 +1,309 @@
+use crate::{get_neighbor_coords, GridSource, MapStats, Regions, SliceGrid, WALL};
+use std::collections::VecDeque;
+
+/// The largest axis-aligned rectangle with no walls in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenArea {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Navigation-quality figures for a map, from [`analyze`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapAnalysis {
+    /// The same figures [`crate::Solver::auto`] works from.
+    pub stats: MapStats,
+    /// Groups of walkable cells that can't reach each other.
+    pub regions: u32,
+    /// Cells in the largest region.
+    pub largest_region: u32,
+    /// The average width of the free space around walkable cells. Each cell
+    /// counts as the width of the widest square of walkable cells centered
+    /// on it, so open fields score high and one-cell corridors score `1`.
+    pub mean_corridor_width: f64,
+    pub largest_open_area: Option<OpenArea>,
+}
+
+impl MapAnalysis {
+    /// The share of cells that are walls, from `0` to `1`.
+    pub fn obstacle_density(&self) -> f64 {
+        if self.stats.cells == 0 {
+            0.0
+        } else {
+            self.stats.walls as f64 / self.stats.cells as f64
+        }
+    }
+}
+
+/// Analyzes a row-major grid of movement costs `width` cells wide, laid out
+/// as for [`crate::astar`]. Regions are counted for the given movement mode.
+pub fn analyze(grid: &[u32], width: u32, cardinal_directions: bool) -> MapAnalysis {
+    if width == 0 {
+        return analyze_grid(&SliceGrid::new(&[], 1), cardinal_directions);
+    }
+    analyze_grid(&SliceGrid::new(grid, width), cardinal_directions)
+}
+
+/// Same as [`analyze`], for any [`GridSource`].
+pub fn analyze_grid<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> MapAnalysis {
+    let regions = Regions::new(grid, cardinal_directions);
+    MapAnalysis {
+        stats: MapStats::new(grid),
+        regions: regions.count(),
+        largest_region: (0..regions.count())
+            .map(|region| regions.size(region))
+            .max()
+            .unwrap_or(0),
+        mean_corridor_width: mean_corridor_width(grid),
+        largest_open_area: largest_open_area(grid),
+    }
+}
+
+/// The narrow places of a map, from [`choke_points`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChokePoints {
+    /// Walkable cells that cut their region in two if walled off, in
+    /// row-major order.
+    pub cells: Vec<u32>,
+    /// Runs of connected cells one cell wide along a row or column, walled
+    /// in on both sides across it. Each run is in row-major order, and runs
+    /// are ordered by their first cells.
+    pub corridors: Vec<Vec<u32>>,
+}
+
+/// Finds the choke points and corridors of `grid` for the given movement
+/// mode. Choke points are the articulation points of the graph of walkable
+/// cells, found with Tarjan's algorithm.
+pub fn choke_points<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> ChokePoints {
+    ChokePoints {
+        cells: articulation_points(grid, cardinal_directions),
+        corridors: corridors(grid, cardinal_directions),
+    }
+}
+
+fn articulation_points<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> Vec<u32> {
+    const UNSEEN: u32 = u32::MAX;
+    let len = grid.len() as usize;
+    let mut discovered = vec![UNSEEN; len];
+    let mut low = vec![0; len];
+    let mut cut = vec![false; len];
+    let mut time = 0;
+    // (cell, parent, its neighbors, how many of them were visited)
+    let mut stack = Vec::new();
+    for root in 0..grid.len() {
+        if discovered[root as usize] != UNSEEN || grid.cost(root) == WALL {
+            continue;
+        }
+        discovered[root as usize] = time;
+        low[root as usize] = time;
+        time += 1;
+        let mut root_children = 0;
+        let neighbors = get_neighbor_coords(root, grid, cardinal_directions);
+        stack.push((root, UNSEEN, neighbors, 0));
+        while let Some((cell, parent, neighbors, next)) = stack.last_mut() {
+            let (cell, parent) = (*cell, *parent);
+            if let Some(&neighbor) = neighbors.get(*next) {
+                *next += 1;
+                if discovered[neighbor as usize] == UNSEEN {
+                    discovered[neighbor as usize] = time;
+                    low[neighbor as usize] = time;
+                    time += 1;
+                    if cell == root {
+                        root_children += 1;
+                    }
+                    let neighbors = get_neighbor_coords(neighbor, grid, cardinal_directions);
+                    stack.push((neighbor, cell, neighbors, 0));
+                } else if neighbor != parent {
+                    low[cell as usize] = low[cell as usize].min(discovered[neighbor as usize]);
+                }
+                continue;
+            }
+            stack.pop();
+            if parent != UNSEEN {
+                low[parent as usize] = low[parent as usize].min(low[cell as usize]);
+                if parent != root && low[cell as usize] >= discovered[parent as usize] {
+                    cut[parent as usize] = true;
+                }
+            }
+        }
+        cut[root as usize] = root_children > 1;
+    }
+    (0..grid.len()).filter(|&cell| cut[cell as usize]).collect()
+}
+
+fn corridors<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> Vec<Vec<u32>> {
+    let (width, height) = (grid.width(), grid.height());
+    let blocked = |x: i64, y: i64| {
+        x < 0 || y < 0 || x >= width as i64 || y >= height as i64 || {
+            grid.cost(y as u32 * width + x as u32) == WALL
+        }
+    };
+    let in_corridor: Vec<bool> = (0..grid.len())
+        .map(|cell| {
+            let (x, y) = ((cell % width) as i64, (cell / width) as i64);
+            let across_row = blocked(x, y - 1) && blocked(x, y + 1);
+            let across_column = blocked(x - 1, y) && blocked(x + 1, y);
+            grid.cost(cell) != WALL && across_row != across_column
+        })
+        .collect();
+    let mut seen = vec![false; grid.len() as usize];
+    let mut runs = Vec::new();
+    let mut queue = VecDeque::new();
+    for cell in 0..grid.len() {
+        if !in_corridor[cell as usize] || seen[cell as usize] {
+            continue;
+        }
+        let mut run = Vec::new();
+        seen[cell as usize] = true;
+        queue.push_back(cell);
+        while let Some(current) = queue.pop_front() {
+            run.push(current);
+            for &neighbor in get_neighbor_coords(current, grid, cardinal_directions).iter() {
+                if in_corridor[neighbor as usize] && !seen[neighbor as usize] {
+                    seen[neighbor as usize] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        run.sort_unstable();
+        runs.push(run);
+    }
+    runs
+}
+
+/// Averages `2 * d - 1` over walkable cells, where `d` is the distance to
+/// the nearest wall or the edge of the map counted in king moves.
+fn mean_corridor_width<G: GridSource + ?Sized>(grid: &G) -> f64 {
+    let width = grid.width();
+    let height = grid.height();
+    let mut distance = vec![u32::MAX; grid.len() as usize];
+    let mut queue = VecDeque::new();
+    for cell in 0..grid.len() {
+        let (x, y) = (cell % width, cell / width);
+        if grid.cost(cell) == WALL {
+            distance[cell as usize] = 0;
+            queue.push_back(cell);
+        } else if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+            distance[cell as usize] = 1;
+            queue.push_back(cell);
+        }
+    }
+    while let Some(current) = queue.pop_front() {
+        let next = distance[current as usize] + 1;
+        let (x, y) = ((current % width) as i64, (current / width) as i64);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                    continue;
+                }
+                let neighbor = ny as usize * width as usize + nx as usize;
+                if distance[neighbor] > next {
+                    distance[neighbor] = next;
+                    queue.push_back(neighbor as u32);
+                }
+            }
+        }
+    }
+    let (sum, walkable) = distance
+        .iter()
+        .filter(|&&distance| distance > 0)
+        .fold((0u64, 0u64), |(sum, count), &distance| {
+            (sum + 2 * distance as u64 - 1, count + 1)
+        });
+    if walkable == 0 {
+        0.0
+    } else {
+        sum as f64 / walkable as f64
+    }
+}
+
+/// The classic largest rectangle in a histogram, run once per row over the
+/// heights of walkable columns ending at that row.
+fn largest_open_area<G: GridSource + ?Sized>(grid: &G) -> Option<OpenArea> {
+    let width = grid.width() as usize;
+    let mut heights = vec![0u32; width];
+    let mut best: Option<(u64, OpenArea)> = None;
+    let mut stack: Vec<usize> = Vec::with_capacity(width + 1);
+    for y in 0..grid.height() {
+        for (x, height) in heights.iter_mut().enumerate() {
+            if grid.cost(y * grid.width() + x as u32) == WALL {
+                *height = 0;
+            } else {
+                *height += 1;
+            }
+        }
+        stack.clear();
+        for x in 0..=width {
+            let current = if x < width { heights[x] } else { 0 };
+            while let Some(&top) = stack.last() {
+                if heights[top] < current {
+                    break;
+                }
+                stack.pop();
+                let height = heights[top];
+                let left = stack.last().map_or(0, |&left| left + 1);
+                let area = height as u64 * (x - left) as u64;
+                if height > 0 && best.is_none_or(|(best, _)| area > best) {
+                    best = Some((
+                        area,
+                        OpenArea {
+                            x: left as u32,
+                            y: y + 1 - height,
+                            width: (x - left) as u32,
+                            height,
+                        },
+                    ));
+                }
+            }
+            stack.push(x);
+        }
+    }
+    best.map(|(_, area)| area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_measures_the_map() {
+        let grid = vec![
+            1, 1, 1, 0, 1, 1,
+            1, 1, 1, 0, 0, 0,
+            1, 1, 1, 0, 1, 0,
+            0, 0, 0, 0, 1, 0,
+        ];
+        let analysis = analyze(&grid, 6, true);
+        assert_eq!(analysis.stats.walls, 11);
+        assert!((analysis.obstacle_density() - 11.0 / 24.0).abs() < 1e-9);
+        assert_eq!((analysis.regions, analysis.largest_region), (3, 9));
+        assert_eq!(
+            analysis.largest_open_area,
+            Some(OpenArea { x: 0, y: 0, width: 3, height: 3 })
+        );
+        // every cell touches a wall or the edge except the middle of the room
+        assert!((analysis.mean_corridor_width - 15.0 / 13.0).abs() < 1e-9);
+        assert_eq!(analyze(&[], 0, false).regions, 0);
+    }
+
+    #[test]
+    fn it_finds_choke_points_and_corridors() {
+        let grid = crate::grid![
+            1, 1, 0, 0, 1, 1;
+            1, 1, 1, 1, 1, 1;
+            1, 1, 0, 0, 1, 1;
+        ];
+        let cardinal = choke_points(&grid, true);
+        assert_eq!(cardinal.cells, vec![7, 8, 9, 10]);
+        assert_eq!(cardinal.corridors, vec![vec![8, 9]]);
+        let diagonal = choke_points(&grid, false);
+        assert!(diagonal.cells.contains(&8) && diagonal.cells.contains(&9));
+        let open = choke_points(&crate::Grid::filled(4, 4, 1).unwrap(), false);
+        assert_eq!(open, ChokePoints::default());
+    }
+}
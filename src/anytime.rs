@@ -0,0 +1,227 @@
+use crate::search::{search_with, Hooks};
+use crate::{GridSource, Manhattan, SearchOptions, TerminationReason, Weighted};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Heuristic weights tried in turn, in tenths. The last one finds the
+/// cheapest path.
+const WEIGHTS: [u32; 6] = [30, 20, 15, 12, 11, 10];
+
+/// A path strictly cheaper than every one found before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Improvement {
+    pub path: Vec<u32>,
+    pub cost: u32,
+    /// The path costs at most this many percent of the cheapest one, as
+    /// proven by the search that found it; see
+    /// [`crate::SearchResult::bound_percent`]. `100` means it is the
+    /// cheapest.
+    pub bound_percent: u32,
+}
+
+/// Finds a quick path and then better ones, one weighted A* search per call
+/// to [`AnytimeSearch::improve`], so a job system can spread the work over
+/// as many jobs as it likes. Each search uses a smaller heuristic weight than
+/// the last, ending with plain A*.
+#[derive(Debug, Clone)]
+pub struct AnytimeSearch {
+    start: u32,
+    end: u32,
+    options: SearchOptions,
+    next_weight: usize,
+    best: Option<u32>,
+}
+
+impl AnytimeSearch {
+    pub fn new(start: u32, end: u32, options: &SearchOptions) -> Self {
+        AnytimeSearch {
+            start,
+            end,
+            options: options.clone(),
+            next_weight: 0,
+            best: None,
+        }
+    }
+
+    /// Whether the cheapest path has been found, or there is none.
+    pub fn is_done(&self) -> bool {
+        self.next_weight == WEIGHTS.len()
+    }
+
+    /// Runs the next search on `grid`, returning its path when it is
+    /// cheaper than any found so far.
+    pub fn improve<G: GridSource + ?Sized>(&mut self, grid: &G) -> Option<Improvement> {
+        self.improve_with(grid, ())
+    }
+
+    fn improve_with<G: GridSource + ?Sized, H: Hooks>(
+        &mut self,
+        grid: &G,
+        hooks: H,
+    ) -> Option<Improvement> {
+        let weight = *WEIGHTS.get(self.next_weight)?;
+        let heuristic = Weighted::new(Manhattan, weight, 10);
+        let result = search_with(self.start, self.end, grid, &self.options, &heuristic, hooks);
+        if result.termination != TerminationReason::GoalReached {
+            // a lower weight can't reach what a higher one couldn't, and
+            // expands more cells on the way
+            self.next_weight = WEIGHTS.len();
+            return None;
+        }
+        self.next_weight += 1;
+        let cost = result.cost?;
+        if self.best.is_some_and(|best| best <= cost) {
+            return None;
+        }
+        self.best = Some(cost);
+        Some(Improvement {
+            path: result.path,
+            cost,
+            bound_percent: result.bound_percent,
+        })
+    }
+}
+
+struct Cancel(Arc<AtomicBool>);
+
+impl Hooks for Cancel {
+    fn interrupt(&mut self, _expanded: u32) -> Option<TerminationReason> {
+        if self.0.load(Ordering::Relaxed) {
+            Some(TerminationReason::Cancelled)
+        } else {
+            None
+        }
+    }
+}
+
+/// A worker thread improving a path, started by [`improve_in_background`].
+/// Dropping the handle cancels the worker without waiting for it.
+#[derive(Debug)]
+pub struct AnytimeHandle {
+    improvements: Receiver<Improvement>,
+    cancel: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl AnytimeHandle {
+    /// The latest improvement sent since the last call, without blocking.
+    pub fn latest(&self) -> Option<Improvement> {
+        let mut latest = None;
+        loop {
+            match self.improvements.try_recv() {
+                Ok(improvement) => latest = Some(improvement),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return latest,
+            }
+        }
+    }
+
+    /// Blocks until the next improvement, or `None` once the worker is done.
+    pub fn next(&self) -> Option<Improvement> {
+        self.improvements.recv().ok()
+    }
+
+    /// Asks the worker to stop as soon as it can.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Waits for the worker to finish, returning the last improvement it
+    /// sent that hasn't been received yet.
+    pub fn wait(mut self) -> Option<Improvement> {
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        self.latest()
+    }
+}
+
+impl Drop for AnytimeHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Returns a quick, possibly suboptimal path straight away, and a handle to
+/// a worker thread that keeps searching and sends every strictly cheaper
+/// path it finds, ending with the cheapest one.
+pub fn improve_in_background<G: GridSource + Send + Sync + 'static>(
+    start: u32,
+    end: u32,
+    grid: Arc<G>,
+    options: &SearchOptions,
+) -> (Option<Improvement>, AnytimeHandle) {
+    let mut anytime = AnytimeSearch::new(start, end, options);
+    let first = anytime.improve(&*grid);
+    let (sender, improvements) = channel();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&cancel);
+    let worker = thread::spawn(move || {
+        while !anytime.is_done() && !flag.load(Ordering::Relaxed) {
+            if let Some(improvement) = anytime.improve_with(&*grid, Cancel(Arc::clone(&flag))) {
+                if sender.send(improvement).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    (
+        first,
+        AnytimeHandle {
+            improvements,
+            cancel,
+            worker: Some(worker),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid, Rng};
+
+    fn weighted_grid() -> Grid {
+        Rng::new(2).grid(60, 60, 20, 8)
+    }
+
+    #[test]
+    fn improvements_get_cheaper_until_optimal() {
+        let grid = weighted_grid();
+        let options = SearchOptions::default();
+        let optimal = search(0, 3599, &grid, &options).cost;
+        assert!(optimal.is_some());
+        let mut anytime = AnytimeSearch::new(0, 3599, &options);
+        let mut costs = Vec::new();
+        while !anytime.is_done() {
+            if let Some(improvement) = anytime.improve(&grid) {
+                let optimal = optimal.unwrap() as u64;
+                assert!(
+                    improvement.cost as u64 * 100 <= optimal * improvement.bound_percent as u64
+                );
+                costs.push(improvement.cost);
+            }
+        }
+        assert!(costs.windows(2).all(|pair| pair[0] > pair[1]));
+        assert_eq!(costs.last().copied(), optimal);
+    }
+
+    #[test]
+    fn the_worker_reports_in_the_background() {
+        let grid = Arc::new(weighted_grid());
+        let options = SearchOptions::default();
+        let optimal = search(0, 3599, &*grid, &options).cost;
+        let (first, handle) = improve_in_background(0, 3599, Arc::clone(&grid), &options);
+        let first = first.unwrap();
+        let mut best = first.cost;
+        while let Some(improvement) = handle.next() {
+            assert!(improvement.cost < best);
+            best = improvement.cost;
+        }
+        assert_eq!(Some(best), optimal);
+        assert_eq!(handle.wait(), None);
+        let (_, handle) = improve_in_background(0, 3599, grid, &options);
+        handle.cancel();
+        handle.wait();
+    }
+}
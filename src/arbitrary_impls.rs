@@ -0,0 +1,62 @@
+//! `Arbitrary` implementations for fuzzing, enabled by the `arbitrary` feature.
+
+use crate::{Backend, Grid, SearchOptions, UnknownCells, Weighting};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Grids of up to `64 * 64` cells. Costs stay small so that path costs can be
+/// compared without overflowing, and about one cell in eight is a wall.
+impl<'a> Arbitrary<'a> for Grid {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let width = u.int_in_range(1..=64u32)?;
+        let height = u.int_in_range(1..=64u32)?;
+        let mut cells = Vec::with_capacity((width * height) as usize);
+        for _ in 0..width * height {
+            cells.push(u8::arbitrary(u)? as u32 / 32);
+        }
+        Ok(Grid::new(cells, width).expect("cells fill every row"))
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (2, Some(2 + 64 * 64))
+    }
+}
+
+impl<'a> Arbitrary<'a> for SearchOptions {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(SearchOptions {
+            cardinal_directions: bool::arbitrary(u)?,
+            record_trace: bool::arbitrary(u)?,
+            time_phases: bool::arbitrary(u)?,
+            max_expansions: Option::<u16>::arbitrary(u)?.map(u32::from),
+            max_cost: Option::<u16>::arbitrary(u)?.map(u32::from),
+            max_steps: Option::<u8>::arbitrary(u)?.map(u32::from),
+            pay_for_start: bool::arbitrary(u)?,
+            partial_path: bool::arbitrary(u)?,
+            path_budget: Option::<u16>::arbitrary(u)?.map(u32::from),
+            reverse_path: bool::arbitrary(u)?,
+            lazy_path: bool::arbitrary(u)?,
+            jitter: Option::<u64>::arbitrary(u)?,
+            weighting: match Option::<u8>::arbitrary(u)? {
+                Some(epsilon_percent) => Weighting::Dynamic {
+                    epsilon_percent: epsilon_percent.into(),
+                },
+                None => Weighting::None,
+            },
+            initial_capacity: Option::<u16>::arbitrary(u)?.map(usize::from),
+            backend: match u.int_in_range(0..=3u8)? {
+                0 => Backend::Auto,
+                1 => Backend::Dense,
+                2 => Backend::Sparse,
+                _ => Backend::Buckets,
+            },
+            canonical_ordering: bool::arbitrary(u)?,
+            unknown_cells: match u.int_in_range(0..=2u8)? {
+                0 => UnknownCells::Cost,
+                1 => UnknownCells::Open {
+                    cost: u8::arbitrary(u)?.into(),
+                },
+                _ => UnknownCells::Blocked,
+            },
+        })
+    }
+}
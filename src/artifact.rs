@@ -0,0 +1,245 @@
+//! One container for every table built ahead of time, so that bakes can
+//! ship with the rest of a game's assets.
+//!
+//! A container starts with the tag `SAA` and its version like the formats
+//! of [`crate::save_grid`], then names the artifact inside and its own
+//! version, and records [`map_hash`] of the grid it was built for. Loading
+//! checks all three, so a table built for an older version of a map is
+//! turned down with [`DecodeError::StaleMap`] instead of quietly answering
+//! for the wrong walls. Integers are little-endian.
+
+use crate::dijkstra::NO_MOVE;
+use crate::wire::{read_u32, untag};
+use crate::{map_hash, DecodeError, GridSource, DIRECTIONS};
+
+const FORMAT: &[u8; 3] = b"SAA";
+const VERSION: u8 = 1;
+/// The tag, the kind and its version, the map hash and the payload length.
+const HEADER: usize = 4 + 4 + 8 + 4;
+
+/// A table that can be stored in an artifact container with
+/// [`save_artifact`]. Implemented for everything in this crate that is
+/// built once for a static map.
+pub trait Artifact: Sized {
+    /// Three bytes naming the kind of table, distinct between kinds.
+    const KIND: [u8; 3];
+    /// Raised whenever the payload changes, so that older readers turn
+    /// down newer tables.
+    const VERSION: u8;
+
+    /// Appends the table to `bytes`.
+    fn write_payload(&self, bytes: &mut Vec<u8>);
+
+    /// Reads the table back from what [`Artifact::write_payload`] wrote,
+    /// failing with [`DecodeError::Corrupt`] on tables that couldn't have
+    /// been built.
+    fn read_payload(payload: &[u8]) -> Result<Self, DecodeError>;
+}
+
+/// Stores `artifact`, built for `grid`, in a container.
+pub fn save_artifact<A: Artifact, G: GridSource + ?Sized>(artifact: &A, grid: &G) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER);
+    bytes.extend_from_slice(FORMAT);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&A::KIND);
+    bytes.push(A::VERSION);
+    bytes.extend_from_slice(&map_hash(grid).to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    artifact.write_payload(&mut bytes);
+    let length = (bytes.len() - HEADER) as u32;
+    bytes[HEADER - 4..HEADER].copy_from_slice(&length.to_le_bytes());
+    bytes
+}
+
+/// Reads an artifact of type `A` back from [`save_artifact`], checking it
+/// was built for `grid` as it is now. Fails with
+/// [`DecodeError::WrongFormat`] when the container holds another kind of
+/// table, and [`DecodeError::StaleMap`] when `grid` has changed since.
+pub fn load_artifact<A: Artifact, G: GridSource + ?Sized>(
+    bytes: &[u8],
+    grid: &G,
+) -> Result<A, DecodeError> {
+    let body = untag(bytes, FORMAT, VERSION)?;
+    let kind = untag(body, &A::KIND, A::VERSION)?;
+    let hash = kind.get(..8).ok_or(DecodeError::Truncated)?;
+    let mut word = [0; 8];
+    word.copy_from_slice(hash);
+    let (found, expected) = (u64::from_le_bytes(word), map_hash(grid));
+    if found != expected {
+        return Err(DecodeError::StaleMap { expected, found });
+    }
+    let length = read_u32(kind, 8)? as usize;
+    let payload = kind[12..].get(..length).ok_or(DecodeError::Truncated)?;
+    A::read_payload(payload)
+}
+
+/// Appends `values`, after how many there are.
+pub(crate) fn write_u32s(bytes: &mut Vec<u8>, values: &[u32]) {
+    bytes.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    for value in values {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Appends `values`, after how many there are.
+pub(crate) fn write_u8s(bytes: &mut Vec<u8>, values: &[u8]) {
+    bytes.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(values);
+}
+
+/// Reads a payload front to back.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < count {
+            return Err(DecodeError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, DecodeError> {
+        read_u32(self.take(4)?, 0)
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, DecodeError> {
+        match self.take(1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Corrupt),
+        }
+    }
+
+    /// Values written by [`write_u32s`], of which there must be `count`.
+    pub(crate) fn u32s(&mut self, count: usize) -> Result<Vec<u32>, DecodeError> {
+        let found = self.u32()? as usize;
+        if found != count {
+            return Err(DecodeError::Corrupt);
+        }
+        let words = self.take(count.checked_mul(4).ok_or(DecodeError::Corrupt)?)?;
+        Ok(words
+            .chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect())
+    }
+
+    /// Values written by [`write_u8s`], of which there must be `count`.
+    pub(crate) fn u8s(&mut self, count: usize) -> Result<Vec<u8>, DecodeError> {
+        let found = self.u32()? as usize;
+        if found != count {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(self.take(count)?.to_vec())
+    }
+
+    /// Like [`Reader::u32s`], for values whose count the payload doesn't
+    /// otherwise give.
+    pub(crate) fn any_u32s(&mut self) -> Result<Vec<u32>, DecodeError> {
+        let count = read_u32(self.bytes, 0)? as usize;
+        self.u32s(count)
+    }
+
+    /// Fails unless the whole payload was read.
+    pub(crate) fn finish(self) -> Result<(), DecodeError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::Corrupt)
+        }
+    }
+}
+
+/// The number of cells of a `width` by `height` table, failing on sizes no
+/// grid could have.
+pub(crate) fn cells(width: u32, height: u32) -> Result<usize, DecodeError> {
+    let len = width as u64 * height as u64;
+    if len > u32::MAX as u64 {
+        return Err(DecodeError::Corrupt);
+    }
+    Ok(len as usize)
+}
+
+/// Whether `first` is [`NO_MOVE`], or one of the eight directions that
+/// keeps a step from `cell` on a `width` by `height` grid.
+pub(crate) fn valid_move(cell: u32, first: u8, width: u32, height: u32) -> bool {
+    if first >= NO_MOVE {
+        return first == NO_MOVE;
+    }
+    let (x, y) = ((cell % width) as i64, (cell / width) as i64);
+    let (dx, dy) = DIRECTIONS[first as usize];
+    let (x, y) = (x + dx as i64, y + dy as i64);
+    x >= 0 && y >= 0 && x < width as i64 && y < height as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AllPairs, CompressedPathDatabase, FirstMoveTable, FlowField, GoalBounds, Grid,
+        PivotHeuristic, Regions,
+    };
+
+    fn round_trip<A: Artifact + PartialEq + std::fmt::Debug>(artifact: A, grid: &Grid) {
+        let bytes = save_artifact(&artifact, grid);
+        assert_eq!(load_artifact::<A, _>(&bytes, grid), Ok(artifact));
+        assert_eq!(
+            load_artifact::<A, _>(&bytes[..bytes.len() - 1], grid),
+            Err(DecodeError::Truncated)
+        );
+    }
+
+    #[test]
+    fn every_bake_round_trips() {
+        let mut grid = Grid::filled(7, 5, 1).unwrap();
+        grid.set(3, 1, 0);
+        grid.set(3, 2, 0);
+        grid.set(5, 4, 3);
+        round_trip(AllPairs::new(&grid, false), &grid);
+        round_trip(CompressedPathDatabase::new(&grid, true), &grid);
+        round_trip(FirstMoveTable::new(&grid, 4, false), &grid);
+        round_trip(FlowField::new(&grid, &[4, 30], true), &grid);
+        round_trip(GoalBounds::new(&grid, false), &grid);
+        round_trip(PivotHeuristic::new(&grid, 3, true, 5), &grid);
+        round_trip(Regions::new(&grid, true), &grid);
+    }
+
+    #[test]
+    fn stale_and_mismatched_bakes_are_turned_down() {
+        let mut grid = Grid::filled(6, 6, 1).unwrap();
+        let bytes = save_artifact(&Regions::new(&grid, false), &grid);
+        let expected = map_hash(&grid);
+        grid.set(2, 2, 0);
+        assert_ne!(map_hash(&grid), expected);
+        assert_eq!(
+            load_artifact::<Regions, _>(&bytes, &grid),
+            Err(DecodeError::StaleMap {
+                expected: map_hash(&grid),
+                found: expected,
+            })
+        );
+        grid.set(2, 2, 1);
+        assert!(load_artifact::<Regions, _>(&bytes, &grid).is_ok());
+        assert_eq!(
+            load_artifact::<AllPairs, _>(&bytes, &grid),
+            Err(DecodeError::WrongFormat)
+        );
+        let mut newer = bytes.clone();
+        newer[7] += 1;
+        assert_eq!(
+            load_artifact::<Regions, _>(&newer, &grid),
+            Err(DecodeError::UnsupportedVersion { version: newer[7] })
+        );
+        // the same size and costs hash the same however they are held
+        let view = Grid::filled(8, 8, 1).unwrap();
+        assert_eq!(map_hash(&view.view(1, 1, 6, 6).unwrap()), expected);
+        assert_ne!(map_hash(&Grid::filled(4, 9, 1).unwrap()), expected);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{search, DijkstraMap, GridSource, Path, SearchOptions};
+
+/// One worker sent to one task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    /// The position of the worker in the slice given to [`assign_jobs`].
+    pub worker: usize,
+    /// The position of the task in the slice given to [`assign_jobs`].
+    pub task: usize,
+    /// The cheapest path from the worker to the task.
+    pub path: Path,
+}
+
+/// Which workers take which tasks, from [`assign_jobs`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Assignment {
+    /// The jobs in worker order. Workers and tasks left over are in none.
+    pub jobs: Vec<Job>,
+    /// What every path together costs.
+    pub cost: u32,
+}
+
+/// Sends workers at cells `workers` to tasks at cells `tasks`, one each,
+/// so that as many tasks as possible are taken and the paths walked there
+/// cost as little as possible in total, such as for handing out jobs in a
+/// colony sim.
+///
+/// The costs between every worker and task come from a [`DijkstraMap`] per
+/// worker, and the Hungarian algorithm picks the best matching among them
+/// in `O(n² * m)` for `n` workers and `m` tasks, whichever is fewer. When
+/// there are more of one than the other, or some can't reach each other,
+/// the rest are left out of the assignment. Each job then gets its path
+/// from a search, which costs what the map said.
+pub fn assign_jobs<G: GridSource + ?Sized>(
+    workers: &[u32],
+    tasks: &[u32],
+    grid: &G,
+    cardinal_directions: bool,
+) -> Assignment {
+    let costs: Vec<Vec<Option<u32>>> = workers
+        .iter()
+        .map(|&worker| {
+            let map = DijkstraMap::new(grid, &[worker], cardinal_directions);
+            // a worker outside the grid measures from nowhere
+            tasks
+                .iter()
+                .map(|&task| map.distance(task).filter(|_| worker < grid.len()))
+                .collect()
+        })
+        .collect();
+    let options = SearchOptions {
+        cardinal_directions,
+        ..SearchOptions::default()
+    };
+    let mut assignment = Assignment::default();
+    for (worker, task) in best_matching(&costs) {
+        let (from, to) = (workers[worker], tasks[task]);
+        let path = Path::new(grid, from, search(from, to, grid, &options).path);
+        assignment.cost = assignment.cost.saturating_add(path.cost());
+        assignment.jobs.push(Job { worker, task, path });
+    }
+    assignment
+}
+
+/// The (row, column) pairs of the cheapest matching of as many rows to
+/// columns as can be matched through `Some` costs, in row order.
+fn best_matching(costs: &[Vec<Option<u32>>]) -> Vec<(usize, usize)> {
+    let rows = costs.len();
+    let columns = costs.first().map_or(0, |row| row.len());
+    if rows == 0 || columns == 0 {
+        return Vec::new();
+    }
+    // the algorithm wants no more rows than columns
+    let transposed = rows > columns;
+    let (n, m) = if transposed {
+        (columns, rows)
+    } else {
+        (rows, columns)
+    };
+    let cost_at = |i: usize, j: usize| {
+        if transposed {
+            costs[j][i]
+        } else {
+            costs[i][j]
+        }
+    };
+    // a missing pair costs more than any matching without one, so that
+    // the most pairs get matched before cost is looked at
+    let finite: i64 = (0..n)
+        .flat_map(|i| (0..m).map(move |j| (i, j)))
+        .filter_map(|(i, j)| cost_at(i, j))
+        .map(i64::from)
+        .max()
+        .unwrap_or(0);
+    let missing = (finite + 1) * (n as i64 + 1);
+    let cell = |i: usize, j: usize| cost_at(i, j).map_or(missing, i64::from);
+
+    // potentials and matches are kept 1-based, with 0 standing for none
+    let mut u = vec![0i64; n + 1];
+    let mut v = vec![0i64; m + 1];
+    let mut row_of = vec![0usize; m + 1];
+    let mut way = vec![0usize; m + 1];
+    for i in 1..=n {
+        row_of[0] = i;
+        let mut column = 0;
+        let mut min = vec![i64::MAX; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[column] = true;
+            let row = row_of[column];
+            let mut delta = i64::MAX;
+            let mut next = 0;
+            for j in 1..=m {
+                if used[j] {
+                    continue;
+                }
+                let reduced = cell(row - 1, j - 1) - u[row] - v[j];
+                if reduced < min[j] {
+                    min[j] = reduced;
+                    way[j] = column;
+                }
+                if min[j] < delta {
+                    delta = min[j];
+                    next = j;
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[row_of[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min[j] -= delta;
+                }
+            }
+            column = next;
+            if row_of[column] == 0 {
+                break;
+            }
+        }
+        while column != 0 {
+            let previous = way[column];
+            row_of[column] = row_of[previous];
+            column = previous;
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = (1..=m)
+        .filter(|&j| row_of[j] != 0 && cost_at(row_of[j] - 1, j - 1).is_some())
+        .map(|j| {
+            let (i, j) = (row_of[j] - 1, j - 1);
+            if transposed {
+                (j, i)
+            } else {
+                (i, j)
+            }
+        })
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid, Rng};
+
+    /// Every way of matching rows to columns, for checking against.
+    fn brute_force(costs: &[Vec<Option<u32>>], row: usize, taken: &mut Vec<bool>) -> (u32, u32) {
+        if row == costs.len() {
+            return (0, 0);
+        }
+        let (mut best_pairs, mut best_cost) = brute_force(costs, row + 1, taken);
+        for column in 0..taken.len() {
+            if let (false, Some(cost)) = (taken[column], costs[row][column]) {
+                taken[column] = true;
+                let (pairs, rest) = brute_force(costs, row + 1, taken);
+                taken[column] = false;
+                let (pairs, total) = (pairs + 1, rest + cost);
+                if pairs > best_pairs || pairs == best_pairs && total < best_cost {
+                    best_pairs = pairs;
+                    best_cost = total;
+                }
+            }
+        }
+        (best_pairs, best_cost)
+    }
+
+    #[test]
+    fn it_finds_the_cheapest_matching() {
+        let mut rng = Rng::new(1);
+        for _ in 0..300 {
+            let (rows, columns) = (rng.range(1, 5), rng.range(1, 5));
+            let costs: Vec<Vec<Option<u32>>> = (0..rows)
+                .map(|_| {
+                    (0..columns)
+                        .map(|_| {
+                            if rng.chance(1, 4) {
+                                None
+                            } else {
+                                Some(rng.range(0, 20))
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            let pairs = best_matching(&costs);
+            let total: u32 = pairs.iter().map(|&(i, j)| costs[i][j].unwrap()).sum();
+            let expected = brute_force(&costs, 0, &mut vec![false; columns as usize]);
+            assert_eq!((pairs.len() as u32, total), expected);
+        }
+    }
+
+    #[test]
+    fn it_sends_workers_along_their_paths() {
+        let grid = grid![1, 1, 1, 1, 1, 1, 0, 1;];
+        // handing out the cheapest pair first would send the worker at 3 to
+        // 2, leaving the one at 0 the long walk to 5
+        let assignment = assign_jobs(&[0, 3], &[2, 5, 7], &grid, true);
+        let jobs: Vec<(usize, usize)> = assignment
+            .jobs
+            .iter()
+            .map(|job| (job.worker, job.task))
+            .collect();
+        assert_eq!(jobs, vec![(0, 0), (1, 1)]);
+        assert_eq!(assignment.jobs[1].path.cells, vec![4, 5]);
+        assert_eq!(assignment.cost, 8);
+        assert_eq!(assign_jobs(&[0], &[7], &grid, true), Assignment::default());
+    }
+}
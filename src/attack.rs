@@ -0,0 +1,132 @@
+use crate::{line_of_sight, reachable_within, GridSource};
+
+/// How the distance an attack reaches is measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RangeMetric {
+    /// Steps along the axes, making the range a diamond.
+    Manhattan,
+    /// Steps in any of the eight directions, making the range a square.
+    #[default]
+    Chebyshev,
+    /// Distance as the crow flies, making the range a disc, as
+    /// [`crate::field_of_view`] measures it.
+    Euclidean,
+}
+
+impl RangeMetric {
+    /// Whether `to` lies at most `range` cells from `from` by this metric.
+    pub fn within(&self, from: (u32, u32), to: (u32, u32), range: u32) -> bool {
+        let dx = from.0.abs_diff(to.0) as u64;
+        let dy = from.1.abs_diff(to.1) as u64;
+        let range = range as u64;
+        match self {
+            RangeMetric::Manhattan => dx + dy <= range,
+            RangeMetric::Chebyshev => dx.max(dy) <= range,
+            RangeMetric::Euclidean => dx * dx + dy * dy <= range * range,
+        }
+    }
+}
+
+/// What an attack can hit: cells up to `range` away by `metric`, and only
+/// those in [`crate::line_of_sight`] unless `line_of_sight` is off, as for
+/// artillery firing over walls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttackRange {
+    pub range: u32,
+    pub metric: RangeMetric,
+    pub line_of_sight: bool,
+}
+
+impl AttackRange {
+    /// An attack up to `range` away by `metric` that walls block.
+    pub fn new(range: u32, metric: RangeMetric) -> Self {
+        AttackRange {
+            range,
+            metric,
+            line_of_sight: true,
+        }
+    }
+
+    /// The same attack, passing over walls.
+    pub fn over_walls(self) -> Self {
+        AttackRange {
+            line_of_sight: false,
+            ..self
+        }
+    }
+
+    /// Whether an attacker at cell `from` of `grid` can hit cell `to`.
+    pub fn covers<G: GridSource + ?Sized>(&self, grid: &G, from: u32, to: u32) -> bool {
+        let width = grid.width();
+        let (from, to) = ((from % width, from / width), (to % width, to / width));
+        self.metric.within(from, to, self.range)
+            && (!self.line_of_sight || line_of_sight(grid, from, to))
+    }
+}
+
+/// Every cell a unit at `start` can move to spending at most `budget` and
+/// hit `target` from with `attack`, along with what moving there costs,
+/// cheapest first in the order of [`reachable_within`]. Staying put comes
+/// first, at `0`, when `target` is already in range. Empty when nowhere in
+/// reach is, or either cell lies outside the grid.
+///
+/// Target cells that are walls can still be attacked, as line of sight
+/// doesn't check its ends, so this also finds where to stand to hit a
+/// door or a barricade.
+pub fn attack_positions<G: GridSource + ?Sized>(
+    start: u32,
+    budget: u32,
+    target: u32,
+    attack: &AttackRange,
+    grid: &G,
+    cardinal_directions: bool,
+) -> Vec<(u32, u32)> {
+    if target >= grid.len() {
+        return Vec::new();
+    }
+    let mut positions = reachable_within(start, budget, grid, cardinal_directions);
+    positions.retain(|&(cell, _)| attack.covers(grid, cell, target));
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid;
+
+    #[test]
+    fn metrics_shape_the_range() {
+        assert!(RangeMetric::Manhattan.within((0, 0), (1, 1), 2));
+        assert!(!RangeMetric::Manhattan.within((0, 0), (2, 1), 2));
+        assert!(RangeMetric::Chebyshev.within((0, 0), (2, 2), 2));
+        assert!(!RangeMetric::Euclidean.within((0, 0), (2, 2), 2));
+        assert!(RangeMetric::Euclidean.within((3, 3), (3, 1), 2));
+    }
+
+    #[test]
+    fn it_finds_where_to_stand() {
+        let grid = grid![
+            1, 1, 1, 1, 1;
+            1, 1, 0, 1, 1;
+            1, 1, 0, 1, 1;
+        ];
+        // the wall hides the target from everywhere left of it but the top
+        // row, which takes the whole budget to reach
+        let bow = AttackRange::new(2, RangeMetric::Chebyshev);
+        assert_eq!(attack_positions(10, 8, 13, &bow, &grid, true), vec![(2, 8)]);
+        assert_eq!(attack_positions(10, 6, 13, &bow, &grid, true), Vec::new());
+        assert!(!bow.covers(&grid, 11, 13));
+        let mortar = bow.over_walls();
+        assert_eq!(
+            attack_positions(10, 8, 13, &mortar, &grid, true),
+            vec![(11, 2), (6, 4), (1, 6), (2, 8)]
+        );
+        assert_eq!(
+            attack_positions(11, 8, 13, &mortar, &grid, true)[0],
+            (11, 0)
+        );
+        assert_eq!(attack_positions(10, 8, 15, &bow, &grid, true), Vec::new());
+    }
+}
@@ -0,0 +1,342 @@
+//! Baking tables ahead of time from a build script or an asset pipeline,
+//! enabled by the `bake` feature, so that nothing has to be precomputed
+//! when the game runs.
+//!
+//! A [`Bake`] reads a map, runs the preprocessors asked for, and writes
+//! each table in an [artifact container](crate::save_artifact) next to the
+//! map, along with a Rust file that embeds them all with `include_bytes!`
+//! and has a function to load each:
+//!
+//! ```text
+//! // build.rs
+//! let out = std::env::var("OUT_DIR").unwrap();
+//! Bake::load("assets/keep.csv", false)?
+//!     .with("regions", Preprocessor::Regions)
+//!     .with("routes", Preprocessor::CompressedPathDatabase)
+//!     .write(&out, "keep")?;
+//! println!("cargo:rerun-if-changed=assets/keep.csv");
+//!
+//! // src/main.rs
+//! mod keep {
+//!     include!(concat!(env!("OUT_DIR"), "/keep.rs"));
+//! }
+//! let map = keep::map()?;
+//! let routes = keep::routes(&map)?;
+//! ```
+//!
+//! Maps are read as CSV when their file name ends in `.csv`, and in the
+//! format of [`crate::save_grid`] otherwise.
+
+use crate::{
+    grid_from_csv, load_grid, save_artifact, save_grid, AllPairs, CompressedPathDatabase, CsvError,
+    DecodeError, FirstMoveTable, FlowField, GoalBounds, Grid, PivotHeuristic, Regions,
+};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A table a [`Bake`] can build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Preprocessor {
+    AllPairs,
+    CompressedPathDatabase,
+    /// A [`FirstMoveTable`] toward one goal.
+    FirstMoves {
+        goal: u32,
+    },
+    /// A [`FlowField`] toward the nearest of some goals.
+    FlowField {
+        goals: Vec<u32>,
+    },
+    GoalBounds,
+    /// A [`PivotHeuristic`] from `count` pivots picked with `seed`.
+    Pivots {
+        count: usize,
+        seed: u64,
+    },
+    Regions,
+}
+
+#[derive(Debug)]
+pub enum BakeError {
+    /// A file couldn't be read or written.
+    Io(io::Error),
+    /// The map isn't in the format of [`crate::save_grid`].
+    Decode(DecodeError),
+    /// The map isn't valid CSV.
+    Csv(CsvError),
+    /// A table's name isn't a lowercase Rust identifier, is a keyword or
+    /// `map`, or is used twice. Names become files and functions of the
+    /// loader.
+    BadName { name: String },
+}
+
+impl fmt::Display for BakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BakeError::Io(error) => write!(f, "couldn't bake: {}", error),
+            BakeError::Decode(error) => write!(f, "invalid map: {}", error),
+            BakeError::Csv(error) => write!(f, "invalid map: {}", error),
+            BakeError::BadName { name } => write!(f, "{:?} can't name a baked table", name),
+        }
+    }
+}
+
+impl Error for BakeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BakeError::Io(error) => Some(error),
+            BakeError::Decode(error) => Some(error),
+            BakeError::Csv(error) => Some(error),
+            BakeError::BadName { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for BakeError {
+    fn from(error: io::Error) -> Self {
+        BakeError::Io(error)
+    }
+}
+
+impl From<DecodeError> for BakeError {
+    fn from(error: DecodeError) -> Self {
+        BakeError::Decode(error)
+    }
+}
+
+impl From<CsvError> for BakeError {
+    fn from(error: CsvError) -> Self {
+        BakeError::Csv(error)
+    }
+}
+
+/// The tables to build for one map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bake {
+    grid: Grid<u32>,
+    cardinal_directions: bool,
+    preprocessors: Vec<(String, Preprocessor)>,
+}
+
+impl Bake {
+    /// A bake of `grid`, whose tables are all built for the one movement
+    /// mode.
+    pub fn new(grid: Grid<u32>, cardinal_directions: bool) -> Self {
+        Bake {
+            grid,
+            cardinal_directions,
+            preprocessors: Vec::new(),
+        }
+    }
+
+    /// A bake of the map at `path`.
+    pub fn load<P: AsRef<Path>>(path: P, cardinal_directions: bool) -> Result<Self, BakeError> {
+        let path = path.as_ref();
+        let grid = if path.extension().is_some_and(|extension| extension == "csv") {
+            grid_from_csv(&fs::read_to_string(path)?)?
+        } else {
+            load_grid(&fs::read(path)?)?
+        };
+        Ok(Bake::new(grid, cardinal_directions))
+    }
+
+    /// Adds a table to build, under `name`.
+    pub fn with(mut self, name: &str, preprocessor: Preprocessor) -> Self {
+        self.preprocessors.push((name.to_string(), preprocessor));
+        self
+    }
+
+    pub fn grid(&self) -> &Grid<u32> {
+        &self.grid
+    }
+
+    /// Builds every table, returning each under its name in the order they
+    /// were added, already in its container.
+    pub fn run(&self) -> Result<Vec<(String, Vec<u8>)>, BakeError> {
+        for (index, (name, _)) in self.preprocessors.iter().enumerate() {
+            let taken = self.preprocessors[..index]
+                .iter()
+                .any(|(other, _)| other == name);
+            if taken || name == "map" || !is_identifier(name) {
+                return Err(BakeError::BadName { name: name.clone() });
+            }
+        }
+        let (grid, cardinal) = (&self.grid, self.cardinal_directions);
+        Ok(self
+            .preprocessors
+            .iter()
+            .map(|(name, preprocessor)| {
+                let bytes = match preprocessor {
+                    Preprocessor::AllPairs => save_artifact(&AllPairs::new(grid, cardinal), grid),
+                    Preprocessor::CompressedPathDatabase => {
+                        save_artifact(&CompressedPathDatabase::new(grid, cardinal), grid)
+                    }
+                    Preprocessor::FirstMoves { goal } => {
+                        save_artifact(&FirstMoveTable::new(grid, *goal, cardinal), grid)
+                    }
+                    Preprocessor::FlowField { goals } => {
+                        save_artifact(&FlowField::new(grid, goals, cardinal), grid)
+                    }
+                    Preprocessor::GoalBounds => {
+                        save_artifact(&GoalBounds::new(grid, cardinal), grid)
+                    }
+                    Preprocessor::Pivots { count, seed } => {
+                        save_artifact(&PivotHeuristic::new(grid, *count, cardinal, *seed), grid)
+                    }
+                    Preprocessor::Regions => save_artifact(&Regions::new(grid, cardinal), grid),
+                };
+                (name.clone(), bytes)
+            })
+            .collect())
+    }
+
+    /// Builds every table and writes it to `dir` as `<module>_<name>.bin`,
+    /// with the map as `<module>_map.bin` and the loader as `<module>.rs`,
+    /// whose path this returns. The loader has `map()`, and a function for
+    /// each table named after it that takes the map and checks the table
+    /// was built for it.
+    pub fn write<P: AsRef<Path>>(&self, dir: P, module: &str) -> Result<PathBuf, BakeError> {
+        if !is_identifier(module) {
+            return Err(BakeError::BadName {
+                name: module.to_string(),
+            });
+        }
+        // nothing is written unless every table can be
+        let tables = self.run()?;
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        // loaders are included from elsewhere, so they embed absolute paths
+        let dir = dir.canonicalize()?;
+        let embed = |name: &str, bytes: &[u8]| -> Result<String, BakeError> {
+            let path = dir.join(format!("{}_{}.bin", module, name));
+            fs::write(&path, bytes)?;
+            Ok(format!(
+                "#[allow(dead_code)]\npub static {}: &[u8] = include_bytes!({:?});\n",
+                name.to_uppercase(),
+                path.display().to_string()
+            ))
+        };
+        let mut loader = format!(
+            "// Tables baked by simple_astar for a {} by {} map.\n\n",
+            self.grid.width(),
+            self.grid.height()
+        );
+        loader += &embed("map", &save_grid(&self.grid))?;
+        loader +=
+            "\n#[allow(dead_code)]\npub fn map() -> Result<simple_astar::Grid<u32>, simple_astar::DecodeError> {\n";
+        loader += "    simple_astar::load_grid(MAP)\n}\n";
+        for ((name, bytes), (_, preprocessor)) in tables.iter().zip(&self.preprocessors) {
+            loader += "\n";
+            loader += &embed(name, bytes)?;
+            loader += &format!(
+                "\n#[allow(dead_code)]\npub fn {}<G: simple_astar::GridSource + ?Sized>(\n    grid: &G,\n) -> \
+                 Result<simple_astar::{}, simple_astar::DecodeError> {{\n    \
+                 simple_astar::load_artifact({}, grid)\n}}\n",
+                name,
+                type_name(preprocessor),
+                name.to_uppercase()
+            );
+        }
+        let path = dir.join(format!("{}.rs", module));
+        fs::write(&path, loader)?;
+        Ok(path)
+    }
+}
+
+fn type_name(preprocessor: &Preprocessor) -> &'static str {
+    match preprocessor {
+        Preprocessor::AllPairs => "AllPairs",
+        Preprocessor::CompressedPathDatabase => "CompressedPathDatabase",
+        Preprocessor::FirstMoves { .. } => "FirstMoveTable",
+        Preprocessor::FlowField { .. } => "FlowField",
+        Preprocessor::GoalBounds => "GoalBounds",
+        Preprocessor::Pivots { .. } => "PivotHeuristic",
+        Preprocessor::Regions => "Regions",
+    }
+}
+
+/// Rust's strict and reserved keywords, which can't name an item.
+const KEYWORDS: &[&str] = &[
+    "_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Whether `name` is made of lowercase letters, digits and underscores,
+/// doesn't start with a digit, and isn't a keyword.
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid_to_csv, load_artifact};
+
+    #[test]
+    fn bakes_are_written_with_their_loader() {
+        let dir = std::env::temp_dir().join(format!("simple_astar_bake_{}", std::process::id()));
+        let mut grid = Grid::filled(6, 4, 1).unwrap();
+        grid.set(2, 1, 0);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("keep.csv"), grid_to_csv(&grid)).unwrap();
+        let bake = Bake::load(dir.join("keep.csv"), true)
+            .unwrap()
+            .with("regions", Preprocessor::Regions)
+            .with("gate", Preprocessor::FlowField { goals: vec![5] })
+            .with("hints", Preprocessor::Pivots { count: 2, seed: 1 });
+        assert_eq!(bake.grid(), &grid);
+        let loader = bake.write(dir.join("out"), "keep").unwrap();
+        let source = fs::read_to_string(&loader).unwrap();
+        assert!(source.contains("pub fn gate<G: simple_astar::GridSource + ?Sized>("));
+        assert!(source.contains("Result<simple_astar::Regions, simple_astar::DecodeError>"));
+        let map = load_grid(&fs::read(dir.join("out/keep_map.bin")).unwrap()).unwrap();
+        assert_eq!(map, grid);
+        let gate = fs::read(dir.join("out/keep_gate.bin")).unwrap();
+        assert_eq!(
+            load_artifact::<FlowField, _>(&gate, &map),
+            Ok(FlowField::new(&grid, &[5], true))
+        );
+        let hints = fs::read(dir.join("out/keep_hints.bin")).unwrap();
+        assert_eq!(
+            load_artifact::<PivotHeuristic, _>(&hints, &map),
+            Ok(PivotHeuristic::new(&grid, 2, true, 1))
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tables_need_names_of_their_own() {
+        let bake = Bake::new(Grid::filled(2, 2, 1).unwrap(), false);
+        for name in &["map", "Routes", "2nd", "", "type", "self", "_", "async"] {
+            let named = bake.clone().with(name, Preprocessor::AllPairs);
+            assert!(matches!(named.run(), Err(BakeError::BadName { .. })));
+        }
+        let twice = bake
+            .clone()
+            .with("routes", Preprocessor::AllPairs)
+            .with("routes", Preprocessor::GoalBounds);
+        assert!(matches!(twice.run(), Err(BakeError::BadName { name }) if name == "routes"));
+        // bad names leave nothing behind
+        let dir = std::env::temp_dir().join(format!("simple_astar_unbaked_{}", std::process::id()));
+        let keyword = bake.with("fn", Preprocessor::Regions);
+        assert!(
+            matches!(keyword.write(&dir, "keep"), Err(BakeError::BadName { name }) if name == "fn")
+        );
+        assert!(!dir.exists());
+        assert!(matches!(
+            keyword.write(&dir, "match"),
+            Err(BakeError::BadName { .. })
+        ));
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{
+    get_neighbor_coords, step_cost, FrontierItem, GridSource, Heuristic, Manhattan, Path, WALL,
+};
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::thread;
+
+const UNSEEN: u32 = u32::MAX;
+/// The best meeting so far, packed as its cost above the cell it was made
+/// in so that both halves can lower it with a single `fetch_min`.
+const NO_MEETING: u64 = u64::MAX;
+
+/// How [`bidirectional_search`] runs its two halves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Halves {
+    /// Take turns expanding a cell from each end on the calling thread.
+    Alternating,
+    /// Run the half from the start on the calling thread and the half from
+    /// the end on another, which only pays for spawning the thread on long
+    /// searches. Of several equally cheap paths, which one is returned can
+    /// differ between runs.
+    Parallel,
+}
+
+/// Searches from both ends at once and stops when the halves have met on a
+/// cheapest path, costing the same as one from [`crate::search`]. Returns
+/// `None` when `end` can't be reached.
+pub fn bidirectional_search<G: GridSource + Sync + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    cardinal_directions: bool,
+    halves: Halves,
+) -> Option<Path> {
+    if start >= grid.len() || end >= grid.len() {
+        return None;
+    }
+    if start == end {
+        return Some(Path::new(grid, start, Vec::new()));
+    }
+    let forward_costs = unseen(grid.len());
+    let backward_costs = unseen(grid.len());
+    let best = AtomicU64::new(NO_MEETING);
+    let mut forward = Half::new(
+        grid,
+        cardinal_directions,
+        true,
+        start,
+        end,
+        &forward_costs,
+        &backward_costs,
+    );
+    let mut backward = Half::new(
+        grid,
+        cardinal_directions,
+        false,
+        end,
+        start,
+        &backward_costs,
+        &forward_costs,
+    );
+
+    match halves {
+        Halves::Alternating => while forward.step(&best) && backward.step(&best) {},
+        Halves::Parallel => {
+            let done = AtomicBool::new(false);
+            // either half alone proves the best meeting cheapest once its
+            // frontier can't beat it, so the first to finish stops both. A
+            // half that runs out of cells before any meeting stops only
+            // itself: the end's half never steps into a walled start, so it
+            // can finish before the start's half has left it
+            let run = |half: &mut Half<'_, G>| {
+                while !done.load(Ordering::Relaxed) {
+                    if !half.step(&best) {
+                        if best.load(Ordering::Relaxed) != NO_MEETING {
+                            done.store(true, Ordering::Relaxed);
+                        }
+                        break;
+                    }
+                }
+            };
+            thread::scope(|scope| {
+                let worker = scope.spawn(|| run(&mut backward));
+                run(&mut forward);
+                worker.join().unwrap();
+            });
+        }
+    }
+
+    let best = best.into_inner();
+    if best == NO_MEETING {
+        return None;
+    }
+    let meeting = best as u32;
+    let mut cells = Vec::new();
+    let mut cell = meeting;
+    while cell != start {
+        cells.push(cell);
+        cell = forward.came_from[cell as usize];
+    }
+    cells.reverse();
+    let mut cell = meeting;
+    while cell != end {
+        cell = backward.came_from[cell as usize];
+        cells.push(cell);
+    }
+    Some(Path::new(grid, start, cells))
+}
+
+fn unseen(len: u32) -> Vec<AtomicU32> {
+    (0..len).map(|_| AtomicU32::new(UNSEEN)).collect()
+}
+
+/// One direction of the search. The half from the end measures what it
+/// costs to get from each cell to the end, so both halves' costs add up to
+/// the cost of a whole path where they meet.
+struct Half<'a, G: ?Sized> {
+    grid: &'a G,
+    cardinal_directions: bool,
+    forward: bool,
+    /// The other end, which the heuristic aims at.
+    target: u32,
+    frontier: BinaryHeap<FrontierItem>,
+    /// Read by the other half while this one runs, so that it can notice
+    /// where they meet.
+    costs: &'a [AtomicU32],
+    other_costs: &'a [AtomicU32],
+    came_from: Vec<u32>,
+}
+
+impl<'a, G: GridSource + ?Sized> Half<'a, G> {
+    fn new(
+        grid: &'a G,
+        cardinal_directions: bool,
+        forward: bool,
+        source: u32,
+        target: u32,
+        costs: &'a [AtomicU32],
+        other_costs: &'a [AtomicU32],
+    ) -> Self {
+        costs[source as usize].store(0, Ordering::Relaxed);
+        let mut half = Half {
+            grid,
+            cardinal_directions,
+            forward,
+            target,
+            frontier: BinaryHeap::new(),
+            costs,
+            other_costs,
+            came_from: vec![UNSEEN; grid.len() as usize],
+        };
+        half.frontier.push(FrontierItem {
+            position: source,
+            cost: half.estimate(source),
+        });
+        half
+    }
+
+    fn estimate(&self, cell: u32) -> u32 {
+        Manhattan.estimate(cell, self.target, self.grid.width())
+    }
+
+    /// Expands the next cell. Returns false once this half can't find a
+    /// path cheaper than the best meeting, or any path at all.
+    fn step(&mut self, best: &AtomicU64) -> bool {
+        while let Some(&FrontierItem { position, cost }) = self.frontier.peek() {
+            if cost as u64 >= best.load(Ordering::Relaxed) >> 32 {
+                return false;
+            }
+            self.frontier.pop();
+            let so_far = self.costs[position as usize].load(Ordering::Relaxed);
+            if so_far.saturating_add(self.estimate(position)) < cost {
+                continue;
+            }
+            // nothing can step into a wall, so the end's half mustn't
+            // grow out of one
+            if !self.forward && self.grid.cost(position) == WALL {
+                continue;
+            }
+            for &neighbor in
+                get_neighbor_coords(position, self.grid, self.cardinal_directions).iter()
+            {
+                let step = if self.forward {
+                    step_cost(self.grid, position, neighbor)
+                } else {
+                    step_cost(self.grid, neighbor, position)
+                };
+                let next = so_far.saturating_add(step);
+                if next >= self.costs[neighbor as usize].load(Ordering::Relaxed) {
+                    continue;
+                }
+                self.costs[neighbor as usize].store(next, Ordering::Relaxed);
+                self.came_from[neighbor as usize] = position;
+                self.frontier.push(FrontierItem {
+                    position: neighbor,
+                    cost: next.saturating_add(self.estimate(neighbor)),
+                });
+                // a stale read only misses this meeting; the other half
+                // finds it again when it reaches the cell, and either half
+                // finds the goal on its own
+                let other = self.other_costs[neighbor as usize].load(Ordering::Relaxed);
+                if other != UNSEEN {
+                    let total = next.saturating_add(other) as u64;
+                    best.fetch_min(total << 32 | neighbor as u64, Ordering::Relaxed);
+                }
+            }
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid, search, Rng, SearchOptions};
+
+    #[test]
+    fn costs_what_a_search_does() {
+        let mut rng = Rng::new(1);
+        for _ in 0..20 {
+            let grid = rng.grid(24, 18, 25, 5);
+            for &cardinal in &[false, true] {
+                let start = rng.range(0, grid.len());
+                let end = rng.range(0, grid.len());
+                let options = SearchOptions {
+                    cardinal_directions: cardinal,
+                    ..SearchOptions::default()
+                };
+                let expected = search(start, end, &grid, &options).cost;
+                for &halves in &[Halves::Alternating, Halves::Parallel] {
+                    let path = bidirectional_search(start, end, &grid, cardinal, halves);
+                    assert_eq!(path.as_ref().map(Path::cost), expected);
+                    if let Some(path) = path {
+                        assert_eq!(path.cells.last().copied().unwrap_or(start), end);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn walled_off_ends_have_no_path() {
+        let grid = grid![
+            1, 0, 1;
+            1, 0, 1;
+            1, 0, 1;
+        ];
+        for &halves in &[Halves::Alternating, Halves::Parallel] {
+            assert_eq!(bidirectional_search(0, 8, &grid, false, halves), None);
+            assert_eq!(bidirectional_search(0, 4, &grid, false, halves), None);
+        }
+    }
+
+    #[test]
+    fn it_steps_out_of_walled_starts() {
+        let grid = grid![
+            0, 1, 1;
+            1, 1, 1;
+        ];
+        let expected = search(0, 5, &grid, &SearchOptions::default()).cost;
+        assert!(expected.is_some());
+        for _ in 0..200 {
+            for &halves in &[Halves::Alternating, Halves::Parallel] {
+                let path = bidirectional_search(0, 5, &grid, false, halves);
+                assert_eq!(path.as_ref().map(Path::cost), expected);
+            }
+        }
+    }
+}
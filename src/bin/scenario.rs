@@ -0,0 +1,80 @@
+//! Runs scenario files and reports the queries that don't cost what they
+//! should, along with how long each scenario took.
+//!
+//! ```text
+//! simple_astar_scenario [--record] <scenario file>...
+//! ```
+//!
+//! See `simple_astar::scenario` for the format. With `--record`, every
+//! expected cost is replaced by the one found and the files are written
+//! back, for starting a new scenario or accepting an intended change. The
+//! exit status is `1` when any query mismatched and `2` when a scenario
+//! couldn't be run.
+
+use simple_astar::scenario::Scenario;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+fn main() {
+    let mut record = false;
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        if arg == "--record" {
+            record = true;
+        } else {
+            paths.push(arg);
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("usage: simple_astar_scenario [--record] <scenario file>...");
+        process::exit(2);
+    }
+    let mut mismatched = false;
+    for path in &paths {
+        let (mut scenario, grid) = match Scenario::load(path) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                process::exit(2);
+            }
+        };
+        let started = Instant::now();
+        let mismatches = match scenario.run(&grid) {
+            Ok(mismatches) => mismatches,
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                process::exit(2);
+            }
+        };
+        println!(
+            "{}: {} queries in {:.2?}, {} mismatched",
+            path,
+            scenario.queries.len(),
+            started.elapsed(),
+            mismatches.len()
+        );
+        for mismatch in &mismatches {
+            let query = &scenario.queries[mismatch.query];
+            println!(
+                "  query {} from {:?} to {:?}: expected {:?}, found {:?}",
+                mismatch.query, query.start, query.goal, mismatch.expected, mismatch.found
+            );
+        }
+        if record {
+            for mismatch in &mismatches {
+                scenario.queries[mismatch.query].cost = mismatch.found;
+            }
+            if let Err(error) = fs::write(path, scenario.to_json() + "\n") {
+                eprintln!("{}: {}", path, error);
+                process::exit(2);
+            }
+        } else if !mismatches.is_empty() {
+            mismatched = true;
+        }
+    }
+    if mismatched {
+        process::exit(1);
+    }
+}
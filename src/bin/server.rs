@@ -0,0 +1,375 @@
+//! Answers path queries over HTTP for services that can't link the crate.
+//!
+//! ```text
+//! simple_astar_server <map file> [address]
+//! ```
+//!
+//! The map is loaded once at startup. It uses the same ASCII format as the
+//! golden tests: `.` is floor, `#` is a wall and `1` to `9` are floor with
+//! that cost (`S` and `G` are read as floor). The server listens on
+//! `127.0.0.1:7878` unless another address is given, and answers each
+//! connection on its own thread without serializing searches, turning
+//! connections away once too many are open. It handles:
+//!
+//! - `GET /health`, which reports the map size.
+//! - `POST /path` with a JSON body like
+//!   `{"start": [0, 0], "goal": [9, 4], "cardinal_directions": false, "max_expansions": 5000}`,
+//!   where the last two fields are optional. The response carries the path
+//!   as `[x, y]` pairs, excluding the start, with its cost, why the search
+//!   stopped, and how much work it did.
+
+use serde::{Deserialize, Serialize};
+use simple_astar::{Grid, PathfinderPool, SearchOptions};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Requests with larger bodies are rejected.
+const MAX_BODY: usize = 64 * 1024;
+/// Requests with a longer request line or header are rejected.
+const MAX_LINE: usize = 8 * 1024;
+/// Requests with more headers are rejected.
+const MAX_HEADERS: usize = 64;
+/// Connections that go quiet for longer are dropped.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections beyond this many at once are answered with a 503.
+const MAX_CONNECTIONS: usize = 64;
+
+fn parse_map(source: &str) -> Result<Grid, String> {
+    let rows: Vec<&str> = source.lines().filter(|line| !line.is_empty()).collect();
+    let width = rows.first().map_or(0, |row| row.len());
+    let mut cells = Vec::with_capacity(width * rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        if row.len() != width {
+            return Err(format!("row {} is not {} cells wide", y, width));
+        }
+        for symbol in row.bytes() {
+            cells.push(match symbol {
+                b'#' => 0,
+                b'.' | b'S' | b'G' => 1,
+                b'1'..=b'9' => (symbol - b'0') as u32,
+                _ => return Err(format!("unknown map symbol {:?}", symbol as char)),
+            });
+        }
+    }
+    Grid::new(cells, width as u32).map_err(|error| error.to_string())
+}
+
+#[derive(Deserialize)]
+struct PathQuery {
+    start: [u32; 2],
+    goal: [u32; 2],
+    #[serde(default)]
+    cardinal_directions: bool,
+    max_expansions: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct PathAnswer {
+    path: Vec<[u32; 2]>,
+    cost: Option<u32>,
+    termination: &'static str,
+    expanded: u32,
+    reached: u32,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(value: &T) -> Self {
+        Response {
+            status: 200,
+            body: serde_json::to_string(value).expect("answers always serialize"),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response {
+            status,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+}
+
+fn answer(pool: &PathfinderPool<Grid>, query: &PathQuery) -> Response {
+    let grid = &**pool.grid();
+    let index = |[x, y]: [u32; 2]| {
+        if x < grid.width() && y < grid.height() {
+            Some(grid.index(x, y))
+        } else {
+            None
+        }
+    };
+    let (start, goal) = match (index(query.start), index(query.goal)) {
+        (Some(start), Some(goal)) => (start, goal),
+        _ => return Response::error(400, "start or goal lies outside the map"),
+    };
+    let options = SearchOptions {
+        cardinal_directions: query.cardinal_directions,
+        max_expansions: query.max_expansions,
+        ..SearchOptions::default()
+    };
+    let result = pool.search(start, goal, &options);
+    let width = grid.width();
+    Response::json(&PathAnswer {
+        path: result
+            .path
+            .iter()
+            .map(|&cell| [cell % width, cell / width])
+            .collect(),
+        cost: result.cost,
+        termination: result.termination.as_str(),
+        expanded: result.stats.expanded,
+        reached: result.stats.reached,
+    })
+}
+
+fn route(pool: &PathfinderPool<Grid>, method: &str, target: &str, body: &[u8]) -> Response {
+    let grid = &**pool.grid();
+    match (method, target) {
+        ("GET", "/health") => Response::json(&serde_json::json!({
+            "status": "ok",
+            "width": grid.width(),
+            "height": grid.height(),
+        })),
+        ("POST", "/path") => match serde_json::from_slice::<PathQuery>(body) {
+            Ok(query) => answer(pool, &query),
+            Err(error) => Response::error(400, &error.to_string()),
+        },
+        (_, "/health") | (_, "/path") => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+/// The request line and headers of a request.
+struct Head {
+    method: String,
+    target: String,
+    content_length: usize,
+}
+
+/// Reads a line of at most [`MAX_LINE`] bytes, returning false when it is
+/// longer.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<bool> {
+    let read = reader.take(MAX_LINE as u64).read_line(line)?;
+    Ok(read < MAX_LINE || line.ends_with('\n'))
+}
+
+/// Reads the request line and headers, returning `None` when they are
+/// longer than the limits allow.
+fn read_head<R: BufRead>(reader: &mut R) -> io::Result<Option<Head>> {
+    let mut request_line = String::new();
+    if !read_line(reader, &mut request_line)? {
+        return Ok(None);
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let target = parts.next().unwrap_or("").to_string();
+    let mut content_length = 0;
+    for _ in 0..=MAX_HEADERS {
+        let mut header = String::new();
+        if !read_line(reader, &mut header)? {
+            return Ok(None);
+        }
+        if header.trim().is_empty() {
+            return Ok(Some(Head {
+                method,
+                target,
+                content_length,
+            }));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Reads one request off `stream` and writes the response. Connections are
+/// not kept alive.
+fn handle(pool: &PathfinderPool<Grid>, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_head(&mut reader)? {
+        None => Response::error(431, "request line or headers too large"),
+        Some(head) if head.content_length > MAX_BODY => {
+            Response::error(413, "request body too large")
+        }
+        Some(head) => {
+            let mut body = vec![0; head.content_length];
+            reader.read_exact(&mut body)?;
+            route(pool, &head.method, &head.target, &body)
+        }
+    };
+    respond(stream, &response)
+}
+
+/// Writes `response` and closes the connection.
+fn respond(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let map_path = match args.next() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: simple_astar_server <map file> [address]");
+            process::exit(2);
+        }
+    };
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:7878".to_string());
+    let pool = match fs::read_to_string(&map_path)
+        .map_err(|error| error.to_string())
+        .and_then(|source| parse_map(&source))
+    {
+        Ok(grid) => Arc::new(PathfinderPool::new(Arc::new(grid))),
+        Err(error) => {
+            eprintln!("could not load {}: {}", map_path, error);
+            process::exit(1);
+        }
+    };
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("could not listen on {}: {}", address, error);
+            process::exit(1);
+        }
+    };
+    eprintln!(
+        "serving a {}x{} map on {}",
+        pool.grid().width(),
+        pool.grid().height(),
+        address
+    );
+    let open = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        if open.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+            open.fetch_sub(1, Ordering::Relaxed);
+            let _ = stream.set_write_timeout(Some(TIMEOUT));
+            let _ = respond(stream, &Response::error(503, "too many connections"));
+            continue;
+        }
+        let pool = Arc::clone(&pool);
+        let open = Arc::clone(&open);
+        thread::spawn(move || {
+            let _ = handle(&pool, stream);
+            open.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "S..#\n.#.#\n.2.G\n";
+
+    fn map_pool() -> PathfinderPool<Grid> {
+        PathfinderPool::new(Arc::new(parse_map(MAP).unwrap()))
+    }
+
+    #[test]
+    fn it_answers_path_queries() {
+        let pool = map_pool();
+        let body = br#"{"start": [0, 0], "goal": [3, 2], "cardinal_directions": true}"#;
+        let response = route(&pool, "POST", "/path", body);
+        assert_eq!(response.status, 200);
+        let answer: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(answer["cost"], 10);
+        assert_eq!(answer["termination"], "goal_reached");
+        assert_eq!(
+            answer["path"].as_array().unwrap().last().unwrap(),
+            &serde_json::json!([3, 2])
+        );
+    }
+
+    #[test]
+    fn it_rejects_bad_requests() {
+        let pool = map_pool();
+        assert_eq!(route(&pool, "POST", "/path", b"{").status, 400);
+        let outside = br#"{"start": [0, 0], "goal": [4, 0]}"#;
+        assert_eq!(route(&pool, "POST", "/path", outside).status, 400);
+        assert_eq!(route(&pool, "GET", "/path", b"").status, 405);
+        assert_eq!(route(&pool, "GET", "/nowhere", b"").status, 404);
+        assert!(parse_map("..\n...\n").is_err());
+    }
+
+    #[test]
+    fn it_limits_request_heads() {
+        let head = read_head(&mut &b"POST /path HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}"[..])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (head.method.as_str(), head.target.as_str()),
+            ("POST", "/path")
+        );
+        assert_eq!(head.content_length, 2);
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert!(read_head(&mut long_line.as_bytes()).unwrap().is_none());
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X: y\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert!(read_head(&mut many_headers.as_bytes()).unwrap().is_none());
+        let full = format!("GET / HTTP/1.1\r\n{}\r\n", "X: y\r\n".repeat(MAX_HEADERS));
+        assert!(read_head(&mut full.as_bytes()).unwrap().is_some());
+    }
+
+    #[test]
+    fn it_serves_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let pool = map_pool();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle(&pool, stream).unwrap();
+        });
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        server.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(r#"{"height":3,"status":"ok","width":4}"#));
+    }
+}
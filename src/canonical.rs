@@ -0,0 +1,94 @@
+/// Which directions out of a cell, entered by a step in direction
+/// `arrived`, a search with [`crate::SearchOptions::canonical_ordering`]
+/// still has to try. Both masks have one bit per index into
+/// [`crate::DIRECTIONS`], and `open` marks the walkable neighbors.
+///
+/// Paths are kept canonical: diagonal steps before straight ones, so a
+/// straight step is only ever followed by another in the same direction
+/// and a diagonal one by itself or the two straight steps it is made of.
+/// Every other neighbor can be reached from the cell before at least as
+/// cheaply without passing this one, unless a wall beside it is in the
+/// way, which forces the neighbor behind that wall to be tried as well.
+/// This only holds where the cells around cost the same, which is for the
+/// caller to check.
+pub(crate) fn successors(arrived: u8, open: u8) -> u8 {
+    // directions turn clockwise an eighth per index, and straight ones
+    // have even indices
+    let turn = |by: i8| 1u8 << ((arrived as i8 + by).rem_euclid(8));
+    let blocked = |by: i8| open & turn(by) == 0;
+    let mut mask = turn(0);
+    if arrived.is_multiple_of(2) {
+        // a wall to either side shields the diagonal beyond it
+        for &side in &[2, -2] {
+            if blocked(side) {
+                mask |= turn(side / 2);
+            }
+        }
+    } else {
+        mask |= turn(1) | turn(-1);
+        // a wall behind either side shields the cell past it
+        for &side in &[3, -3] {
+            if blocked(side) {
+                mask |= turn(side - side.signum());
+            }
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid, GridSource, Rng, SearchOptions};
+
+    const ALL: u8 = 0xff;
+
+    #[test]
+    fn walls_force_the_neighbors_behind_them() {
+        // right, and down and to the right
+        let (right, down_right) = (2, 3);
+        assert_eq!(successors(right, ALL), 1 << 2);
+        assert_eq!(successors(down_right, ALL), 1 << 3 | 1 << 2 | 1 << 4);
+        // with walls above and down to the left
+        let open = ALL & !(1 << 0) & !(1 << 5);
+        assert_eq!(successors(right, open), 1 << 2 | 1 << 1);
+        assert_eq!(
+            successors(down_right, open),
+            1 << 3 | 1 << 2 | 1 << 4 | 1 << 1
+        );
+        // and with a wall to the left instead
+        let open = ALL & !(1 << 6);
+        assert_eq!(
+            successors(down_right, open),
+            1 << 3 | 1 << 2 | 1 << 4 | 1 << 5
+        );
+    }
+
+    #[test]
+    fn pruned_searches_cost_the_same() {
+        let mut rng = Rng::new(1);
+        let canonical = SearchOptions {
+            canonical_ordering: true,
+            ..SearchOptions::default()
+        };
+        let mut fewer = 0;
+        for round in 0..400 {
+            let (width, height) = (rng.range(1, 24), rng.range(1, 24));
+            // half the maps cost the same everywhere, the rest are patchy
+            let cells: Vec<u32> = (0..width * height)
+                .map(|_| match (rng.chance(1, 5), round % 2) {
+                    (true, _) => 0,
+                    (false, 0) => 1,
+                    _ => rng.range(1, 4),
+                })
+                .collect();
+            let grid = Grid::new(cells, width).unwrap();
+            let (start, end) = (rng.below(grid.len()), rng.below(grid.len()));
+            let plain = search(start, end, &grid, &SearchOptions::default());
+            let pruned = search(start, end, &grid, &canonical);
+            assert_eq!(pruned.cost, plain.cost, "{:?}", grid);
+            fewer += (pruned.stats.peak_frontier < plain.stats.peak_frontier) as u32;
+        }
+        assert!(fewer > 100);
+    }
+}
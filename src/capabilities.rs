@@ -0,0 +1,160 @@
+use crate::{GridSource, WALL};
+use std::ops::BitOr;
+
+/// What is special about a cell beyond its movement cost. Combine flags with
+/// `|`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Terrain(u8);
+
+impl Terrain {
+    pub const PLAIN: Terrain = Terrain(0);
+    /// Only swimmers and flyers can enter.
+    pub const WATER: Terrain = Terrain(1);
+    /// Flyers can't enter, whatever the ground is like.
+    pub const NO_FLY: Terrain = Terrain(1 << 1);
+    /// A wall that phasers can pass through.
+    pub const THIN_WALL: Terrain = Terrain(1 << 2);
+
+    pub fn contains(self, other: Terrain) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Terrain {
+    type Output = Terrain;
+
+    fn bitor(self, other: Terrain) -> Terrain {
+        Terrain(self.0 | other.0)
+    }
+}
+
+/// How an agent gets around. Combine flags with `|`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    pub const WALK: Capabilities = Capabilities(0);
+    /// Crosses walls and water at a cost of `1`, but can't enter
+    /// [`Terrain::NO_FLY`] cells at all.
+    pub const FLY: Capabilities = Capabilities(1);
+    /// Enters [`Terrain::WATER`] at its ground cost.
+    pub const SWIM: Capabilities = Capabilities(1 << 1);
+    /// Passes [`Terrain::THIN_WALL`] walls, at the penalty given to
+    /// [`Movement::phase_penalty`].
+    pub const PHASE: Capabilities = Capabilities(1 << 2);
+
+    pub fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+}
+
+/// A grid as an agent with some [`Capabilities`] sees it, given the
+/// [`Terrain`] of each cell. Any search works over it unchanged.
+#[derive(Debug, Clone, Copy)]
+pub struct Movement<'a, G> {
+    grid: G,
+    terrain: &'a [Terrain],
+    capabilities: Capabilities,
+    phase_penalty: u32,
+}
+
+impl<'a, G: GridSource> Movement<'a, G> {
+    /// `terrain` holds one entry per cell of `grid`, in row-major order.
+    /// Cells past its end are [`Terrain::PLAIN`].
+    pub fn new(grid: G, terrain: &'a [Terrain], capabilities: Capabilities) -> Self {
+        Movement {
+            grid,
+            terrain,
+            capabilities,
+            phase_penalty: 1,
+        }
+    }
+
+    /// What entering a thin wall costs a phaser. Defaults to `1`.
+    pub fn phase_penalty(self, phase_penalty: u32) -> Self {
+        Movement {
+            phase_penalty: phase_penalty.max(1),
+            ..self
+        }
+    }
+}
+
+impl<'a, G: GridSource> GridSource for Movement<'a, G> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.grid.width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.grid.height()
+    }
+
+    fn cost(&self, index: u32) -> u32 {
+        let terrain = self
+            .terrain
+            .get(index as usize)
+            .copied()
+            .unwrap_or_default();
+        let cost = self.grid.cost(index);
+        if self.capabilities.contains(Capabilities::FLY) {
+            return if terrain.contains(Terrain::NO_FLY) {
+                WALL
+            } else {
+                cost.max(1)
+            };
+        }
+        if cost == WALL {
+            let phases = self.capabilities.contains(Capabilities::PHASE)
+                && terrain.contains(Terrain::THIN_WALL);
+            return if phases { self.phase_penalty } else { WALL };
+        }
+        if terrain.contains(Terrain::WATER) && !self.capabilities.contains(Capabilities::SWIM) {
+            return WALL;
+        }
+        cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, SearchOptions};
+
+    #[test]
+    #[rustfmt::skip]
+    fn each_capability_changes_the_way() {
+        let grid = crate::grid![
+            1, 0, 1, 1, 1;
+            1, 0, 1, 0, 1;
+            1, 1, 1, 1, 1;
+        ];
+        let (p, w, t, n) = (Terrain::PLAIN, Terrain::WATER, Terrain::THIN_WALL, Terrain::NO_FLY);
+        let terrain = [
+            p, t, p, p, p,
+            p, p, p, t, p,
+            p, w, p, p, n,
+        ];
+        let options = SearchOptions { cardinal_directions: true, ..SearchOptions::default() };
+        let path = |capabilities| {
+            let movement = Movement::new(&grid, &terrain, capabilities).phase_penalty(3);
+            search(0, 4, &movement, &options).path
+        };
+        assert!(path(Capabilities::WALK).is_empty());
+        assert_eq!(path(Capabilities::SWIM), vec![5, 10, 11, 12, 13, 14, 9, 4]);
+        assert_eq!(path(Capabilities::PHASE), vec![1, 2, 3, 4]);
+        assert_eq!(path(Capabilities::FLY), vec![1, 2, 3, 4]);
+        assert!(Movement::new(&grid, &terrain, Capabilities::FLY).cost(14) == 0);
+        assert!((Capabilities::FLY | Capabilities::SWIM).contains(Capabilities::SWIM));
+    }
+}
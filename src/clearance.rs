@@ -0,0 +1,111 @@
+use crate::{GridSource, WALL};
+
+/// How big a unit fits at each cell of a grid: the side of the largest
+/// square of walkable cells with its top left corner there. A unit `size`
+/// cells wide stands at the top left cell it covers, and fits wherever the
+/// clearance is at least `size`; walls have none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClearanceMap {
+    width: u32,
+    clearances: Vec<u32>,
+}
+
+impl ClearanceMap {
+    /// Measures every cell, working up from the bottom right corner: a
+    /// square fits at a cell when one a size smaller fits to its right,
+    /// below it and diagonally between.
+    pub fn new<G: GridSource + ?Sized>(grid: &G) -> Self {
+        let (width, height) = (grid.width(), grid.height());
+        let mut clearances = vec![0u32; grid.len() as usize];
+        for y in (0..height).rev() {
+            for x in (0..width).rev() {
+                let cell = y * width + x;
+                if grid.cost(cell) == WALL {
+                    continue;
+                }
+                let at = |x: u32, y: u32| {
+                    if x < width && y < height {
+                        clearances[(y * width + x) as usize]
+                    } else {
+                        0
+                    }
+                };
+                let smallest = at(x + 1, y).min(at(x, y + 1)).min(at(x + 1, y + 1));
+                clearances[cell as usize] = smallest + 1;
+            }
+        }
+        ClearanceMap { width, clearances }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The side of the largest unit that fits at `cell`, `0` for walls and
+    /// cells outside the grid.
+    pub fn clearance(&self, cell: u32) -> u32 {
+        self.clearances.get(cell as usize).copied().unwrap_or(0)
+    }
+
+    /// Whether a unit `size` cells wide fits with its top left corner at
+    /// `cell`.
+    pub fn fits(&self, cell: u32, size: u32) -> bool {
+        self.clearance(cell) >= size
+    }
+}
+
+/// `grid` with every cell a unit of `size` doesn't fit at walled off, so
+/// that anything searching it keeps the unit out of gaps too narrow for it.
+pub(crate) struct Fitting<'a, G: ?Sized> {
+    pub(crate) grid: &'a G,
+    pub(crate) clearance: &'a ClearanceMap,
+    pub(crate) size: u32,
+}
+
+impl<G: GridSource + ?Sized> GridSource for Fitting<'_, G> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.grid.width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.grid.height()
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        if self.clearance.fits(index, self.size) {
+            self.grid.cost(index)
+        } else {
+            WALL
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid;
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_measures_the_room_at_each_cell() {
+        let grid = grid![
+            1, 1, 1, 0;
+            1, 1, 1, 1;
+            1, 1, 2, 1;
+        ];
+        let map = ClearanceMap::new(&grid);
+        let clearances: Vec<u32> = (0..12).map(|cell| map.clearance(cell)).collect();
+        assert_eq!(clearances, vec![
+            3, 2, 1, 0,
+            2, 2, 2, 1,
+            1, 1, 1, 1,
+        ]);
+        assert!(map.fits(5, 2));
+        assert!(!map.fits(2, 2));
+        assert_eq!(map.clearance(12), 0);
+    }
+}
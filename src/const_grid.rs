@@ -0,0 +1,194 @@
+use crate::{get_neighbor_coords, step_cost, GridSource, Heuristic, Manhattan};
+
+/// A grid whose size is fixed at compile time, stored inline so it can live
+/// on the stack or in a `static`.
+///
+/// [`ConstGrid::astar`] keeps all its bookkeeping in arrays the size of the
+/// grid and never allocates. It picks the next cell by scanning those arrays
+/// rather than through a heap, which is only quick on small boards, and
+/// needs a few times the grid's size in stack space. Paths match
+/// [`crate::astar_grid`] exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConstGrid<const W: usize, const H: usize> {
+    cells: [[u32; W]; H],
+}
+
+impl<const W: usize, const H: usize> ConstGrid<W, H> {
+    pub const fn new(cells: [[u32; W]; H]) -> Self {
+        ConstGrid { cells }
+    }
+
+    pub fn rows(&self) -> &[[u32; W]; H] {
+        &self.cells
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<u32> {
+        self.cells.get(y as usize)?.get(x as usize).copied()
+    }
+
+    /// Replaces a cell, returning the previous cost, or `None` when the
+    /// coordinates are outside the grid.
+    pub fn set(&mut self, x: u32, y: u32, cost: u32) -> Option<u32> {
+        let cell = self.cells.get_mut(y as usize)?.get_mut(x as usize)?;
+        Some(std::mem::replace(cell, cost))
+    }
+
+    /// The cheapest path from `start` to `end`, in the same form as
+    /// [`crate::astar_grid`] returns it.
+    pub fn astar(&self, start: u32, end: u32, cardinal_directions: bool) -> ConstPath<W, H> {
+        let mut path = ConstPath {
+            cells: [[0; W]; H],
+            len: 0,
+        };
+        let len = self.len();
+        if start >= len || end >= len {
+            return path;
+        }
+        let width = W as u32;
+        let mut cost_so_far: [[Option<u32>; W]; H] = [[None; W]; H];
+        let mut came_from = [[0u32; W]; H];
+        let mut open = [[false; W]; H];
+        let at = |cell: u32| (cell as usize / W, cell as usize % W);
+        let (y, x) = at(start);
+        cost_so_far[y][x] = Some(0);
+        open[y][x] = true;
+        // the start's priority is 0, so it always goes first
+        let mut current = start;
+        loop {
+            let (y, x) = at(current);
+            open[y][x] = false;
+            if current == end {
+                break;
+            }
+            let current_cost = cost_so_far[y][x].unwrap_or(0);
+            for &neighbor in get_neighbor_coords(current, self, cardinal_directions).iter() {
+                let cost = current_cost.saturating_add(step_cost(self, current, neighbor));
+                let (ny, nx) = at(neighbor);
+                if cost_so_far[ny][nx].is_none_or(|known| cost < known) {
+                    cost_so_far[ny][nx] = Some(cost);
+                    came_from[ny][nx] = current;
+                    open[ny][nx] = true;
+                }
+            }
+            // lowest priority first and, among equal priorities, the highest
+            // cell, just as the heap in the other searches orders them
+            let mut next = None;
+            for cell in 0..len {
+                let (y, x) = at(cell);
+                if !open[y][x] {
+                    continue;
+                }
+                // open cells have all been reached
+                let priority = cost_so_far[y][x]
+                    .unwrap_or(0)
+                    .saturating_add(Manhattan.estimate(cell, end, width));
+                if next.is_none_or(|(best, _)| priority <= best) {
+                    next = Some((priority, cell));
+                }
+            }
+            match next {
+                Some((_, cell)) => current = cell,
+                None => return path,
+            }
+        }
+        let mut cell = end;
+        while cell != start {
+            let (y, x) = at(cell);
+            path.push(cell);
+            cell = came_from[y][x];
+        }
+        path.cells.as_flattened_mut()[..path.len].reverse();
+        path
+    }
+}
+
+impl<const W: usize, const H: usize> Default for ConstGrid<W, H> {
+    /// A grid where every cell costs `1`.
+    fn default() -> Self {
+        ConstGrid::new([[1; W]; H])
+    }
+}
+
+impl<const W: usize, const H: usize> GridSource for ConstGrid<W, H> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        W as u32
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        H as u32
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        self.cells[index as usize / W][index as usize % W]
+    }
+}
+
+/// A path found by [`ConstGrid::astar`], stored inline like the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConstPath<const W: usize, const H: usize> {
+    cells: [[u32; W]; H],
+    len: usize,
+}
+
+impl<const W: usize, const H: usize> ConstPath<W, H> {
+    fn push(&mut self, cell: u32) {
+        self.cells.as_flattened_mut()[self.len] = cell;
+        self.len += 1;
+    }
+
+    pub fn as_slice(&self) -> &[u32] {
+        &self.cells.as_flattened()[..self.len]
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_grid, Rng};
+
+    #[test]
+    fn it_finds_the_same_paths_as_astar() {
+        let mut rng = Rng::new(1);
+        for _ in 0..200 {
+            let costs = rng.grid(7, 5, 25, 4);
+            let mut grid = ConstGrid::<7, 5>::default();
+            for y in 0..5 {
+                for x in 0..7 {
+                    grid.set(x, y, costs.cost(costs.index(x, y)));
+                }
+            }
+            let start = rng.below(grid.len());
+            let end = rng.below(grid.len());
+            let cardinal = rng.chance(1, 2);
+            assert_eq!(
+                grid.astar(start, end, cardinal).as_slice(),
+                &astar_grid(start, end, &grid, cardinal)[..]
+            );
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_works_from_a_static() {
+        static MAP: ConstGrid<4, 3> = ConstGrid::new([
+            [1, 1, 1, 1],
+            [0, 0, 0, 1],
+            [1, 1, 1, 1],
+        ]);
+        assert_eq!(MAP.astar(0, 8, true).as_slice(), &[1, 2, 3, 7, 11, 10, 9, 8]);
+        assert!(MAP.astar(0, 4, true).is_empty());
+        assert_eq!(MAP.get(3, 1), Some(1));
+        assert_eq!(MAP.get(4, 1), None);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::search::{search_with, Hooks};
+use crate::{DijkstraMap, GridSource, Manhattan, SearchOptions, SearchResult};
+
+/// The cells within a cost radius of a reference path, for escorts that
+/// should follow a leader's route without walking exactly in its steps.
+///
+/// Distances to the route are measured like path costs, with the same
+/// movement rules, so a radius of `4` on a map of `1`s lets an escort stray
+/// two cardinal steps away. Searching a [`Layered`](crate::Layered) grid
+/// with a [`TrafficLayer`](crate::TrafficLayer) heated along the route
+/// pushes escorts onto parallel lines within the corridor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corridor {
+    field: DijkstraMap,
+    radius: u32,
+}
+
+impl Corridor {
+    /// The corridor around `route`, a list of cells such as a path returned
+    /// by [`crate::astar`], on `grid`. Cells of `route` outside the grid are
+    /// ignored.
+    pub fn around<G: GridSource + ?Sized>(
+        grid: &G,
+        route: &[u32],
+        radius: u32,
+        cardinal_directions: bool,
+    ) -> Self {
+        Corridor {
+            field: DijkstraMap::new(grid, route, cardinal_directions),
+            radius,
+        }
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    /// The cost of getting from `index` to the nearest cell of the route, or
+    /// `None` when it can't be reached.
+    pub fn distance(&self, index: u32) -> Option<u32> {
+        self.field.distance(index)
+    }
+
+    pub fn contains(&self, index: u32) -> bool {
+        self.distance(index)
+            .is_some_and(|distance| distance <= self.radius)
+    }
+
+    /// [`search`](crate::search) that only enters cells inside the corridor,
+    /// apart from the goal. The start may lie outside it.
+    pub fn search<G: GridSource + ?Sized>(
+        &self,
+        start: u32,
+        end: u32,
+        grid: &G,
+        options: &SearchOptions,
+    ) -> SearchResult {
+        search_with(
+            start,
+            end,
+            grid,
+            options,
+            &Manhattan,
+            Confine {
+                corridor: self,
+                end,
+            },
+        )
+    }
+}
+
+struct Confine<'a> {
+    corridor: &'a Corridor,
+    end: u32,
+}
+
+impl<'a> Hooks for Confine<'a> {
+    #[inline(always)]
+    fn allow_step(&mut self, _from: u32, to: u32) -> bool {
+        to == self.end || self.corridor.contains(to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid};
+
+    #[test]
+    #[rustfmt::skip]
+    fn escorts_stay_near_the_route() {
+        // the leader took the top, though the bottom is cheaper
+        let grid = Grid::new(vec![
+            1, 1, 1, 1, 1, 1, 1,
+            2, 2, 2, 2, 2, 2, 2,
+            1, 0, 0, 0, 0, 0, 1,
+            1, 1, 1, 1, 1, 1, 1,
+        ], 7).unwrap();
+        let options = SearchOptions { cardinal_directions: true, ..SearchOptions::default() };
+        let route = [1, 2, 3, 4, 5];
+        let corridor = Corridor::around(&grid, &route, 6, true);
+        assert!(corridor.contains(8));
+        assert!(!corridor.contains(21));
+        let free = search(14, 20, &grid, &options);
+        assert!(free.path.contains(&24));
+        let escorted = corridor.search(14, 20, &grid, &options);
+        assert_eq!(escorted.path.last(), Some(&20));
+        assert!(escorted.path[..escorted.path.len() - 1].iter().all(|&cell| corridor.contains(cell)));
+        assert!(escorted.path[..escorted.path.len() - 1].iter().all(|&cell| cell < 14));
+    }
+}
@@ -0,0 +1,243 @@
+//! Compressed path databases: "next step toward the goal" lookups with no
+//! search at all.
+//!
+//! A [`CompressedPathDatabase`] stores, for every cell of a static grid, the
+//! first move of the cheapest route to every other cell. Each cell's row of
+//! first moves is run-length encoded over goal indices, which keeps
+//! neighboring goals reached the same way down to a single entry. Building it
+//! runs a Dijkstra search from every cell, so it takes time quadratic in the
+//! number of cells; answering a query is a binary search over one row.
+
+use crate::artifact::{cells, valid_move, write_u32s, write_u8s, Reader};
+use crate::dijkstra::{first_moves, NO_MOVE};
+use crate::{neighbor_in, Artifact, DecodeError, GridSource};
+use std::collections::BinaryHeap;
+
+/// Run-length encoded first-move tables for one static grid and movement mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressedPathDatabase {
+    width: u32,
+    height: u32,
+    cardinal_directions: bool,
+    /// Where each cell's runs start in `run_starts` and `run_moves`, plus one
+    /// trailing entry.
+    row_offsets: Vec<u32>,
+    /// First goal index covered by each run.
+    run_starts: Vec<u32>,
+    run_moves: Vec<u8>,
+}
+
+impl CompressedPathDatabase {
+    /// Builds the database for `grid`. It is only valid for as long as the
+    /// grid's costs don't change.
+    pub fn new<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> Self {
+        let len = grid.len() as usize;
+        let mut row_offsets = Vec::with_capacity(len + 1);
+        let mut run_starts = Vec::new();
+        let mut run_moves = Vec::new();
+        let mut distances = vec![u32::MAX; len];
+        let mut moves = vec![NO_MOVE; len];
+        let mut frontier = BinaryHeap::new();
+        for source in 0..grid.len() {
+            row_offsets.push(run_starts.len() as u32);
+            first_moves(
+                grid,
+                source,
+                cardinal_directions,
+                &mut distances,
+                &mut moves,
+                &mut frontier,
+            );
+            let mut previous = None;
+            for (goal, &first) in moves.iter().enumerate() {
+                if previous != Some(first) {
+                    run_starts.push(goal as u32);
+                    run_moves.push(first);
+                    previous = Some(first);
+                }
+            }
+        }
+        row_offsets.push(run_starts.len() as u32);
+        CompressedPathDatabase {
+            width: grid.width(),
+            height: grid.height(),
+            cardinal_directions,
+            row_offsets,
+            run_starts,
+            run_moves,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn cardinal_directions(&self) -> bool {
+        self.cardinal_directions
+    }
+
+    /// Total number of runs across all rows.
+    pub fn runs(&self) -> usize {
+        self.run_starts.len()
+    }
+
+    /// Approximate heap memory used by the tables.
+    pub fn memory_bytes(&self) -> usize {
+        self.row_offsets.len() * std::mem::size_of::<u32>()
+            + self.run_starts.len() * std::mem::size_of::<u32>()
+            + self.run_moves.len()
+    }
+
+    /// The cell to step into from `from` on a cheapest route to `goal`, or
+    /// `None` when `from` is `goal`, `goal` can't be reached, or either lies
+    /// outside the grid.
+    pub fn next_step(&self, from: u32, goal: u32) -> Option<u32> {
+        let len = self.width * self.height;
+        if from >= len || goal >= len {
+            return None;
+        }
+        let row =
+            self.row_offsets[from as usize] as usize..self.row_offsets[from as usize + 1] as usize;
+        let run = self.run_starts[row.clone()].partition_point(|&start| start <= goal) - 1;
+        let first = self.run_moves[row.start + run];
+        if first == NO_MOVE {
+            return None;
+        }
+        Some(neighbor_in(from, first, self.width))
+    }
+
+    /// Follows [`CompressedPathDatabase::next_step`] from `start` to `end`,
+    /// giving the same kind of path as [`crate::astar`]: `start` excluded,
+    /// ending at `end`, and empty when there is none.
+    pub fn path(&self, start: u32, end: u32) -> Vec<u32> {
+        let mut path = Vec::new();
+        let mut current = start;
+        while let Some(next) = self.next_step(current, end) {
+            path.push(next);
+            current = next;
+        }
+        path
+    }
+}
+
+impl Artifact for CompressedPathDatabase {
+    const KIND: [u8; 3] = *b"cpd";
+    const VERSION: u8 = 1;
+
+    fn write_payload(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.push(self.cardinal_directions as u8);
+        write_u32s(bytes, &self.row_offsets);
+        write_u32s(bytes, &self.run_starts);
+        write_u8s(bytes, &self.run_moves);
+    }
+
+    fn read_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(payload);
+        let (width, height) = (reader.u32()?, reader.u32()?);
+        let cardinal_directions = reader.bool()?;
+        let len = cells(width, height)?;
+        let row_offsets = reader.u32s(len + 1)?;
+        let run_starts = reader.any_u32s()?;
+        let run_moves = reader.u8s(run_starts.len())?;
+        reader.finish()?;
+        // every row has runs, the first covering goal 0, in order
+        let rows_fit = row_offsets[0] == 0
+            && row_offsets[len] as usize == run_starts.len()
+            && row_offsets.windows(2).all(|pair| {
+                pair[0] < pair[1]
+                    && pair[1] as usize <= run_starts.len()
+                    && run_starts[pair[0] as usize] == 0
+                    && run_starts[pair[0] as usize..pair[1] as usize]
+                        .windows(2)
+                        .all(|runs| runs[0] < runs[1])
+            });
+        if !rows_fit {
+            return Err(DecodeError::Corrupt);
+        }
+        let valid = row_offsets.windows(2).enumerate().all(|(cell, row)| {
+            run_moves[row[0] as usize..row[1] as usize]
+                .iter()
+                .all(|&first| valid_move(cell as u32, first, width, height))
+        });
+        if !valid {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(CompressedPathDatabase {
+            width,
+            height,
+            cardinal_directions,
+            row_offsets,
+            run_starts,
+            run_moves,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        load_artifact, save_artifact, search, step_cost, DecodeError, Grid, Rng, SearchOptions,
+    };
+
+    #[test]
+    fn databases_that_step_off_the_grid_are_corrupt() {
+        let grid = Grid::filled(3, 3, 1).unwrap();
+        let mut database = CompressedPathDatabase::new(&grid, false);
+        // up, from the top row
+        let run = database.row_offsets[1] as usize;
+        database.run_moves[run] = 0;
+        let bytes = save_artifact(&database, &grid);
+        assert_eq!(
+            load_artifact::<CompressedPathDatabase, _>(&bytes, &grid),
+            Err(DecodeError::Corrupt)
+        );
+    }
+
+    #[test]
+    fn paths_are_as_cheap_as_a_search() {
+        let mut rng = Rng::new(1);
+        for _ in 0..20 {
+            let grid = rng.grid(13, 9, 25, 5);
+            let cardinal_directions = rng.chance(1, 2);
+            let database = CompressedPathDatabase::new(&grid, cardinal_directions);
+            let options = SearchOptions {
+                cardinal_directions,
+                ..SearchOptions::default()
+            };
+            for _ in 0..30 {
+                let start = rng.below(grid.len());
+                let end = rng.below(grid.len());
+                let path = database.path(start, end);
+                let expected = search(start, end, &grid, &options);
+                assert_eq!(path.is_empty(), expected.path.is_empty());
+                if let Some(cost) = expected.cost {
+                    let mut from = start;
+                    let mut total = 0;
+                    for &to in path.iter() {
+                        total += step_cost(&grid, from, to);
+                        from = to;
+                    }
+                    assert_eq!(total, cost);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn open_fields_compress_well() {
+        let grid = Grid::filled(16, 16, 1).unwrap();
+        let database = CompressedPathDatabase::new(&grid, false);
+        assert!(database.runs() < 256 * 256 / 4);
+        assert_eq!(database.next_step(0, 255), Some(17));
+        assert_eq!(database.next_step(17, 17), None);
+        assert_eq!(database.next_step(0, 256), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! Reading and writing cost grids as CSV, one row of the grid per line,
+//! enabled by the `csv` feature. This is the plain subset spreadsheets
+//! export for a table of numbers: no quoting, no header row.
+
+use crate::{Grid, GridError, GridSource};
+use std::error::Error;
+use std::fmt;
+
+/// What is wrong with a CSV grid. Rows and columns count from `1`, the way
+/// spreadsheets number them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvError {
+    /// There are no rows.
+    Empty,
+    /// A field isn't a cost: a whole number from `0` to `u32::MAX`.
+    InvalidCost {
+        row: usize,
+        column: usize,
+        field: String,
+    },
+    /// A row doesn't have as many fields as the first.
+    UnevenRow {
+        row: usize,
+        len: usize,
+        width: usize,
+    },
+    /// The rows make a grid that can't be built, such as one too large.
+    Grid(GridError),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Empty => write!(f, "the grid has no rows"),
+            CsvError::InvalidCost { row, column, field } => write!(
+                f,
+                "row {}, column {}: {:?} is not a cost",
+                row, column, field
+            ),
+            CsvError::UnevenRow { row, len, width } => write!(
+                f,
+                "row {} has {} fields, but the first row has {}",
+                row, len, width
+            ),
+            CsvError::Grid(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for CsvError {}
+
+/// Writes the costs of `grid` as comma-separated rows, each ending in a
+/// newline.
+pub fn grid_to_csv<G: GridSource + ?Sized>(grid: &G) -> String {
+    let mut csv = String::new();
+    for y in 0..grid.height() {
+        let row: Vec<String> = (0..grid.width())
+            .map(|x| grid.cost(y * grid.width() + x).to_string())
+            .collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Reads a grid from comma-separated rows, as written by [`grid_to_csv`].
+/// Spaces around fields are ignored, and so are blank lines, though they
+/// still count towards the row numbers in errors.
+pub fn grid_from_csv(csv: &str) -> Result<Grid<u32>, CsvError> {
+    let mut cells = Vec::new();
+    let mut width = None;
+    for (line, text) in csv.lines().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let row = line + 1;
+        let mut len = 0;
+        for (column, field) in text.split(',').enumerate() {
+            let field = field.trim();
+            let cost = field.parse().map_err(|_| CsvError::InvalidCost {
+                row,
+                column: column + 1,
+                field: field.to_string(),
+            })?;
+            cells.push(cost);
+            len += 1;
+        }
+        match width {
+            None => width = Some(len),
+            Some(width) if width != len => return Err(CsvError::UnevenRow { row, len, width }),
+            Some(_) => {}
+        }
+    }
+    let width = width.ok_or(CsvError::Empty)?;
+    Grid::new(cells, width as u32).map_err(CsvError::Grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid;
+
+    #[test]
+    fn grids_round_trip_and_errors_point_at_the_field() {
+        let grid = grid![
+            1, 0, 3;
+            4, 5, 600;
+        ];
+        let csv = grid_to_csv(&grid);
+        assert_eq!(csv, "1,0,3\n4,5,600\n");
+        assert_eq!(grid_from_csv(&csv), Ok(grid.clone()));
+        assert_eq!(grid_from_csv(" 1, 0 ,3\r\n\n4,5,600"), Ok(grid));
+        assert_eq!(
+            grid_from_csv("1,2\n\n3,x"),
+            Err(CsvError::InvalidCost {
+                row: 3,
+                column: 2,
+                field: "x".to_string()
+            })
+        );
+        assert_eq!(
+            grid_from_csv("1,2\n3,-4"),
+            Err(CsvError::InvalidCost {
+                row: 2,
+                column: 2,
+                field: "-4".to_string()
+            })
+        );
+        assert_eq!(
+            grid_from_csv("1,2\n3"),
+            Err(CsvError::UnevenRow {
+                row: 2,
+                len: 1,
+                width: 2
+            })
+        );
+        assert_eq!(grid_from_csv("\n\n"), Err(CsvError::Empty));
+    }
+}
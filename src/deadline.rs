@@ -0,0 +1,86 @@
+use crate::search::{search_with, Hooks};
+use crate::{GridSource, Manhattan, SearchOptions, SearchResult, TerminationReason};
+use std::time::Instant;
+
+/// The clock is only read every this many expansions.
+const CHECK_INTERVAL: u32 = 16;
+
+struct Deadline(Instant);
+
+impl Hooks for Deadline {
+    fn interrupt(&mut self, expanded: u32) -> Option<TerminationReason> {
+        if expanded.is_multiple_of(CHECK_INTERVAL) && Instant::now() >= self.0 {
+            Some(TerminationReason::DeadlineReached)
+        } else {
+            None
+        }
+    }
+
+    fn wants_partial_path(&self) -> bool {
+        true
+    }
+}
+
+/// [`search`](crate::search) that gives up once `deadline` passes and
+/// returns the cheapest known path toward the reached cell closest to the
+/// goal instead, flagged by [`SearchResult::is_partial`].
+///
+/// A partial path is the best guess available, not a promise: following it
+/// may lead into a dead end. Searches that stop on
+/// [`SearchOptions::max_expansions`] return a partial path too. The clock is
+/// checked every few expansions, so the search can overrun the deadline by
+/// the time those take.
+pub fn astar_with_deadline<G: GridSource + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    deadline: Instant,
+) -> SearchResult {
+    search_with(start, end, grid, options, &Manhattan, Deadline(deadline))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid};
+    use std::time::Duration;
+
+    #[test]
+    fn it_returns_a_partial_path_when_time_runs_out() {
+        let grid = Grid::filled(200, 200, 1).unwrap();
+        let result =
+            astar_with_deadline(0, 39_999, &grid, &SearchOptions::default(), Instant::now());
+        assert_eq!(result.termination, TerminationReason::DeadlineReached);
+        assert_eq!(result.cost, None);
+        assert_eq!(result.stats.expanded, 0);
+        assert!(!result.is_partial());
+        let limited = SearchOptions {
+            max_expansions: Some(50),
+            ..SearchOptions::default()
+        };
+        let result = astar_with_deadline(
+            0,
+            39_999,
+            &grid,
+            &limited,
+            Instant::now() + Duration::from_secs(60),
+        );
+        assert_eq!(result.termination, TerminationReason::ExpansionLimit);
+        assert!(result.is_partial());
+        assert_eq!(result.path.last(), Some(&result.closest));
+        assert_eq!(result.path[0], 201);
+    }
+
+    #[test]
+    fn it_finds_the_whole_path_in_time() {
+        let grid = Grid::filled(20, 20, 1).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let result = astar_with_deadline(0, 399, &grid, &SearchOptions::default(), deadline);
+        assert!(!result.is_partial());
+        assert_eq!(
+            result.path,
+            search(0, 399, &grid, &SearchOptions::default()).path
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{direction, get_neighbor_coords, step_cost, FrontierItem, GridSource};
+use std::collections::BinaryHeap;
+
+/// Cheapest cost from the nearest of a set of source cells to every cell of
+/// a grid, using the same movement rules and step costs as [`crate::astar`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DijkstraMap {
+    distances: Vec<u32>,
+    width: u32,
+}
+
+impl DijkstraMap {
+    /// Floods the grid outward from `sources`. Sources are always entered,
+    /// even when they are walls, just like the start of a search. Sources
+    /// outside the grid are ignored.
+    pub fn new<G: GridSource + ?Sized>(
+        grid: &G,
+        sources: &[u32],
+        cardinal_directions: bool,
+    ) -> Self {
+        let mut distances = vec![u32::MAX; grid.len() as usize];
+        let mut frontier = BinaryHeap::new();
+        for &source in sources.iter().filter(|&&source| source < grid.len()) {
+            distances[source as usize] = 0;
+            frontier.push(FrontierItem {
+                cost: 0,
+                position: source,
+            });
+        }
+        while let Some(FrontierItem { cost, position }) = frontier.pop() {
+            if cost > distances[position as usize] {
+                continue;
+            }
+            for &neighbor in get_neighbor_coords(position, grid, cardinal_directions).iter() {
+                let next = cost.saturating_add(step_cost(grid, position, neighbor));
+                if next < distances[neighbor as usize] {
+                    distances[neighbor as usize] = next;
+                    frontier.push(FrontierItem {
+                        cost: next,
+                        position: neighbor,
+                    });
+                }
+            }
+        }
+        DijkstraMap {
+            distances,
+            width: grid.width(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Cost of reaching `index` from the closest source, or `None` when it is unreachable.
+    pub fn distance(&self, index: u32) -> Option<u32> {
+        match self.distances.get(index as usize) {
+            Some(&distance) if distance != u32::MAX => Some(distance),
+            _ => None,
+        }
+    }
+
+    /// The raw distances in row-major order, with `u32::MAX` marking unreachable cells.
+    pub fn distances(&self) -> &[u32] {
+        &self.distances
+    }
+}
+
+/// Marks a cell with no first move in [`first_moves`]: the source itself and
+/// every cell it can't reach.
+pub(crate) const NO_MOVE: u8 = 8;
+
+/// Floods the grid from `source`, leaving the cheapest cost to every cell in
+/// `distances` and the direction of the first step on that cheapest route in
+/// `moves`. Both buffers must be `grid.len()` long; they are reset here so
+/// they can be reused across sources.
+pub(crate) fn first_moves<G: GridSource + ?Sized>(
+    grid: &G,
+    source: u32,
+    cardinal_directions: bool,
+    distances: &mut [u32],
+    moves: &mut [u8],
+    frontier: &mut BinaryHeap<FrontierItem>,
+) {
+    let width = grid.width();
+    distances
+        .iter_mut()
+        .for_each(|distance| *distance = u32::MAX);
+    moves.iter_mut().for_each(|first| *first = NO_MOVE);
+    distances[source as usize] = 0;
+    frontier.push(FrontierItem {
+        cost: 0,
+        position: source,
+    });
+    while let Some(FrontierItem { cost, position }) = frontier.pop() {
+        if cost > distances[position as usize] {
+            continue;
+        }
+        for &neighbor in get_neighbor_coords(position, grid, cardinal_directions).iter() {
+            let next = cost.saturating_add(step_cost(grid, position, neighbor));
+            if next < distances[neighbor as usize] {
+                distances[neighbor as usize] = next;
+                moves[neighbor as usize] = if position == source {
+                    direction(source, neighbor, width)
+                } else {
+                    moves[position as usize]
+                };
+                frontier.push(FrontierItem {
+                    cost: next,
+                    position: neighbor,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_measures_step_costs_from_the_sources() {
+        let grid = Grid::new(vec![
+            1, 1, 1,
+            1, 0, 3,
+            1, 1, 1,
+        ], 3).unwrap();
+        let map = DijkstraMap::new(&grid, &[0], true);
+        assert_eq!(map.distance(0), Some(0));
+        assert_eq!(map.distance(2), Some(4));
+        assert_eq!(map.distance(4), None);
+        assert_eq!(map.distance(5), Some(8));
+        assert_eq!(map.distance(8), Some(8));
+        let diagonal = DijkstraMap::new(&grid, &[0], false);
+        assert_eq!(diagonal.distance(5), Some(7));
+        let both_corners = DijkstraMap::new(&grid, &[0, 8], true);
+        assert_eq!(both_corners.distance(2), Some(4));
+        assert_eq!(both_corners.distance(7), Some(2));
+    }
+}
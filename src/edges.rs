@@ -0,0 +1,121 @@
+use crate::search::{search_with, Hooks};
+use crate::{GridSource, Manhattan, SearchOptions, SearchResult};
+use fxhash::FxHashMap;
+
+/// Costs that belong to the step between two cells rather than to the cell
+/// entered, like climbing over a low wall between two floor cells.
+pub trait EdgeCosts {
+    /// The cost added to stepping from `from` to its neighbor `to`, or
+    /// `None` when that step can't be taken at all.
+    fn edge_cost(&self, from: u32, to: u32) -> Option<u32>;
+}
+
+impl<F: Fn(u32, u32) -> Option<u32>> EdgeCosts for F {
+    #[inline(always)]
+    fn edge_cost(&self, from: u32, to: u32) -> Option<u32> {
+        self(from, to)
+    }
+}
+
+/// Edge costs for a handful of steps, with every other step free.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EdgeTable {
+    costs: FxHashMap<(u32, u32), Option<u32>>,
+}
+
+impl EdgeTable {
+    pub fn new() -> Self {
+        EdgeTable::default()
+    }
+
+    /// Makes the step from `from` to `to` cost `cost` more, in that direction only.
+    pub fn set(&mut self, from: u32, to: u32, cost: u32) -> &mut Self {
+        self.costs.insert((from, to), Some(cost));
+        self
+    }
+
+    /// Makes the step between `a` and `b` cost `cost` more, both ways.
+    pub fn set_both(&mut self, a: u32, b: u32, cost: u32) -> &mut Self {
+        self.set(a, b, cost).set(b, a, cost)
+    }
+
+    /// Forbids stepping between `a` and `b` either way, like a thin wall
+    /// between two cells.
+    pub fn block(&mut self, a: u32, b: u32) -> &mut Self {
+        self.costs.insert((a, b), None);
+        self.costs.insert((b, a), None);
+        self
+    }
+
+    /// Makes the step from `from` to `to` free again.
+    pub fn clear(&mut self, from: u32, to: u32) -> &mut Self {
+        self.costs.remove(&(from, to));
+        self
+    }
+}
+
+impl EdgeCosts for EdgeTable {
+    #[inline(always)]
+    fn edge_cost(&self, from: u32, to: u32) -> Option<u32> {
+        match self.costs.get(&(from, to)) {
+            Some(&cost) => cost,
+            None => Some(0),
+        }
+    }
+}
+
+struct Edges<'e, E: ?Sized>(&'e E);
+
+impl<'e, E: EdgeCosts + ?Sized> Hooks for Edges<'e, E> {
+    #[inline(always)]
+    fn allow_step(&mut self, from: u32, to: u32) -> bool {
+        self.0.edge_cost(from, to).is_some()
+    }
+
+    #[inline(always)]
+    fn extra_cost(&mut self, from: u32, to: u32) -> u32 {
+        self.0.edge_cost(from, to).unwrap_or(0)
+    }
+}
+
+/// [`search`](crate::search) with `edges` added on top of the cell costs.
+pub fn search_with_edge_costs<G: GridSource + ?Sized, E: EdgeCosts + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    edges: &E,
+) -> SearchResult {
+    search_with(start, end, grid, options, &Manhattan, Edges(edges))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    fn low_walls_cost_extra_to_climb() {
+        let grid = Grid::filled(3, 2, 1).unwrap();
+        let options = SearchOptions {
+            cardinal_directions: true,
+            ..SearchOptions::default()
+        };
+        let mut edges = EdgeTable::new();
+        edges.set_both(0, 1, 10);
+        let result = search_with_edge_costs(0, 2, &grid, &options, &edges);
+        assert_eq!(result.path, vec![3, 4, 5, 2]);
+        assert_eq!(result.cost, Some(8));
+        edges.set_both(0, 1, 1);
+        let over = search_with_edge_costs(0, 2, &grid, &options, &edges);
+        assert_eq!((over.path, over.cost), (vec![1, 2], Some(5)));
+        edges.block(0, 1).block(0, 3);
+        let none = search_with_edge_costs(0, 2, &grid, &options, &edges);
+        assert!(none.cost.is_none());
+        let uphill = |from: u32, to: u32| if to > from { Some(3) } else { Some(0) };
+        assert_eq!(
+            search_with_edge_costs(2, 0, &grid, &options, &uphill).cost,
+            Some(4)
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::search::{search_with, Hooks};
+use crate::{GridSource, Manhattan, SearchOptions, SearchResult};
+
+/// A heightmap laid over a grid, making each step cost more depending on
+/// how far it climbs or drops.
+///
+/// `slope` gets the height difference of a step, positive when climbing,
+/// and returns the cost added to it, so climbing and descending can cost
+/// differently. Steps that climb more than [`Elevation::max_climb`] or drop
+/// more than [`Elevation::max_drop`] are cliffs and can't be taken at all.
+#[derive(Debug, Clone, Copy)]
+pub struct Elevation<'a, F> {
+    heights: &'a [i32],
+    slope: F,
+    max_climb: Option<u32>,
+    max_drop: Option<u32>,
+}
+
+impl<'a, F: Fn(i32) -> u32> Elevation<'a, F> {
+    /// `heights` holds one height per cell of the grid that will be
+    /// searched, in row-major order. Cells past its end count as height `0`.
+    pub fn new(heights: &'a [i32], slope: F) -> Self {
+        Elevation {
+            heights,
+            slope,
+            max_climb: None,
+            max_drop: None,
+        }
+    }
+
+    pub fn max_climb(self, max_climb: u32) -> Self {
+        Elevation {
+            max_climb: Some(max_climb),
+            ..self
+        }
+    }
+
+    pub fn max_drop(self, max_drop: u32) -> Self {
+        Elevation {
+            max_drop: Some(max_drop),
+            ..self
+        }
+    }
+
+    fn height(&self, index: u32) -> i32 {
+        self.heights.get(index as usize).copied().unwrap_or(0)
+    }
+
+    /// The cost the heightmap adds to stepping from `from` to its neighbor
+    /// `to`, or `None` when the step goes over a cliff.
+    pub fn step_cost(&self, from: u32, to: u32) -> Option<u32> {
+        let difference = self.height(to).saturating_sub(self.height(from));
+        let too_steep = if difference >= 0 {
+            self.max_climb
+                .is_some_and(|max| difference.unsigned_abs() > max)
+        } else {
+            self.max_drop
+                .is_some_and(|max| difference.unsigned_abs() > max)
+        };
+        if too_steep {
+            None
+        } else {
+            Some((self.slope)(difference))
+        }
+    }
+
+    /// [`search`](crate::search) with the heightmap's costs and cliffs.
+    pub fn search<G: GridSource + ?Sized>(
+        &self,
+        start: u32,
+        end: u32,
+        grid: &G,
+        options: &SearchOptions,
+    ) -> SearchResult {
+        search_with(start, end, grid, options, &Manhattan, Slopes(self))
+    }
+}
+
+struct Slopes<'e, 'a, F>(&'e Elevation<'a, F>);
+
+impl<'e, 'a, F: Fn(i32) -> u32> Hooks for Slopes<'e, 'a, F> {
+    #[inline(always)]
+    fn allow_step(&mut self, from: u32, to: u32) -> bool {
+        self.0.step_cost(from, to).is_some()
+    }
+
+    #[inline(always)]
+    fn extra_cost(&mut self, from: u32, to: u32) -> u32 {
+        self.0.step_cost(from, to).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid};
+
+    #[test]
+    #[rustfmt::skip]
+    fn climbing_costs_more_than_going_around() {
+        let grid = Grid::filled(5, 3, 1).unwrap();
+        let heights = [
+            0, 0, 0, 0, 0,
+            0, 0, 9, 0, 0,
+            0, 0, 0, 0, 0,
+        ];
+        let options = SearchOptions { cardinal_directions: true, ..SearchOptions::default() };
+        let flat = search(5, 9, &grid, &options);
+        assert!(flat.path.contains(&7));
+        let hills = Elevation::new(&heights, |difference| difference.unsigned_abs() * 2);
+        let result = hills.search(5, 9, &grid, &options);
+        assert!(!result.path.contains(&7));
+        assert_eq!(result.cost, Some(12));
+    }
+
+    #[test]
+    fn cliffs_can_be_jumped_down_but_not_climbed() {
+        let grid = Grid::filled(3, 1, 1).unwrap();
+        let heights = [0, 5, 5];
+        let cliffs = Elevation::new(&heights, |_| 0).max_climb(2).max_drop(10);
+        assert_eq!(cliffs.step_cost(0, 1), None);
+        assert_eq!(cliffs.step_cost(1, 0), Some(0));
+        let options = SearchOptions::default();
+        assert!(cliffs.search(0, 2, &grid, &options).path.is_empty());
+        assert_eq!(cliffs.search(2, 0, &grid, &options).path, vec![1, 0]);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{get_neighbor_coords, step_cost, FrontierItem, GridSource, Path};
+use std::collections::BinaryHeap;
+
+/// The cheapest path from `start` to the nearest cell that hasn't been
+/// explored yet, for autoexplore: follow it, mark what comes into view, and
+/// ask again. `None` once nothing unexplored can be reached, which means
+/// exploring is done.
+///
+/// `explored` holds a flag per cell of `grid` in row-major order; cells
+/// past its end count as unexplored. The grid should hold what is believed
+/// about unexplored cells, such as that they can be walked on, since only
+/// cells a search could enter are ever picked. Routes only cross explored
+/// cells, so the one found always borders what is already known. When
+/// `start` itself is unexplored the path is empty.
+pub fn nearest_unexplored<G: GridSource + ?Sized>(
+    start: u32,
+    explored: &[bool],
+    grid: &G,
+    cardinal_directions: bool,
+) -> Option<Path> {
+    let len = grid.len();
+    if start >= len {
+        return None;
+    }
+    let is_explored = |cell: u32| explored.get(cell as usize).copied().unwrap_or(false);
+    let mut distances = vec![u32::MAX; len as usize];
+    let mut came_from = vec![u32::MAX; len as usize];
+    let mut frontier = BinaryHeap::new();
+    distances[start as usize] = 0;
+    frontier.push(FrontierItem {
+        cost: 0,
+        position: start,
+    });
+    while let Some(FrontierItem { cost, position }) = frontier.pop() {
+        if cost > distances[position as usize] {
+            continue;
+        }
+        if !is_explored(position) {
+            let mut cells = Vec::new();
+            let mut cell = position;
+            while cell != start {
+                cells.push(cell);
+                cell = came_from[cell as usize];
+            }
+            cells.reverse();
+            return Some(Path::new(grid, start, cells));
+        }
+        for &neighbor in get_neighbor_coords(position, grid, cardinal_directions).iter() {
+            let next = cost.saturating_add(step_cost(grid, position, neighbor));
+            if next < distances[neighbor as usize] {
+                distances[neighbor as usize] = next;
+                came_from[neighbor as usize] = position;
+                frontier.push(FrontierItem {
+                    cost: next,
+                    position: neighbor,
+                });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid;
+
+    #[test]
+    fn it_heads_for_the_nearest_unknown_cell() {
+        let grid = grid![
+            1, 1, 1, 1, 1;
+            1, 0, 0, 0, 1;
+            1, 1, 1, 0, 1;
+        ];
+        let mut explored = vec![true; 15];
+        explored[4] = false;
+        explored[14] = false;
+        // 14 is only two cells away, but through a wall: the way around to
+        // it passes 4 first
+        let path = nearest_unexplored(12, &explored, &grid, true).unwrap();
+        assert_eq!(path.cells, vec![11, 10, 5, 0, 1, 2, 3, 4]);
+        assert_eq!(path.cost(), path.len() as u32 * 2);
+        explored[4] = true;
+        let path = nearest_unexplored(12, &explored, &grid, true).unwrap();
+        assert_eq!(path.cells[8..], [9, 14]);
+        explored[14] = true;
+        assert_eq!(nearest_unexplored(12, &explored, &grid, true), None);
+        assert!(nearest_unexplored(12, &[], &grid, true).unwrap().is_empty());
+    }
+}
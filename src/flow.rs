@@ -0,0 +1,525 @@
+use crate::artifact::{cells, valid_move, write_u32s, write_u8s, Reader};
+use crate::clearance::Fitting;
+use crate::dijkstra::NO_MOVE;
+use crate::{
+    direction, get_neighbor_coords, neighbor_in, step_cost, Artifact, ClearanceMap, DecodeError,
+    FrontierItem, GridSource, DIRECTIONS, WALL,
+};
+use std::collections::BinaryHeap;
+
+/// The cheapest way to the nearest of a set of goals from every cell of a
+/// grid, for moving crowds toward the same place without a search each.
+///
+/// Unlike a [`DijkstraMap`](crate::DijkstraMap), which measures costs from
+/// its sources outward, a flow field measures them inward, so that
+/// following it costs exactly what [`crate::astar`] would charge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlowField {
+    width: u32,
+    height: u32,
+    cardinal_directions: bool,
+    distances: Vec<u32>,
+    /// Index into `DIRECTIONS` of each cell's next step, or `NO_MOVE`.
+    moves: Vec<u8>,
+}
+
+impl FlowField {
+    /// Integrates the field toward `goals`. Goals outside the grid are ignored.
+    pub fn new<G: GridSource + ?Sized>(grid: &G, goals: &[u32], cardinal_directions: bool) -> Self {
+        let len = grid.len() as usize;
+        let mut distances = vec![u32::MAX; len];
+        let mut moves = vec![NO_MOVE; len];
+        let mut frontier = BinaryHeap::new();
+        for &goal in goals.iter().filter(|&&goal| goal < grid.len()) {
+            distances[goal as usize] = 0;
+            frontier.push(FrontierItem {
+                cost: 0,
+                position: goal,
+            });
+        }
+        let width = grid.width();
+        while let Some(FrontierItem { cost, position }) = frontier.pop() {
+            if cost > distances[position as usize] || grid.cost(position) == WALL {
+                continue;
+            }
+            // every walkable neighbor can step into this cell
+            for &from in get_neighbor_coords(position, grid, cardinal_directions).iter() {
+                let next = cost.saturating_add(step_cost(grid, from, position));
+                if next < distances[from as usize] {
+                    distances[from as usize] = next;
+                    moves[from as usize] = direction(from, position, width);
+                    frontier.push(FrontierItem {
+                        cost: next,
+                        position: from,
+                    });
+                }
+            }
+        }
+        FlowField {
+            width,
+            height: grid.height(),
+            cardinal_directions,
+            distances,
+            moves,
+        }
+    }
+
+    /// Integrates the field for units `size` cells wide, which stand at the
+    /// top left cell they cover, like [`ClearanceMap`] measures them. Cells
+    /// `clearance` says such a unit doesn't fit at count as walls, so the
+    /// field never leads it into a gap too narrow for it, and goals it
+    /// doesn't fit at are left out. Steps cost what the cell stepped into
+    /// does, as for a unit of one cell.
+    pub fn for_unit_size<G: GridSource + ?Sized>(
+        grid: &G,
+        clearance: &ClearanceMap,
+        size: u32,
+        goals: &[u32],
+        cardinal_directions: bool,
+    ) -> Self {
+        let fitting = Fitting {
+            grid,
+            clearance,
+            size,
+        };
+        let mut field = FlowField::new(&fitting, goals, cardinal_directions);
+        for &goal in goals.iter().filter(|&&goal| !clearance.fits(goal, size)) {
+            if let Some(distance) = field.distances.get_mut(goal as usize) {
+                *distance = u32::MAX;
+            }
+        }
+        field
+    }
+
+    /// A field from already integrated `distances`, pointing each cell at the
+    /// neighbor its distance was reached through. Ties go to the first such
+    /// neighbor, so the directions can differ from [`FlowField::new`]'s
+    /// where several steps are equally cheap.
+    #[cfg_attr(not(feature = "gpu"), allow(dead_code))]
+    pub(crate) fn from_distances<G: GridSource + ?Sized>(
+        grid: &G,
+        distances: Vec<u32>,
+        cardinal_directions: bool,
+    ) -> Self {
+        let width = grid.width();
+        let moves = (0..grid.len())
+            .map(|cell| {
+                let distance = distances[cell as usize];
+                if distance == 0 || distance == u32::MAX {
+                    return NO_MOVE;
+                }
+                get_neighbor_coords(cell, grid, cardinal_directions)
+                    .into_iter()
+                    .find(|&to| {
+                        distances[to as usize].saturating_add(step_cost(grid, cell, to)) == distance
+                    })
+                    .map_or(NO_MOVE, |to| direction(cell, to, width))
+            })
+            .collect();
+        FlowField {
+            width,
+            height: grid.height(),
+            cardinal_directions,
+            distances,
+            moves,
+        }
+    }
+
+    /// Moves the goal at `from` to `to`, only integrating again within
+    /// `margin` cells of the two, for goals that move a little at a time.
+    /// Every other goal of the field stays put.
+    ///
+    /// Cells outside that window keep their directions, which lead into it
+    /// the way they led to `from`, and the new field takes over inside, so
+    /// following the field from anywhere still ends at a goal and
+    /// [`FlowField::distance`] stays what doing so costs. That can be a
+    /// little more than a field integrated from scratch charges, where the
+    /// cheapest way to `to` from afar no longer passes where the way to
+    /// `from` did; integrating a fresh field once in a while puts that
+    /// right.
+    ///
+    /// When some cell of the window can't reach a goal without leaving it,
+    /// such as when `to` lies behind a wall, this integrates the whole field
+    /// again instead and returns `false`.
+    pub fn move_goal<G: GridSource + ?Sized>(
+        &mut self,
+        grid: &G,
+        from: u32,
+        to: u32,
+        margin: u32,
+    ) -> bool {
+        let len = grid.len();
+        if from == to && self.distance(from) == Some(0) {
+            return true;
+        }
+        if from >= len || to >= len || self.distance(from) != Some(0) {
+            self.integrate_again(grid, from, to);
+            return false;
+        }
+        let (width, cardinal_directions) = (self.width, self.cardinal_directions);
+        let (left, right) = (
+            (from % width).min(to % width),
+            (from % width).max(to % width),
+        );
+        let (top, bottom) = (
+            (from / width).min(to / width),
+            (from / width).max(to / width),
+        );
+        let (left, top) = (left.saturating_sub(margin), top.saturating_sub(margin));
+        let right = right.saturating_add(margin).min(width - 1);
+        let bottom = bottom.saturating_add(margin).min(self.height - 1);
+        let window_width = right - left + 1;
+        let window = |cell: u32| {
+            let (x, y) = (cell % width, cell / width);
+            (left..=right).contains(&x) && (top..=bottom).contains(&y)
+        };
+        let local =
+            |cell: u32| ((cell / width - top) * window_width + cell % width - left) as usize;
+        let cells = (window_width * (bottom - top + 1)) as usize;
+        let mut distances = vec![u32::MAX; cells];
+        let mut moves = vec![NO_MOVE; cells];
+        let mut frontier = BinaryHeap::new();
+        // the goals in the window besides `from` still count
+        let goals = (top..=bottom)
+            .flat_map(|y| (left..=right).map(move |x| y * width + x))
+            .filter(|&cell| cell != from && self.distances[cell as usize] == 0);
+        for goal in goals.chain(std::iter::once(to)) {
+            distances[local(goal)] = 0;
+            frontier.push(FrontierItem {
+                cost: 0,
+                position: goal,
+            });
+        }
+        while let Some(FrontierItem { cost, position }) = frontier.pop() {
+            if cost > distances[local(position)] || grid.cost(position) == WALL {
+                continue;
+            }
+            for &from in get_neighbor_coords(position, grid, cardinal_directions).iter() {
+                if !window(from) {
+                    continue;
+                }
+                let next = cost.saturating_add(step_cost(grid, from, position));
+                if next < distances[local(from)] {
+                    distances[local(from)] = next;
+                    moves[local(from)] = direction(from, position, width);
+                    frontier.push(FrontierItem {
+                        cost: next,
+                        position: from,
+                    });
+                }
+            }
+        }
+        // cells that lost their way to a goal, and cells newly reached that
+        // may lead outside to others that were cut off, change more than
+        // the window can tell
+        let stranded = (top..=bottom)
+            .flat_map(|y| (left..=right).map(move |x| y * width + x))
+            .any(|cell| {
+                let (old, new) = (self.distances[cell as usize], distances[local(cell)]);
+                (old != u32::MAX && new == u32::MAX)
+                    || (old == u32::MAX
+                        && new != u32::MAX
+                        && get_neighbor_coords(cell, grid, cardinal_directions)
+                            .iter()
+                            .any(|&neighbor| !window(neighbor)))
+            });
+        if stranded {
+            self.integrate_again(grid, from, to);
+            return false;
+        }
+        // how much more reaching a goal costs from each cell than it did,
+        // settled for the window first and then carried outward along the
+        // old directions into it
+        const UNSETTLED: i64 = i64::MIN;
+        let mut changes = vec![UNSETTLED; len as usize];
+        for cell in (top..=bottom).flat_map(|y| (left..=right).map(move |x| y * width + x)) {
+            let (old, new) = (self.distances[cell as usize], distances[local(cell)]);
+            changes[cell as usize] = new as i64 - old as i64;
+            self.distances[cell as usize] = new;
+            self.moves[cell as usize] = moves[local(cell)];
+        }
+        let mut trail = Vec::new();
+        for cell in 0..len {
+            if changes[cell as usize] != UNSETTLED || self.distances[cell as usize] == u32::MAX {
+                continue;
+            }
+            let mut at = cell;
+            while changes[at as usize] == UNSETTLED {
+                if self.moves[at as usize] == NO_MOVE {
+                    // another goal outside the window
+                    changes[at as usize] = 0;
+                    break;
+                }
+                trail.push(at);
+                at = neighbor_in(at, self.moves[at as usize], width);
+            }
+            let change = changes[at as usize];
+            for passed in trail.drain(..) {
+                changes[passed as usize] = change;
+                let distance = &mut self.distances[passed as usize];
+                *distance = (*distance as i64 + change) as u32;
+            }
+        }
+        true
+    }
+
+    /// Integrates the field over, toward `to` and every goal but `from`.
+    fn integrate_again<G: GridSource + ?Sized>(&mut self, grid: &G, from: u32, to: u32) {
+        let mut goals: Vec<u32> = (0..self.distances.len() as u32)
+            .filter(|&cell| cell != from && self.distances[cell as usize] == 0)
+            .collect();
+        goals.push(to);
+        *self = FlowField::new(grid, &goals, self.cardinal_directions);
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn cardinal_directions(&self) -> bool {
+        self.cardinal_directions
+    }
+
+    /// Index into `DIRECTIONS` of each cell's next step, or `NO_MOVE`.
+    pub(crate) fn moves(&self) -> &[u8] {
+        &self.moves
+    }
+
+    /// Cost of reaching the nearest goal from `cell`, or `None` when no goal
+    /// can be reached from it or it is a wall.
+    pub fn distance(&self, cell: u32) -> Option<u32> {
+        match self.distances.get(cell as usize) {
+            Some(&distance) if distance != u32::MAX => Some(distance),
+            _ => None,
+        }
+    }
+
+    /// The cell to step into from `cell`, or `None` at a goal and wherever
+    /// no goal can be reached.
+    pub fn next_step(&self, cell: u32) -> Option<u32> {
+        match self.moves.get(cell as usize) {
+            Some(&first) if first != NO_MOVE => Some(neighbor_in(cell, first, self.width)),
+            _ => None,
+        }
+    }
+
+    /// The `(dx, dy)` offset of the step out of `cell`, like
+    /// [`FlowField::next_step`].
+    pub fn direction(&self, cell: u32) -> Option<(i32, i32)> {
+        match self.moves.get(cell as usize) {
+            Some(&first) if first != NO_MOVE => Some(DIRECTIONS[first as usize]),
+            _ => None,
+        }
+    }
+
+    /// A unit steering vector at `position`, blending the directions of the
+    /// four cells whose centers surround it so agents turn smoothly
+    /// instead of snapping between eight headings. A cell `(x, y)` covers
+    /// `x..x + 1` and `y..y + 1`. Cells without a direction don't take part;
+    /// `(0.0, 0.0)` means none around `position` has one.
+    pub fn steer(&self, position: (f32, f32)) -> (f32, f32) {
+        let (fx, fy) = (position.0 - 0.5, position.1 - 0.5);
+        let (left, top) = (fx.floor(), fy.floor());
+        let (tx, ty) = (fx - left, fy - top);
+        let mut blended = (0.0f32, 0.0f32);
+        for &(dx, dy, weight) in &[
+            (0, 0, (1.0 - tx) * (1.0 - ty)),
+            (1, 0, tx * (1.0 - ty)),
+            (0, 1, (1.0 - tx) * ty),
+            (1, 1, tx * ty),
+        ] {
+            let (x, y) = (left as i64 + dx, top as i64 + dy);
+            if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+                continue;
+            }
+            if let Some((sx, sy)) = self.direction(y as u32 * self.width + x as u32) {
+                let length = ((sx * sx + sy * sy) as f32).sqrt();
+                blended.0 += weight * sx as f32 / length;
+                blended.1 += weight * sy as f32 / length;
+            }
+        }
+        let length = (blended.0 * blended.0 + blended.1 * blended.1).sqrt();
+        if length <= f32::EPSILON {
+            (0.0, 0.0)
+        } else {
+            (blended.0 / length, blended.1 / length)
+        }
+    }
+}
+
+impl Artifact for FlowField {
+    const KIND: [u8; 3] = *b"flf";
+    const VERSION: u8 = 1;
+
+    fn write_payload(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.push(self.cardinal_directions as u8);
+        write_u32s(bytes, &self.distances);
+        write_u8s(bytes, &self.moves);
+    }
+
+    fn read_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(payload);
+        let (width, height) = (reader.u32()?, reader.u32()?);
+        let cardinal_directions = reader.bool()?;
+        let len = cells(width, height)?;
+        let distances = reader.u32s(len)?;
+        let moves = reader.u8s(len)?;
+        reader.finish()?;
+        let valid = moves
+            .iter()
+            .enumerate()
+            .all(|(cell, &first)| valid_move(cell as u32, first, width, height));
+        if !valid {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(FlowField {
+            width,
+            height,
+            cardinal_directions,
+            distances,
+            moves,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        grid, load_artifact, save_artifact, search, DecodeError, Grid, Rng, SearchOptions,
+    };
+
+    #[test]
+    fn following_the_field_costs_what_a_search_does() {
+        let mut rng = Rng::new(1);
+        let grid = rng.grid(15, 15, 20, 5);
+        let goal = 112;
+        let field = FlowField::new(&grid, &[goal], false);
+        for start in 0..grid.len() {
+            if grid.cost(start) == WALL {
+                assert_eq!(field.distance(start), None);
+                continue;
+            }
+            let expected = search(start, goal, &grid, &SearchOptions::default()).cost;
+            assert_eq!(field.distance(start), expected);
+            let mut cell = start;
+            let mut cost = 0;
+            while let Some(next) = field.next_step(cell) {
+                cost += step_cost(&grid, cell, next);
+                cell = next;
+            }
+            if expected.is_some() {
+                assert_eq!((cell, Some(cost)), (goal, expected));
+            }
+        }
+        assert_eq!(field.next_step(goal), None);
+    }
+
+    #[test]
+    fn fields_that_step_off_the_grid_are_corrupt() {
+        let grid = Grid::filled(3, 3, 1).unwrap();
+        let mut field = FlowField::new(&grid, &[0], false);
+        // up, from the top row
+        field.moves[1] = 0;
+        let bytes = save_artifact(&field, &grid);
+        assert_eq!(
+            load_artifact::<FlowField, _>(&bytes, &grid),
+            Err(DecodeError::Corrupt)
+        );
+    }
+
+    #[test]
+    fn steering_blends_neighboring_directions() {
+        let grid = Grid::filled(4, 4, 1).unwrap();
+        let field = FlowField::new(&grid, &[3], true);
+        let (dx, dy) = field.direction(12).unwrap();
+        assert_eq!(field.steer((0.5, 3.5)), (dx as f32, dy as f32));
+        let (x, y) = field.steer((3.0, 1.0));
+        assert!(x > 0.0 && y < 0.0);
+        assert!(((x * x + y * y).sqrt() - 1.0).abs() < 1e-5);
+        assert_eq!(field.steer((3.5, 0.5)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn moved_goals_are_patched_in_place() {
+        let mut rng = Rng::new(2);
+        let mut patched = 0;
+        for _ in 0..100 {
+            let (width, height) = (rng.range(1, 30), rng.range(1, 30));
+            let grid = rng.grid(width, height, 17, 3);
+            let cardinal = rng.chance(1, 2);
+            let from = rng.below(grid.len());
+            let (x, y) = (from % width, from / width);
+            let x = (x + rng.range(0, 3)).saturating_sub(1).min(width - 1);
+            let y = (y + rng.range(0, 3)).saturating_sub(1).min(height - 1);
+            let to = y * width + x;
+            let mut field = FlowField::new(&grid, &[from], cardinal);
+            patched += field.move_goal(&grid, from, to, 3) as u32;
+            let fresh = FlowField::new(&grid, &[to], cardinal);
+            for start in 0..grid.len() {
+                let (distance, best) = (field.distance(start), fresh.distance(start));
+                assert_eq!(distance.is_some(), best.is_some());
+                assert!(distance >= best);
+                let (mut cell, mut cost) = (start, 0);
+                while let Some(next) = field.next_step(cell) {
+                    cost += step_cost(&grid, cell, next);
+                    cell = next;
+                }
+                if distance.is_some() {
+                    assert_eq!((cell, Some(cost)), (to, distance));
+                }
+            }
+        }
+        assert!(patched > 50);
+    }
+
+    #[test]
+    fn goals_moved_out_of_reach_integrate_again() {
+        let grid = grid![
+            1, 1, 0, 1;
+            1, 1, 0, 1;
+        ];
+        let mut field = FlowField::new(&grid, &[1], false);
+        assert!(!field.move_goal(&grid, 1, 3, 1));
+        assert_eq!(field, FlowField::new(&grid, &[3], false));
+        assert!(field.move_goal(&grid, 3, 7, 1));
+        assert_eq!(field.distance(0), None);
+        // moving into a pocket opens the cells beyond the window too
+        let grid = grid![1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1;];
+        let mut field = FlowField::new(&grid, &[0, 1], true);
+        assert!(!field.move_goal(&grid, 1, 4, 1));
+        assert_eq!(field, FlowField::new(&grid, &[0, 4], true));
+        assert_eq!(field.distance(8), Some(8));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn large_units_go_around_narrow_gaps() {
+        let grid = grid![
+            1, 1, 1, 1, 1, 1, 1;
+            1, 1, 1, 1, 1, 1, 1;
+            1, 0, 0, 0, 0, 1, 1;
+            1, 1, 1, 1, 1, 1, 1;
+            1, 1, 1, 1, 1, 1, 1;
+        ];
+        let clearance = ClearanceMap::new(&grid);
+        let small = FlowField::new(&grid, &[21], true);
+        let large = FlowField::for_unit_size(&grid, &clearance, 2, &[21], true);
+        // the gap on the left is one cell wide, so a unit of two cells has
+        // to go round through the one on the right
+        assert_eq!((small.next_step(0), small.distance(0)), (Some(7), Some(6)));
+        assert_eq!(large.next_step(0), Some(1));
+        assert_eq!(large.distance(0), Some(26));
+        // and fits nowhere along the right edge
+        assert_eq!(large.distance(6), None);
+        let inside = FlowField::for_unit_size(&grid, &clearance, 2, &[14], true);
+        assert_eq!(inside.distance(14), None);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    assign_jobs, get_neighbor_coords, step_cost, FlowField, FrontierItem, GridSource, WALL,
+};
+use fxhash::FxHashMap;
+use std::collections::BinaryHeap;
+
+/// Where a group sent to one place should end up, one cell each, so that
+/// they spread out around it instead of all fighting over the one cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formation {
+    /// The cells the group gathers on: the walkable cells closest to the
+    /// destination by walking, nearest first, one per unit that can get
+    /// there.
+    pub cells: Vec<u32>,
+    /// The cell of [`Formation::cells`] each unit takes, in the order the
+    /// units were given, or `None` for a unit that can't reach any.
+    pub slots: Vec<Option<u32>>,
+    /// One field for the whole group, leading toward the nearest of the
+    /// cells. Units follow it until they stand on one, then walk the last
+    /// few steps across the formation to their own slot.
+    pub field: FlowField,
+}
+
+impl Formation {
+    /// Picks slots around `destination` for the units at cells `units`,
+    /// handing them out so that the units walk as little as possible in
+    /// total, with [`assign_jobs`]. A destination that is a wall gathers the
+    /// group around it.
+    pub fn new<G: GridSource + ?Sized>(
+        units: &[u32],
+        destination: u32,
+        grid: &G,
+        cardinal_directions: bool,
+    ) -> Self {
+        let nearest = nearest_walkable(destination, units.len(), grid, cardinal_directions);
+        let assignment = assign_jobs(units, &nearest, grid, cardinal_directions);
+        let mut slots = vec![None; units.len()];
+        for job in &assignment.jobs {
+            slots[job.worker] = Some(nearest[job.task]);
+        }
+        // slots nobody could reach would only draw the field away
+        let mut cells: Vec<u32> = Vec::with_capacity(assignment.jobs.len());
+        cells.extend(
+            nearest
+                .iter()
+                .copied()
+                .filter(|cell| slots.contains(&Some(*cell))),
+        );
+        Formation {
+            field: FlowField::new(grid, &cells, cardinal_directions),
+            cells,
+            slots,
+        }
+    }
+}
+
+/// Up to `count` walkable cells, cheapest to walk to from `from` first.
+fn nearest_walkable<G: GridSource + ?Sized>(
+    from: u32,
+    count: usize,
+    grid: &G,
+    cardinal_directions: bool,
+) -> Vec<u32> {
+    let mut nearest = Vec::with_capacity(count);
+    if from >= grid.len() || count == 0 {
+        return nearest;
+    }
+    let mut distances = FxHashMap::default();
+    let mut frontier = BinaryHeap::new();
+    distances.insert(from, 0);
+    frontier.push(FrontierItem {
+        cost: 0,
+        position: from,
+    });
+    while let Some(FrontierItem { cost, position }) = frontier.pop() {
+        if distances.get(&position).is_some_and(|&known| cost > known) {
+            continue;
+        }
+        if grid.cost(position) != WALL {
+            nearest.push(position);
+            if nearest.len() == count {
+                break;
+            }
+        }
+        for &neighbor in get_neighbor_coords(position, grid, cardinal_directions).iter() {
+            let next = cost.saturating_add(step_cost(grid, position, neighbor));
+            if distances.get(&neighbor).is_none_or(|&known| next < known) {
+                distances.insert(neighbor, next);
+                frontier.push(FrontierItem {
+                    cost: next,
+                    position: neighbor,
+                });
+            }
+        }
+    }
+    nearest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid, Grid};
+
+    #[test]
+    fn every_unit_gets_a_cell_of_its_own() {
+        let grid = Grid::filled(9, 9, 1).unwrap();
+        let units = [0, 8, 72, 80, 4];
+        let formation = Formation::new(&units, 40, &grid, false);
+        assert_eq!(formation.cells.len(), 5);
+        assert_eq!(formation.cells[0], 40);
+        let mut taken: Vec<u32> = formation.slots.iter().map(|slot| slot.unwrap()).collect();
+        taken.sort_unstable();
+        taken.dedup();
+        assert_eq!(taken.len(), 5);
+        for cell in &formation.cells {
+            let (x, y) = (cell % 9, cell / 9);
+            assert!(x.abs_diff(4) <= 1 && y.abs_diff(4) <= 1);
+            assert_eq!(formation.field.distance(*cell), Some(0));
+        }
+        // the unit straight above the middle takes the slot on its side
+        assert_eq!(formation.slots[4], Some(31));
+    }
+
+    #[test]
+    fn units_that_cant_get_there_go_without() {
+        let grid = grid![
+            1, 1, 0, 1;
+            1, 1, 0, 1;
+        ];
+        // the one unit that can get there is already standing on a slot
+        let formation = Formation::new(&[3, 7, 4], 0, &grid, true);
+        assert_eq!(formation.slots, vec![None, None, Some(4)]);
+        assert_eq!(formation.cells, vec![4]);
+        assert_eq!(formation.field.next_step(0), Some(4));
+    }
+}
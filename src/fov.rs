@@ -0,0 +1,137 @@
+use crate::{GridSource, WALL};
+
+/// Someone watching the map, who sees along straight lines that walls block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Observer {
+    pub x: u32,
+    pub y: u32,
+    /// How far the observer sees, in cells.
+    pub range: u32,
+    /// The direction the observer faces, as an `(x, y)` offset, or `None`
+    /// to see all around.
+    pub facing: Option<(i32, i32)>,
+    /// The full width of the view cone, in degrees. Ignored without `facing`.
+    pub cone_degrees: f64,
+}
+
+impl Observer {
+    /// An observer seeing all around, up to `range` cells away.
+    pub fn new(x: u32, y: u32, range: u32) -> Self {
+        Observer {
+            x,
+            y,
+            range,
+            facing: None,
+            cone_degrees: 360.0,
+        }
+    }
+
+    /// Narrows the view to a cone `cone_degrees` wide around `facing`.
+    pub fn facing(self, facing: (i32, i32), cone_degrees: f64) -> Self {
+        Observer {
+            facing: Some(facing),
+            cone_degrees,
+            ..self
+        }
+    }
+
+    fn in_cone(&self, dx: i64, dy: i64) -> bool {
+        let (fx, fy) = match self.facing {
+            Some(facing) if self.cone_degrees < 360.0 => facing,
+            _ => return true,
+        };
+        if dx == 0 && dy == 0 {
+            return true;
+        }
+        let (fx, fy) = (fx as f64, fy as f64);
+        let (dx, dy) = (dx as f64, dy as f64);
+        let cos = (fx * dx + fy * dy) / ((fx * fx + fy * fy).sqrt() * (dx * dx + dy * dy).sqrt());
+        cos >= (self.cone_degrees / 2.0).to_radians().cos()
+    }
+}
+
+/// Whether no wall stands on the straight line between two cells, walking
+/// it with Bresenham's algorithm. The end cells themselves may be walls.
+pub fn line_of_sight<G: GridSource + ?Sized>(grid: &G, from: (u32, u32), to: (u32, u32)) -> bool {
+    let width = grid.width();
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (x1, y1) = (to.0 as i64, to.1 as i64);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    loop {
+        if (x, y) == (x1, y1) {
+            return true;
+        }
+        if (x, y) != (from.0 as i64, from.1 as i64)
+            && grid.cost(y as u32 * width + x as u32) == WALL
+        {
+            return false;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Every cell `observer` can see, in row-major order. Walls are seen but
+/// not seen through.
+pub fn field_of_view<G: GridSource + ?Sized>(grid: &G, observer: &Observer) -> Vec<u32> {
+    let width = grid.width();
+    let height = grid.height();
+    let mut visible = Vec::new();
+    if observer.x >= width || observer.y >= height {
+        return visible;
+    }
+    let range = observer.range as i64;
+    let top = (observer.y as i64 - range).max(0);
+    let bottom = (observer.y as i64 + range).min(height as i64 - 1);
+    let left = (observer.x as i64 - range).max(0);
+    let right = (observer.x as i64 + range).min(width as i64 - 1);
+    for y in top..=bottom {
+        for x in left..=right {
+            let (dx, dy) = (x - observer.x as i64, y - observer.y as i64);
+            if dx * dx + dy * dy > range * range || !observer.in_cone(dx, dy) {
+                continue;
+            }
+            if line_of_sight(grid, (observer.x, observer.y), (x as u32, y as u32)) {
+                visible.push(y as u32 * width + x as u32);
+            }
+        }
+    }
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    #[rustfmt::skip]
+    fn walls_and_cones_limit_the_view() {
+        let grid = Grid::new(vec![
+            1, 1, 1, 1, 1,
+            1, 1, 0, 1, 1,
+            1, 1, 1, 1, 1,
+        ], 5).unwrap();
+        let all_around = field_of_view(&grid, &Observer::new(0, 1, 10));
+        assert!(all_around.contains(&7));
+        assert!(!all_around.contains(&8));
+        assert!(all_around.contains(&4));
+        let east = field_of_view(&grid, &Observer::new(0, 1, 10).facing((1, 0), 90.0));
+        assert!(east.contains(&6));
+        assert!(!east.contains(&0));
+        let short = field_of_view(&grid, &Observer::new(0, 1, 1));
+        assert_eq!(short, vec![0, 5, 6, 10]);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    get_neighbor_coords, step_cost, FrontierItem, GridSource, Heuristic, Manhattan, Path, WALL,
+};
+use std::collections::BinaryHeap;
+
+/// The cheapest path to `end` from whichever of `starts` it is cheapest
+/// from, such as which idle worker should take a job, along with that
+/// start. `None` when no start can reach `end`.
+///
+/// This is one search run backwards from `end`, guided toward the nearest
+/// start, rather than a search per start. Each step is charged what it
+/// costs walked forwards, so the path costs what a search from the chosen
+/// start finds, though where several routes or starts tie, the one picked
+/// may not be the one that search would. Starts are left from even when
+/// they are walls, as the start of any search is, and starts outside the
+/// grid are ignored.
+pub fn astar_from_any<G: GridSource + ?Sized>(
+    starts: &[u32],
+    end: u32,
+    grid: &G,
+    cardinal_directions: bool,
+) -> Option<(u32, Path)> {
+    let len = grid.len();
+    let width = grid.width();
+    let mut starts: Vec<u32> = starts
+        .iter()
+        .copied()
+        .filter(|&start| start < len)
+        .collect();
+    starts.sort_unstable();
+    starts.dedup();
+    if end >= len || starts.is_empty() {
+        return None;
+    }
+    let is_start = |cell: u32| starts.binary_search(&cell).is_ok();
+    if is_start(end) {
+        return Some((end, Path::new(grid, end, Vec::new())));
+    }
+    if grid.cost(end) == WALL {
+        return None;
+    }
+    // walkable starts are found among the neighbors every cell lists
+    let wall_starts: Vec<u32> = starts
+        .iter()
+        .copied()
+        .filter(|&start| grid.cost(start) == WALL)
+        .collect();
+    let estimate = |cell: u32| {
+        starts
+            .iter()
+            .map(|&start| Manhattan.estimate(cell, start, width))
+            .min()
+            .unwrap_or(0)
+    };
+    // the cost of walking from each cell to `end`, and the cell stepped to
+    let mut distances = vec![u32::MAX; len as usize];
+    let mut next = vec![u32::MAX; len as usize];
+    let mut frontier = BinaryHeap::new();
+    distances[end as usize] = 0;
+    frontier.push(FrontierItem {
+        cost: estimate(end),
+        position: end,
+    });
+    while let Some(FrontierItem { cost, position }) = frontier.pop() {
+        let distance = distances[position as usize];
+        if cost > distance.saturating_add(estimate(position)) {
+            continue;
+        }
+        if is_start(position) {
+            let mut cells = Vec::new();
+            let mut cell = position;
+            while cell != end {
+                cell = next[cell as usize];
+                cells.push(cell);
+            }
+            return Some((position, Path::new(grid, position, cells)));
+        }
+        let neighbors = get_neighbor_coords(position, grid, cardinal_directions);
+        let adjacent_wall_starts = wall_starts.iter().copied().filter(|&start| {
+            let (dx, dy) = (
+                (start % width).abs_diff(position % width),
+                (start / width).abs_diff(position / width),
+            );
+            let steps = if cardinal_directions {
+                dx + dy
+            } else {
+                dx.max(dy)
+            };
+            steps == 1
+        });
+        for previous in neighbors.iter().copied().chain(adjacent_wall_starts) {
+            // walked forwards, this steps from `previous` into `position`
+            let through = distance.saturating_add(step_cost(grid, previous, position));
+            if through < distances[previous as usize] {
+                distances[previous as usize] = through;
+                next[previous as usize] = position;
+                frontier.push(FrontierItem {
+                    cost: through.saturating_add(estimate(previous)),
+                    position: previous,
+                });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid, Rng, SearchOptions};
+
+    #[test]
+    fn it_matches_a_search_from_every_start() {
+        let mut rng = Rng::new(1);
+        for _ in 0..200 {
+            let (width, height) = (rng.range(1, 12), rng.range(1, 12));
+            let grid = rng.grid(width, height, 25, 5);
+            let starts: Vec<u32> = (0..rng.range(1, 5))
+                .map(|_| rng.below(grid.len()))
+                .collect();
+            let end = rng.below(grid.len());
+            let cardinal = rng.chance(1, 2);
+            let options = SearchOptions {
+                cardinal_directions: cardinal,
+                ..SearchOptions::default()
+            };
+            let best = starts
+                .iter()
+                .filter_map(|&start| search(start, end, &grid, &options).cost)
+                .min();
+            let found = astar_from_any(&starts, end, &grid, cardinal);
+            assert_eq!(found.as_ref().map(|(_, path)| path.cost()), best);
+            if let Some((start, path)) = found {
+                assert!(starts.contains(&start));
+                assert_eq!(search(start, end, &grid, &options).cost, Some(path.cost()));
+                assert_eq!(path.cells.last().copied().unwrap_or(start), end);
+            }
+        }
+        assert_eq!(
+            astar_from_any(&[], 0, &Grid::filled(2, 2, 1).unwrap(), true),
+            None
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Organic cave maps grown with a cellular automaton.
+//!
+//! The grid starts as random noise and is then smoothed a number of times:
+//! a cell becomes a wall when most of its eight neighbors are walls and
+//! floor when few of them are. The outer border is always wall.
+
+use crate::{Grid, Rng};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaveOptions {
+    /// Chance, in percent, that a cell starts out as a wall.
+    pub fill_percent: u32,
+    /// Number of smoothing passes over the initial noise.
+    pub iterations: u32,
+    /// A cell becomes a wall when at least this many of its neighbors are walls.
+    pub birth_limit: u32,
+    /// A cell becomes floor when at most this many of its neighbors are walls.
+    pub death_limit: u32,
+    pub seed: u64,
+}
+
+impl Default for CaveOptions {
+    fn default() -> Self {
+        CaveOptions {
+            fill_percent: 45,
+            iterations: 5,
+            birth_limit: 5,
+            death_limit: 3,
+            seed: 0,
+        }
+    }
+}
+
+fn wall_neighbors(walls: &[bool], width: u32, height: u32, x: u32, y: u32) -> u32 {
+    let mut count = 0;
+    for dy in -1..=1i32 {
+        for dx in -1..=1i32 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            let outside = nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32;
+            if outside || walls[(ny as u32 * width + nx as u32) as usize] {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+pub fn generate(width: u32, height: u32, options: &CaveOptions) -> Grid {
+    let mut rng = Rng::new(options.seed);
+    let is_border = |x: u32, y: u32| x == 0 || y == 0 || x + 1 == width || y + 1 == height;
+    let mut walls: Vec<bool> = (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            is_border(x, y) || rng.chance(options.fill_percent, 100)
+        })
+        .collect();
+    let mut next = walls.clone();
+    for _ in 0..options.iterations {
+        for y in 0..height {
+            for x in 0..width {
+                let index = (y * width + x) as usize;
+                let count = wall_neighbors(&walls, width, height, x, y);
+                next[index] = if is_border(x, y) || count >= options.birth_limit {
+                    true
+                } else if count <= options.death_limit {
+                    false
+                } else {
+                    walls[index]
+                };
+            }
+        }
+        std::mem::swap(&mut walls, &mut next);
+    }
+    let cells: Vec<u32> = walls.iter().map(|&wall| if wall { 0 } else { 1 }).collect();
+    Grid::new(cells, width.max(1)).expect("cave dimensions match the cell count")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caves_are_walled_in() {
+        let grid = generate(30, 20, &CaveOptions::default());
+        for x in 0..30 {
+            assert_eq!(grid.get(x, 0), Some(0));
+            assert_eq!(grid.get(x, 19), Some(0));
+        }
+        for y in 0..20 {
+            assert_eq!(grid.get(0, y), Some(0));
+            assert_eq!(grid.get(29, y), Some(0));
+        }
+        let floor = grid.cells().iter().filter(|&&cost| cost > 0).count();
+        assert!(floor > 0);
+    }
+
+    #[test]
+    fn smoothing_rounds_off_corners() {
+        let options = CaveOptions {
+            fill_percent: 0,
+            ..CaveOptions::default()
+        };
+        let grid = generate(10, 10, &options);
+        assert_eq!(grid.get(5, 5), Some(1));
+        assert_eq!(grid.get(2, 1), Some(1));
+        assert_eq!(grid.get(1, 1), Some(0));
+        assert_eq!(grid.get(8, 8), Some(0));
+    }
+
+    #[test]
+    fn caves_depend_only_on_the_seed() {
+        let options = CaveOptions {
+            seed: 9,
+            ..CaveOptions::default()
+        };
+        assert_eq!(generate(40, 40, &options), generate(40, 40, &options));
+        assert_ne!(
+            generate(40, 40, &options),
+            generate(40, 40, &CaveOptions::default())
+        );
+    }
+}
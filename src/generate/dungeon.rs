@@ -0,0 +1,225 @@
+//! Rooms connected by corridors, laid out with binary space partitioning.
+//!
+//! The map is split recursively into leaves, each leaf gets one rectangular
+//! room, and the two halves of every split are joined with an L-shaped
+//! corridor, so every room is reachable from every other room.
+
+use crate::{Grid, Rng};
+
+/// A rectangular room, in grid coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Room {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Room {
+    pub fn center(&self) -> (u32, u32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DungeonOptions {
+    /// Leaves are never split into parts narrower or shorter than this.
+    pub min_leaf_size: u32,
+    /// Rooms are at least this wide and tall, space permitting.
+    pub min_room_size: u32,
+    pub seed: u64,
+}
+
+impl Default for DungeonOptions {
+    fn default() -> Self {
+        DungeonOptions {
+            min_leaf_size: 8,
+            min_room_size: 4,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dungeon {
+    pub grid: Grid,
+    pub rooms: Vec<Room>,
+}
+
+struct Builder<'a> {
+    grid: Grid,
+    rooms: Vec<Room>,
+    rng: Rng,
+    options: &'a DungeonOptions,
+}
+
+impl<'a> Builder<'a> {
+    fn split(&mut self, area: Room) -> Option<(Room, Room)> {
+        let min = self.options.min_leaf_size.max(1);
+        let can_split_x = area.width >= 2 * min;
+        let can_split_y = area.height >= 2 * min;
+        let split_x = match (can_split_x, can_split_y) {
+            (false, false) => return None,
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) if area.width * 4 > area.height * 5 => true,
+            (true, true) if area.height * 4 > area.width * 5 => false,
+            (true, true) => self.rng.chance(1, 2),
+        };
+        if split_x {
+            let cut = self.rng.range(min, area.width - min + 1);
+            let left = Room { width: cut, ..area };
+            let right = Room {
+                x: area.x + cut,
+                width: area.width - cut,
+                ..area
+            };
+            Some((left, right))
+        } else {
+            let cut = self.rng.range(min, area.height - min + 1);
+            let top = Room {
+                height: cut,
+                ..area
+            };
+            let bottom = Room {
+                y: area.y + cut,
+                height: area.height - cut,
+                ..area
+            };
+            Some((top, bottom))
+        }
+    }
+
+    /// Returns the index of a room inside `area` that corridors can attach to.
+    fn build(&mut self, area: Room) -> usize {
+        match self.split(area) {
+            Some((first, second)) => {
+                let a = self.build(first);
+                let b = self.build(second);
+                self.corridor(self.rooms[a].center(), self.rooms[b].center());
+                if self.rng.chance(1, 2) {
+                    a
+                } else {
+                    b
+                }
+            }
+            None => self.place_room(area),
+        }
+    }
+
+    fn place_room(&mut self, leaf: Room) -> usize {
+        // leave a column and row free so rooms in neighboring leaves don't merge
+        let max_width = (leaf.width - 1).max(1);
+        let max_height = (leaf.height - 1).max(1);
+        let min = self.options.min_room_size.max(1);
+        let width = self.rng.range(min.min(max_width), max_width + 1);
+        let height = self.rng.range(min.min(max_height), max_height + 1);
+        let room = Room {
+            x: leaf.x + self.rng.below(leaf.width - width + 1),
+            y: leaf.y + self.rng.below(leaf.height - height + 1),
+            width,
+            height,
+        };
+        for y in room.y..room.y + room.height {
+            for x in room.x..room.x + room.width {
+                self.grid.set(x, y, 1);
+            }
+        }
+        self.rooms.push(room);
+        self.rooms.len() - 1
+    }
+
+    fn corridor(&mut self, from: (u32, u32), to: (u32, u32)) {
+        let corner = if self.rng.chance(1, 2) {
+            (to.0, from.1)
+        } else {
+            (from.0, to.1)
+        };
+        for &(a, b) in [(from, corner), (corner, to)].iter() {
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                for y in a.1.min(b.1)..=a.1.max(b.1) {
+                    self.grid.set(x, y, 1);
+                }
+            }
+        }
+    }
+}
+
+pub fn generate(width: u32, height: u32, options: &DungeonOptions) -> Dungeon {
+    let mut builder = Builder {
+        grid: Grid::filled(width.max(1), height, 0).expect("dungeon width is never zero"),
+        rooms: Vec::new(),
+        rng: Rng::new(options.seed),
+        options,
+    };
+    if width >= 3 && height >= 3 {
+        builder.build(Room {
+            x: 1,
+            y: 1,
+            width: width - 2,
+            height: height - 2,
+        });
+    }
+    Dungeon {
+        grid: builder.grid,
+        rooms: builder.rooms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar_grid;
+
+    #[test]
+    fn every_room_is_reachable_from_every_other_room() {
+        let dungeon = generate(
+            60,
+            40,
+            &DungeonOptions {
+                seed: 11,
+                ..DungeonOptions::default()
+            },
+        );
+        let grid = &dungeon.grid;
+        assert!(dungeon.rooms.len() > 4);
+        let centers: Vec<u32> = dungeon
+            .rooms
+            .iter()
+            .map(|room| grid.index(room.center().0, room.center().1))
+            .collect();
+        for &start in centers.iter() {
+            for &end in centers.iter().filter(|&&end| end != start) {
+                assert!(!astar_grid(start, end, grid, true).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn rooms_are_carved_inside_the_border() {
+        let dungeon = generate(50, 30, &DungeonOptions::default());
+        for room in dungeon.rooms.iter() {
+            assert!(room.x >= 1 && room.y >= 1);
+            assert!(room.x + room.width < 50 && room.y + room.height < 30);
+            assert!(room.width >= 4 && room.height >= 4);
+            let (x, y) = room.center();
+            assert!(room.contains(x, y));
+            assert_eq!(dungeon.grid.get(x, y), Some(1));
+        }
+        for x in 0..50 {
+            assert_eq!(dungeon.grid.get(x, 0), Some(0));
+            assert_eq!(dungeon.grid.get(x, 29), Some(0));
+        }
+    }
+
+    #[test]
+    fn tiny_maps_have_no_rooms() {
+        let dungeon = generate(2, 2, &DungeonOptions::default());
+        assert!(dungeon.rooms.is_empty());
+        assert_eq!(dungeon.grid.cells(), &[0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,168 @@
+//! Perfect mazes: every floor cell is reachable from every other floor cell
+//! through exactly one route.
+//!
+//! A maze of `columns * rows` cells is laid out on a grid of
+//! `(2 * columns + 1) * (2 * rows + 1)`, with cells at odd coordinates and
+//! the walls between them at even ones. [`cell_index`] finds a cell's index
+//! in the generated grid.
+
+use crate::{Grid, Rng};
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Index in the generated grid of the maze cell at `(column, row)`.
+pub fn cell_index(columns: u32, column: u32, row: u32) -> u32 {
+    (2 * row + 1) * (2 * columns + 1) + 2 * column + 1
+}
+
+struct Carver {
+    grid: Grid,
+    columns: u32,
+    rows: u32,
+    visited: Vec<bool>,
+}
+
+impl Carver {
+    fn new(columns: u32, rows: u32) -> Self {
+        Carver {
+            grid: Grid::filled(2 * columns + 1, 2 * rows + 1, 0)
+                .expect("maze dimensions are never zero"),
+            columns,
+            rows,
+            visited: vec![false; columns as usize * rows as usize],
+        }
+    }
+
+    fn neighbor(&self, cell: (u32, u32), direction: (i32, i32)) -> Option<(u32, u32)> {
+        let column = cell.0 as i32 + direction.0;
+        let row = cell.1 as i32 + direction.1;
+        let in_bounds =
+            column >= 0 && row >= 0 && column < self.columns as i32 && row < self.rows as i32;
+        if in_bounds {
+            Some((column as u32, row as u32))
+        } else {
+            None
+        }
+    }
+
+    fn is_visited(&self, cell: (u32, u32)) -> bool {
+        self.visited[(cell.1 * self.columns + cell.0) as usize]
+    }
+
+    fn open(&mut self, cell: (u32, u32)) {
+        self.visited[(cell.1 * self.columns + cell.0) as usize] = true;
+        self.grid.set(2 * cell.0 + 1, 2 * cell.1 + 1, 1);
+    }
+
+    fn connect(&mut self, from: (u32, u32), to: (u32, u32)) {
+        self.grid.set(from.0 + to.0 + 1, from.1 + to.1 + 1, 1);
+        self.open(to);
+    }
+}
+
+/// Carves a maze with a depth-first random walk that backtracks at dead
+/// ends. These mazes have long, winding corridors and few branches.
+pub fn recursive_backtracker(columns: u32, rows: u32, seed: u64) -> Grid {
+    let mut carver = Carver::new(columns, rows);
+    if columns == 0 || rows == 0 {
+        return carver.grid;
+    }
+    let mut rng = Rng::new(seed);
+    let start = (rng.below(columns), rng.below(rows));
+    carver.open(start);
+    let mut stack = vec![start];
+    while let Some(&current) = stack.last() {
+        let mut directions = DIRECTIONS;
+        rng.shuffle(&mut directions);
+        let next = directions
+            .iter()
+            .filter_map(|&direction| carver.neighbor(current, direction))
+            .find(|&cell| !carver.is_visited(cell));
+        match next {
+            Some(cell) => {
+                carver.connect(current, cell);
+                stack.push(cell);
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    carver.grid
+}
+
+/// Grows a maze outward from a random cell with randomized Prim's algorithm.
+/// These mazes branch a lot and have many short dead ends.
+pub fn prim(columns: u32, rows: u32, seed: u64) -> Grid {
+    let mut carver = Carver::new(columns, rows);
+    if columns == 0 || rows == 0 {
+        return carver.grid;
+    }
+    let mut rng = Rng::new(seed);
+    let start = (rng.below(columns), rng.below(rows));
+    carver.open(start);
+    let mut frontier = Vec::new();
+    for &direction in DIRECTIONS.iter() {
+        if let Some(cell) = carver.neighbor(start, direction) {
+            frontier.push((start, cell));
+        }
+    }
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(rng.below(frontier.len() as u32) as usize);
+        if carver.is_visited(to) {
+            continue;
+        }
+        carver.connect(from, to);
+        for &direction in DIRECTIONS.iter() {
+            if let Some(cell) = carver.neighbor(to, direction) {
+                if !carver.is_visited(cell) {
+                    frontier.push((to, cell));
+                }
+            }
+        }
+    }
+    carver.grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar_grid;
+
+    fn assert_perfect(grid: &Grid, columns: u32, rows: u32) {
+        let cells = columns * rows;
+        let floor = grid.cells().iter().filter(|&&cost| cost > 0).count() as u32;
+        // every cell plus exactly one opening per spanning tree edge
+        assert_eq!(floor, cells + cells - 1);
+        let start = cell_index(columns, 0, 0);
+        for row in 0..rows {
+            for column in 0..columns {
+                let goal = cell_index(columns, column, row);
+                if goal != start {
+                    assert!(!astar_grid(start, goal, grid, true).is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn backtracker_mazes_are_perfect() {
+        let grid = recursive_backtracker(9, 6, 42);
+        assert_eq!((grid.width(), grid.height()), (19, 13));
+        assert_perfect(&grid, 9, 6);
+    }
+
+    #[test]
+    fn prim_mazes_are_perfect() {
+        assert_perfect(&prim(7, 8, 3), 7, 8);
+    }
+
+    #[test]
+    fn mazes_depend_only_on_the_seed() {
+        assert_eq!(prim(10, 10, 5), prim(10, 10, 5));
+        assert_ne!(
+            recursive_backtracker(10, 10, 5),
+            recursive_backtracker(10, 10, 6)
+        );
+    }
+}
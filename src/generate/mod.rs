@@ -0,0 +1,9 @@
+//! Seeded map generators, enabled by the `generate` feature.
+//!
+//! Every generator takes a seed and produces the same map for it on every
+//! platform, which makes them suitable for benchmarks and reproducible tests.
+//! Generated grids use `1` for floor and `0` for walls.
+
+pub mod cave;
+pub mod dungeon;
+pub mod maze;
@@ -0,0 +1,241 @@
+//! Goal bounding: neighbor pruning from precomputed per-direction bounding
+//! boxes.
+//!
+//! For every cell and each of its eight outgoing directions, [`GoalBounds`]
+//! stores the bounding box of all goals whose cheapest path from that cell
+//! starts in that direction. A search can then skip every step whose box
+//! doesn't contain its goal, without losing optimality, which works with
+//! weighted costs too.
+//!
+//! Building the boxes runs a Dijkstra search from every cell, so it takes
+//! time quadratic in the number of cells. It is meant to be done once, ahead
+//! of time, for static maps of moderate size.
+
+use crate::artifact::{cells, write_u32s, Reader};
+use crate::dijkstra::{first_moves, NO_MOVE};
+use crate::search::{search_with, Hooks};
+use crate::{
+    direction, search, Artifact, DecodeError, GridSource, Manhattan, SearchOptions, SearchResult,
+};
+use std::collections::BinaryHeap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Bounds {
+    min_x: u32,
+    min_y: u32,
+    max_x: u32,
+    max_y: u32,
+}
+
+impl Bounds {
+    const EMPTY: Bounds = Bounds {
+        min_x: u32::MAX,
+        min_y: u32::MAX,
+        max_x: 0,
+        max_y: 0,
+    };
+
+    fn insert(&mut self, x: u32, y: u32) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+
+    #[inline(always)]
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
+    }
+}
+
+/// Precomputed goal bounding boxes for one static grid and movement mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GoalBounds {
+    width: u32,
+    height: u32,
+    cardinal_directions: bool,
+    boxes: Vec<[Bounds; 8]>,
+}
+
+impl GoalBounds {
+    /// Builds the boxes for `grid`. The result is only valid for as long as
+    /// the grid's costs don't change.
+    pub fn new<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> Self {
+        let width = grid.width();
+        let len = grid.len() as usize;
+        let mut boxes = vec![[Bounds::EMPTY; 8]; len];
+        let mut distances = vec![u32::MAX; len];
+        let mut moves = vec![NO_MOVE; len];
+        let mut frontier = BinaryHeap::new();
+        for source in 0..grid.len() {
+            first_moves(
+                grid,
+                source,
+                cardinal_directions,
+                &mut distances,
+                &mut moves,
+                &mut frontier,
+            );
+            let cell_boxes = &mut boxes[source as usize];
+            for (goal, &first) in moves.iter().enumerate() {
+                if first != NO_MOVE {
+                    let goal = goal as u32;
+                    cell_boxes[first as usize].insert(goal % width, goal / width);
+                }
+            }
+        }
+        GoalBounds {
+            width,
+            height: grid.height(),
+            cardinal_directions,
+            boxes,
+        }
+    }
+
+    pub fn cardinal_directions(&self) -> bool {
+        self.cardinal_directions
+    }
+
+    /// Approximate heap memory used by the boxes.
+    pub fn memory_bytes(&self) -> usize {
+        self.boxes.len() * std::mem::size_of::<[Bounds; 8]>()
+    }
+
+    /// Runs [`search`] with goal bounding pruning. The movement mode is
+    /// always the one the bounds were built for, whatever `options` says.
+    ///
+    /// `grid` must be the grid the bounds were built from. When its
+    /// dimensions don't match, this falls back to an unpruned search.
+    pub fn search<G: GridSource + ?Sized>(
+        &self,
+        start: u32,
+        end: u32,
+        grid: &G,
+        options: &SearchOptions,
+    ) -> SearchResult {
+        let options = SearchOptions {
+            cardinal_directions: self.cardinal_directions,
+            ..options.clone()
+        };
+        if grid.width() != self.width || grid.height() != self.height || end >= grid.len() {
+            return search(start, end, grid, &options);
+        }
+        let pruner = Pruner {
+            bounds: self,
+            goal_x: end % self.width,
+            goal_y: end / self.width,
+        };
+        search_with(start, end, grid, &options, &Manhattan, pruner)
+    }
+}
+
+struct Pruner<'a> {
+    bounds: &'a GoalBounds,
+    goal_x: u32,
+    goal_y: u32,
+}
+
+impl<'a> Hooks for Pruner<'a> {
+    #[inline(always)]
+    fn allow_step(&mut self, from: u32, to: u32) -> bool {
+        let direction = direction(from, to, self.bounds.width);
+        self.bounds.boxes[from as usize][direction as usize].contains(self.goal_x, self.goal_y)
+    }
+}
+
+impl Artifact for GoalBounds {
+    const KIND: [u8; 3] = *b"gbb";
+    const VERSION: u8 = 1;
+
+    /// Each box as its corners, `min_x`, `min_y`, `max_x` and `max_y`, in
+    /// the order of the directions.
+    fn write_payload(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.push(self.cardinal_directions as u8);
+        let corners: Vec<u32> = self
+            .boxes
+            .iter()
+            .flatten()
+            .flat_map(|bounds| [bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y])
+            .collect();
+        write_u32s(bytes, &corners);
+    }
+
+    fn read_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(payload);
+        let (width, height) = (reader.u32()?, reader.u32()?);
+        let cardinal_directions = reader.bool()?;
+        let len = cells(width, height)?;
+        let corners = reader.u32s(len.checked_mul(32).ok_or(DecodeError::Corrupt)?)?;
+        reader.finish()?;
+        let boxes = corners
+            .chunks(32)
+            .map(|cell| {
+                let mut boxes = [Bounds::EMPTY; 8];
+                for (bounds, corners) in boxes.iter_mut().zip(cell.chunks(4)) {
+                    *bounds = Bounds {
+                        min_x: corners[0],
+                        min_y: corners[1],
+                        max_x: corners[2],
+                        max_y: corners[3],
+                    };
+                }
+                boxes
+            })
+            .collect();
+        Ok(GoalBounds {
+            width,
+            height,
+            cardinal_directions,
+            boxes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid, Rng};
+
+    #[test]
+    fn pruned_searches_stay_optimal() {
+        let mut rng = Rng::new(1);
+        for _ in 0..20 {
+            let grid = rng.grid(14, 11, 25, 5);
+            let cardinal_directions = rng.chance(1, 2);
+            let bounds = GoalBounds::new(&grid, cardinal_directions);
+            let options = SearchOptions {
+                cardinal_directions,
+                ..SearchOptions::default()
+            };
+            for _ in 0..20 {
+                let start = rng.below(grid.len());
+                let end = rng.below(grid.len());
+                let plain = search(start, end, &grid, &options);
+                let pruned = bounds.search(start, end, &grid, &options);
+                assert_eq!(pruned.cost, plain.cost);
+                assert!(pruned.stats.expanded <= plain.stats.expanded);
+            }
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_prunes_dead_ends() {
+        let grid = Grid::new(vec![
+            1, 1, 1, 1, 1, 1, 1,
+            1, 0, 0, 0, 0, 0, 1,
+            1, 0, 1, 1, 1, 0, 1,
+            1, 1, 1, 0, 1, 1, 1,
+        ], 7).unwrap();
+        let bounds = GoalBounds::new(&grid, true);
+        let options = SearchOptions::default();
+        let plain = search(21, 27, &grid, &SearchOptions { cardinal_directions: true, ..options.clone() });
+        let pruned = bounds.search(21, 27, &grid, &options);
+        assert_eq!(pruned.path, plain.path);
+        assert!(pruned.stats.expanded < plain.stats.expanded);
+    }
+}
@@ -0,0 +1,57 @@
+// One relaxation pass of a flow field's integration step: every walkable
+// cell takes the cheapest of its neighbors' distances plus the cost of
+// stepping into that neighbor. Passes repeat until none changes anything.
+
+struct Params {
+    width: u32,
+    height: u32,
+    cardinal: u32,
+    // invocations per row of the dispatch grid
+    stride: u32,
+}
+
+const UNREACHED: u32 = 0xffffffffu;
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> costs: array<u32>;
+@group(0) @binding(2) var<storage, read_write> distances: array<atomic<u32>>;
+@group(0) @binding(3) var<storage, read_write> changed: atomic<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let cell = id.y * params.stride + id.x;
+    if (cell >= params.width * params.height || costs[cell] == 0u) {
+        return;
+    }
+    let x = i32(cell % params.width);
+    let y = i32(cell / params.width);
+    let current = atomicLoad(&distances[cell]);
+    var best = current;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            if ((dx == 0 && dy == 0) || (params.cardinal != 0u && dx != 0 && dy != 0)) {
+                continue;
+            }
+            let nx = x + dx;
+            let ny = y + dy;
+            if (nx < 0 || ny < 0 || nx >= i32(params.width) || ny >= i32(params.height)) {
+                continue;
+            }
+            let neighbor = u32(ny) * params.width + u32(nx);
+            let cost = costs[neighbor];
+            let distance = atomicLoad(&distances[neighbor]);
+            if (cost == 0u || distance == UNREACHED) {
+                continue;
+            }
+            // saturating, like the CPU integration
+            let length = u32(abs(dx) + abs(dy));
+            let step = select(cost + length, UNREACHED, cost > UNREACHED - length);
+            let candidate = select(distance + step, UNREACHED, distance > UNREACHED - step);
+            best = min(best, candidate);
+        }
+    }
+    if (best < current) {
+        atomicMin(&distances[cell], best);
+        atomicStore(&changed, 1u);
+    }
+}
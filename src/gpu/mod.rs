@@ -0,0 +1,283 @@
+//! Flow-field integration on the GPU through `wgpu`, enabled by the `gpu`
+//! feature.
+//!
+//! Integrating a field over a large map is the slow part of moving crowds,
+//! and unlike a search it parallelizes well: every cell repeatedly takes
+//! the cheapest of its neighbors' distances until nothing changes. The
+//! result is the same [`FlowField`] that [`FlowField::new`] builds on the
+//! CPU, which stays the portable fallback when no adapter is available.
+
+use crate::{FlowField, GridSource};
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc;
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = include_str!("integrate.wgsl");
+const WORKGROUP_SIZE: u32 = 64;
+/// Relaxation passes submitted between checks for convergence, trading a
+/// few wasted passes for fewer round trips to the device.
+const PASSES_PER_CHECK: u32 = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuError {
+    /// No adapter could be found, so the CPU integration has to do.
+    NoAdapter,
+    /// The adapter refused to create a device.
+    Device(String),
+    /// The grid's buffers are bigger than the device can bind.
+    TooLarge { cells: u32 },
+    /// Reading the results back from the device failed.
+    Readback(String),
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuError::NoAdapter => write!(f, "no GPU adapter is available"),
+            GpuError::Device(reason) => write!(f, "could not create a GPU device: {}", reason),
+            GpuError::TooLarge { cells } => {
+                write!(f, "{} cells are more than the GPU can bind at once", cells)
+            }
+            GpuError::Readback(reason) => write!(f, "could not read back from the GPU: {}", reason),
+        }
+    }
+}
+
+impl Error for GpuError {}
+
+/// A device and compiled pipeline for integrating [`FlowField`]s, kept
+/// around so that each field doesn't pay for setting them up.
+pub struct GpuFlowFields {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl fmt::Debug for GpuFlowFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GpuFlowFields").finish_non_exhaustive()
+    }
+}
+
+impl GpuFlowFields {
+    /// Sets up the default adapter, blocking until it is ready.
+    pub fn new() -> Result<Self, GpuError> {
+        pollster::block_on(Self::request())
+    }
+
+    async fn request() -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .ok_or(GpuError::NoAdapter)?;
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("flow fields"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::downlevel_defaults()
+                        .using_resolution(adapter.limits()),
+                    memory_hints: wgpu::MemoryHints::Performance,
+                },
+                None,
+            )
+            .await
+            .map_err(|error| GpuError::Device(error.to_string()))?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("flow field integration"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("flow field integration"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Ok(GpuFlowFields {
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    /// Integrates a field toward `goals`, like [`FlowField::new`]. The
+    /// distances are identical; where several steps are equally cheap the
+    /// directions may pick a different one.
+    pub fn flow_field<G: GridSource + ?Sized>(
+        &self,
+        grid: &G,
+        goals: &[u32],
+        cardinal_directions: bool,
+    ) -> Result<FlowField, GpuError> {
+        let cells = grid.len();
+        let size = cells as u64 * 4;
+        let limits = self.device.limits();
+        if size > limits.max_storage_buffer_binding_size as u64 || size > limits.max_buffer_size {
+            return Err(GpuError::TooLarge { cells });
+        }
+
+        let costs: Vec<u32> = (0..cells).map(|cell| grid.cost(cell)).collect();
+        let mut distances = vec![u32::MAX; cells as usize];
+        for &goal in goals.iter().filter(|&&goal| goal < cells) {
+            distances[goal as usize] = 0;
+        }
+        if cells == 0 {
+            return Ok(FlowField::from_distances(
+                grid,
+                distances,
+                cardinal_directions,
+            ));
+        }
+
+        // a dispatch has at most `max_compute_workgroups_per_dimension`
+        // workgroups a side, so big grids wrap onto more rows
+        let workgroups = cells.div_ceil(WORKGROUP_SIZE).max(1);
+        let columns = workgroups.min(limits.max_compute_workgroups_per_dimension);
+        let rows = workgroups.div_ceil(columns);
+        let params = [
+            grid.width(),
+            grid.height(),
+            cardinal_directions as u32,
+            columns * WORKGROUP_SIZE,
+        ];
+
+        let params = self.buffer("params", &params, wgpu::BufferUsages::UNIFORM);
+        let costs = self.buffer("costs", &costs, wgpu::BufferUsages::STORAGE);
+        let distances = self.buffer(
+            "distances",
+            &distances,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        );
+        let changed = self.buffer(
+            "changed",
+            &[0],
+            wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+        );
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                entry(0, &params),
+                entry(1, &costs),
+                entry(2, &distances),
+                entry(3, &changed),
+            ],
+        });
+
+        loop {
+            self.queue.write_buffer(&changed, 0, &0u32.to_le_bytes());
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                for _ in 0..PASSES_PER_CHECK {
+                    pass.dispatch_workgroups(columns, rows, 1);
+                }
+            }
+            self.queue.submit(Some(encoder.finish()));
+            if self.read(&changed, 1)?[0] == 0 {
+                break;
+            }
+        }
+
+        let distances = self.read(&distances, cells as usize)?;
+        Ok(FlowField::from_distances(
+            grid,
+            distances,
+            cardinal_directions,
+        ))
+    }
+
+    fn buffer(&self, label: &str, contents: &[u32], usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        let bytes: Vec<u8> = contents
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        self.device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: &bytes,
+                usage,
+            })
+    }
+
+    /// Copies the first `len` values of `source` back to the CPU, blocking
+    /// until the device is done with it.
+    fn read(&self, source: &wgpu::Buffer, len: usize) -> Result<Vec<u32>, GpuError> {
+        let size = len as u64 * 4;
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(source, 0, &staging, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .map_err(|error| GpuError::Readback(error.to_string()))?
+            .map_err(|error| GpuError::Readback(error.to_string()))?;
+        let values = slice
+            .get_mapped_range()
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        staging.unmap();
+        Ok(values)
+    }
+}
+
+fn entry(binding: u32, buffer: &wgpu::Buffer) -> wgpu::BindGroupEntry<'_> {
+    wgpu::BindGroupEntry {
+        binding,
+        resource: buffer.as_entire_binding(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rng;
+
+    #[test]
+    fn matches_the_cpu_integration() {
+        let gpu = match GpuFlowFields::new() {
+            Ok(gpu) => gpu,
+            // nothing to compare against on machines without an adapter
+            Err(GpuError::NoAdapter) => return,
+            Err(error) => panic!("{}", error),
+        };
+        let mut rng = Rng::new(1);
+        let grid = rng.grid(40, 30, 20, 5);
+        for &cardinal in &[false, true] {
+            let expected = FlowField::new(&grid, &[5, 611], cardinal);
+            let field = gpu.flow_field(&grid, &[5, 611], cardinal).unwrap();
+            for cell in 0..grid.len() {
+                assert_eq!(field.distance(cell), expected.distance(cell));
+                if let Some(next) = field.next_step(cell) {
+                    assert_eq!(
+                        field
+                            .distance(next)
+                            .map(|d| d + crate::step_cost(&grid, cell, next)),
+                        field.distance(cell)
+                    );
+                }
+            }
+        }
+    }
+}
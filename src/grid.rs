@@ -0,0 +1,642 @@
+use std::error::Error;
+use std::fmt;
+
+/// The cost that marks a cell as a wall, which no path enters. Every other
+/// cost is walkable, and stepping onto a cell always costs at least one
+/// more than its cost, so the accumulated cost of a path is `0` only for a
+/// path that hasn't left the start.
+///
+/// Searches keep what they know about each cell in maps that say whether
+/// it was reached at all, so `0` means nothing there but a wall. Code that
+/// compared costs against `0` to find walls keeps working and is clearer
+/// against this name; grids only need to change if they used `0` for
+/// something else, which never worked.
+pub const WALL: u32 = 0;
+
+/// The cost that marks a cell nobody has seen yet, for maps revealed as
+/// they are explored. What a search makes of it is up to
+/// [`crate::SearchOptions::unknown_cells`]: entering it like an open cell,
+/// treating it as a wall, or, by default, reading it as the highest cost
+/// there is, as before it meant anything of its own.
+pub const UNKNOWN: u32 = u32::MAX;
+
+/// Anything the search functions can read movement costs from.
+///
+/// Cells are addressed by their row-major index (`y * width + x`), and a cost
+/// of [`WALL`] marks a cell as impassable.
+pub trait GridSource {
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+    /// Cost of entering the cell at `index`. Only called with indices below `len()`.
+    fn cost(&self, index: u32) -> u32;
+
+    #[inline(always)]
+    fn len(&self) -> u32 {
+        self.width() * self.height()
+    }
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether every cell on the edges is a wall, which lets searches skip
+    /// checking for the edges. Only cells inside the border may be searched
+    /// from when this is true.
+    #[doc(hidden)]
+    #[inline(always)]
+    fn has_wall_border(&self) -> bool {
+        false
+    }
+}
+
+impl<G: GridSource + ?Sized> GridSource for &G {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        (**self).width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        (**self).height()
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        (**self).cost(index)
+    }
+
+    #[inline(always)]
+    fn len(&self) -> u32 {
+        (**self).len()
+    }
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
+    }
+
+    #[inline(always)]
+    fn has_wall_border(&self) -> bool {
+        (**self).has_wall_border()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridError {
+    /// The width was zero.
+    ZeroWidth,
+    /// The number of cells is not a multiple of the width.
+    RaggedRows { len: usize, width: u32 },
+    /// A row given to [`Grid::from_rows`] is not as long as the first one.
+    UnevenRow { row: usize, len: usize, width: u32 },
+    /// The grid has more cells than can be addressed with a `u32` index.
+    TooLarge { len: usize },
+    /// A requested window, or a cell to change, lies outside the grid.
+    OutOfBounds,
+    /// Grids that have to be the same size aren't.
+    SizeMismatch,
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::ZeroWidth => write!(f, "grid width must be greater than zero"),
+            GridError::RaggedRows { len, width } => write!(
+                f,
+                "{} cells cannot be split into rows of width {}",
+                len, width
+            ),
+            GridError::UnevenRow { row, len, width } => write!(
+                f,
+                "row {} has {} cells, but the first row has {}",
+                row, len, width
+            ),
+            GridError::TooLarge { len } => {
+                write!(f, "{} cells cannot be addressed with a u32 index", len)
+            }
+            GridError::OutOfBounds => write!(f, "window does not fit inside the grid"),
+            GridError::SizeMismatch => write!(f, "grids are not all the same size"),
+        }
+    }
+}
+
+impl Error for GridError {}
+
+/// A cell payload that knows its own movement cost, so that a [`Grid`] of
+/// game tiles can be searched directly. `0` marks the cell as impassable.
+pub trait Cost {
+    fn cost(&self) -> u32;
+}
+
+impl Cost for u32 {
+    #[inline(always)]
+    fn cost(&self) -> u32 {
+        *self
+    }
+}
+
+fn validate(len: usize, width: u32) -> Result<u32, GridError> {
+    if width == 0 {
+        return Err(GridError::ZeroWidth);
+    }
+    if len > u32::MAX as usize {
+        return Err(GridError::TooLarge { len });
+    }
+    if !len.is_multiple_of(width as usize) {
+        return Err(GridError::RaggedRows { len, width });
+    }
+    Ok(len as u32 / width)
+}
+
+/// An owned, row-major grid of cells, which are plain movement costs unless
+/// some other payload is given.
+///
+/// Grids of any [`Cost`] payload can be searched as they are. For payloads
+/// that don't implement it, [`Grid::costs_by`] supplies the costs from a
+/// closure instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T = u32> {
+    cells: Vec<T>,
+    width: u32,
+    height: u32,
+}
+
+impl<T> Grid<T> {
+    /// Takes ownership of row-major `cells`. Passing a `Vec` or a boxed
+    /// slice reuses its allocation; to search a borrowed slice without
+    /// copying it, use [`GridView::new`] instead.
+    pub fn new(cells: impl Into<Vec<T>>, width: u32) -> Result<Self, GridError> {
+        let cells = cells.into();
+        let height = validate(cells.len(), width)?;
+        Ok(Grid {
+            cells,
+            width,
+            height,
+        })
+    }
+
+    /// Copies `rows` top to bottom into a grid as wide as the first row. See
+    /// [`grid!`](crate::grid!) for writing them inline.
+    pub fn from_rows<R: AsRef<[T]>>(rows: &[R]) -> Result<Self, GridError>
+    where
+        T: Clone,
+    {
+        let width = rows.first().map_or(0, |row| row.as_ref().len());
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for (row, cells_in_row) in rows.iter().enumerate() {
+            let cells_in_row = cells_in_row.as_ref();
+            if cells_in_row.len() != width {
+                return Err(GridError::UnevenRow {
+                    row,
+                    len: cells_in_row.len(),
+                    width: width as u32,
+                });
+            }
+            cells.extend_from_slice(cells_in_row);
+        }
+        if width > u32::MAX as usize {
+            return Err(GridError::TooLarge { len: cells.len() });
+        }
+        Grid::new(cells, width as u32)
+    }
+
+    /// A `width * height` grid with every cell set to `value`.
+    pub fn filled(width: u32, height: u32, value: T) -> Result<Self, GridError>
+    where
+        T: Clone,
+    {
+        Grid::new(vec![value; width as usize * height as usize], width)
+    }
+
+    #[inline(always)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline(always)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline(always)]
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    pub fn into_cells(self) -> Vec<T> {
+        self.cells
+    }
+
+    #[inline(always)]
+    pub fn index(&self, x: u32, y: u32) -> u32 {
+        y * self.width + x
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<T>
+    where
+        T: Copy,
+    {
+        self.get_ref(x, y).copied()
+    }
+
+    pub fn get_ref(&self, x: u32, y: u32) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[self.index(x, y) as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            let index = self.index(x, y) as usize;
+            Some(&mut self.cells[index])
+        } else {
+            None
+        }
+    }
+
+    /// Replaces a cell, returning the previous one, or `None` when the
+    /// coordinates are outside the grid.
+    pub fn set(&mut self, x: u32, y: u32, value: T) -> Option<T> {
+        self.get_mut(x, y)
+            .map(|cell| std::mem::replace(cell, value))
+    }
+
+    /// Borrows the whole grid as a view.
+    pub fn as_view(&self) -> GridView<'_, T> {
+        GridView {
+            cells: &self.cells,
+            stride: self.width,
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Borrows the `width * height` window whose top left corner is at `(x, y)`.
+    pub fn view(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<GridView<'_, T>, GridError> {
+        self.as_view().view(x, y, width, height)
+    }
+
+    /// Searches the grid with costs read from each cell by `cost`.
+    pub fn costs_by<F: Fn(&T) -> u32>(&self, cost: F) -> CostsBy<'_, T, F> {
+        self.as_view().costs_by(cost)
+    }
+}
+
+/// A [`Grid`] of movement costs written row by row, with rows separated by
+/// semicolons and the width taken from the first row.
+///
+/// ```
+/// let grid = simple_astar::grid![
+///     1, 1, 1;
+///     1, 0, 1;
+///     1, 1, 1;
+/// ];
+/// assert_eq!(simple_astar::astar_grid(0, 8, &grid, true), vec![3, 6, 7, 8]);
+/// ```
+///
+/// # Panics
+///
+/// When the rows aren't all the same length.
+#[macro_export]
+macro_rules! grid {
+    ($($($cost:expr),+ $(,)?);+ $(;)?) => {
+        $crate::Grid::<u32>::from_rows(&[$(&[$($cost),+][..]),+])
+            .expect("grid! rows must all be the same length")
+    };
+}
+
+impl<T: Cost> GridSource for Grid<T> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        self.cells[index as usize].cost()
+    }
+}
+
+/// A read-only rectangular window into a larger row-major grid.
+///
+/// Views don't copy anything. Indices passed to and returned from searches
+/// over a view are local to the view; use [`GridView::to_parent`] and
+/// [`GridView::from_parent`] to translate them.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GridView<'a, T = u32> {
+    cells: &'a [T],
+    stride: u32,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl<'a, T> Clone for GridView<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for GridView<'a, T> {}
+
+impl<'a, T> GridView<'a, T> {
+    /// Views a whole row-major slice of cells.
+    pub fn new(cells: &'a [T], width: u32) -> Result<Self, GridError> {
+        let height = validate(cells.len(), width)?;
+        Ok(GridView {
+            cells,
+            stride: width,
+            x: 0,
+            y: 0,
+            width,
+            height,
+        })
+    }
+
+    /// Narrows this view to the `width * height` window whose top left corner
+    /// is at `(x, y)`, in this view's coordinates.
+    pub fn view(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<GridView<'a, T>, GridError> {
+        if width == 0 {
+            return Err(GridError::ZeroWidth);
+        }
+        let fits_x = x
+            .checked_add(width)
+            .is_some_and(|right| right <= self.width);
+        let fits_y = y
+            .checked_add(height)
+            .is_some_and(|bottom| bottom <= self.height);
+        if !fits_x || !fits_y {
+            return Err(GridError::OutOfBounds);
+        }
+        Ok(GridView {
+            cells: self.cells,
+            stride: self.stride,
+            x: self.x + x,
+            y: self.y + y,
+            width,
+            height,
+        })
+    }
+
+    #[inline(always)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline(always)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Offset of this view's top left corner in the underlying slice.
+    pub fn origin(&self) -> (u32, u32) {
+        (self.x, self.y)
+    }
+
+    /// Translates an index local to this view into an index of the underlying slice.
+    #[inline(always)]
+    pub fn to_parent(&self, index: u32) -> u32 {
+        let row = index / self.width;
+        let column = index - row * self.width;
+        (self.y + row) * self.stride + self.x + column
+    }
+
+    /// Translates an index of the underlying slice into an index local to
+    /// this view, or `None` when the cell lies outside the view.
+    pub fn from_parent(&self, index: u32) -> Option<u32> {
+        let parent_x = index % self.stride;
+        let parent_y = index / self.stride;
+        if parent_x < self.x || parent_y < self.y {
+            return None;
+        }
+        let x = parent_x - self.x;
+        let y = parent_y - self.y;
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    /// Searches the view with costs read from each cell by `cost`.
+    pub fn costs_by<F: Fn(&T) -> u32>(self, cost: F) -> CostsBy<'a, T, F> {
+        CostsBy { view: self, cost }
+    }
+}
+
+impl<'a, T: Cost> GridSource for GridView<'a, T> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        self.cells[self.to_parent(index) as usize].cost()
+    }
+}
+
+/// A [`GridView`] whose costs come from a closure, made by
+/// [`Grid::costs_by`] or [`GridView::costs_by`].
+#[derive(Clone, Copy)]
+pub struct CostsBy<'a, T, F> {
+    view: GridView<'a, T>,
+    cost: F,
+}
+
+impl<'a, T, F: Fn(&T) -> u32> GridSource for CostsBy<'a, T, F> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.view.width
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.view.height
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        (self.cost)(&self.view.cells[self.view.to_parent(index) as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_rejects_ragged_grids() {
+        assert_eq!(
+            Grid::new(vec![1; 7], 3),
+            Err(GridError::RaggedRows { len: 7, width: 3 })
+        );
+        assert_eq!(Grid::new(vec![1; 4], 0), Err(GridError::ZeroWidth));
+    }
+
+    #[test]
+    fn references_read_like_their_grids() {
+        /// Overrides that disagree with the defaults, to tell forwarding
+        /// apart from them.
+        struct FirstRow;
+
+        impl GridSource for FirstRow {
+            fn width(&self) -> u32 {
+                4
+            }
+
+            fn height(&self) -> u32 {
+                3
+            }
+
+            fn cost(&self, _index: u32) -> u32 {
+                1
+            }
+
+            fn len(&self) -> u32 {
+                4
+            }
+
+            fn is_empty(&self) -> bool {
+                true
+            }
+
+            fn has_wall_border(&self) -> bool {
+                true
+            }
+        }
+
+        let by_ref = &FirstRow;
+        assert_eq!(GridSource::len(&by_ref), 4);
+        assert!(GridSource::is_empty(&by_ref));
+        assert!(GridSource::has_wall_border(&by_ref));
+    }
+
+    #[test]
+    fn it_builds_grids_from_rows() {
+        let grid = crate::grid![
+            1, 2, 3;
+            4, 5, 6;
+        ];
+        assert_eq!(grid, Grid::new(vec![1, 2, 3, 4, 5, 6], 3).unwrap());
+        assert_eq!(
+            Grid::from_rows(&[vec![1, 2], vec![3]]),
+            Err(GridError::UnevenRow {
+                row: 1,
+                len: 1,
+                width: 2
+            })
+        );
+        assert_eq!(
+            Grid::<u32>::from_rows::<&[u32]>(&[]),
+            Err(GridError::ZeroWidth)
+        );
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn views_read_through_to_the_parent() {
+        let grid = Grid::new(vec![
+            1, 2, 3, 4,
+            5, 6, 7, 8,
+            9, 10, 11, 12,
+        ], 4).unwrap();
+        let view = grid.view(1, 1, 2, 2).unwrap();
+        assert_eq!(view.len(), 4);
+        assert_eq!(
+            (0..view.len()).map(|i| view.cost(i)).collect::<Vec<_>>(),
+            vec![6, 7, 10, 11]
+        );
+        assert_eq!(view.to_parent(3), 10);
+        assert_eq!(view.from_parent(10), Some(3));
+        assert_eq!(view.from_parent(4), None);
+        let nested = view.view(1, 0, 1, 2).unwrap();
+        assert_eq!(nested.cost(1), 11);
+        assert_eq!(grid.view(3, 0, 2, 1), Err(GridError::OutOfBounds));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Terrain {
+        Grass,
+        Mud,
+        Rock,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Tile {
+        terrain: Terrain,
+        occupant: Option<&'static str>,
+    }
+
+    impl Cost for Tile {
+        fn cost(&self) -> u32 {
+            match (self.terrain, self.occupant) {
+                (Terrain::Rock, _) | (_, Some(_)) => 0,
+                (Terrain::Mud, None) => 4,
+                (Terrain::Grass, None) => 1,
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_carry_their_own_costs() {
+        let tile = |terrain| Tile {
+            terrain,
+            occupant: None,
+        };
+        let mut grid = Grid::filled(3, 2, tile(Terrain::Grass)).unwrap();
+        grid.set(1, 0, tile(Terrain::Rock));
+        grid.get_mut(2, 1).unwrap().terrain = Terrain::Mud;
+        assert_eq!(
+            grid.get_ref(1, 0).map(|tile| tile.terrain),
+            Some(Terrain::Rock)
+        );
+        assert_eq!(
+            (0..grid.len())
+                .map(|i| GridSource::cost(&grid, i))
+                .collect::<Vec<_>>(),
+            vec![1, 0, 1, 1, 1, 4]
+        );
+        grid.get_mut(0, 1).unwrap().occupant = Some("goblin");
+        assert_eq!(GridSource::cost(&grid.view(0, 1, 2, 1).unwrap(), 0), 0);
+        let flying = grid.costs_by(|tile| if tile.occupant.is_some() { 0 } else { 1 });
+        assert_eq!(
+            (0..flying.len())
+                .map(|i| flying.cost(i))
+                .collect::<Vec<_>>(),
+            vec![1, 1, 1, 0, 1, 1]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Stable 64-bit hashes of maps and paths, so that clients can tell that a
+//! route or a map is unchanged without comparing it cell by cell, and skip
+//! sending or animating it again.
+//!
+//! Both are FNV-1a over little-endian `u32`s, and are frozen: the same map
+//! or path hashes the same on every platform, in every run and in every
+//! version of this crate, so hashes can be stored and sent between builds.
+//! Should they ever need to change, it will be under new names.
+
+use crate::GridSource;
+
+/// FNV-1a, fed a word at a time.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn feed(&mut self, word: u32) {
+        for byte in word.to_le_bytes().iter() {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// A fingerprint of the size and costs of `grid`: its width, height and
+/// every cost in row-major order. Grids of any type with the same costs
+/// hash the same.
+pub fn map_hash<G: GridSource + ?Sized>(grid: &G) -> u64 {
+    let mut hash = Fnv::new();
+    hash.feed(grid.width());
+    hash.feed(grid.height());
+    for cell in 0..grid.len() {
+        hash.feed(grid.cost(cell));
+    }
+    hash.0
+}
+
+/// A fingerprint of a path leaving `start` through `cells`, as searches
+/// return them: the start, the number of cells and then the cells. Paths
+/// along the same cells from the same start hash the same whatever they
+/// cost, so a replanned route that only got cheaper counts as unchanged;
+/// see [`crate::Path::same_cost`] for that.
+pub fn path_hash(start: u32, cells: &[u32]) -> u64 {
+    let mut hash = Fnv::new();
+    hash.feed(start);
+    hash.feed(cells.len() as u32);
+    for &cell in cells {
+        hash.feed(cell);
+    }
+    hash.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid, Path, SearchOptions};
+
+    #[test]
+    fn hashes_stay_the_same_between_versions() {
+        // these values are part of the format, and must never change
+        assert_eq!(path_hash(0, &[]), 0xa8c7_f832_281a_39c5);
+        assert_eq!(path_hash(7, &[8, 9, 17]), 0x2bf2_3bcd_d729_3681);
+        assert_eq!(
+            map_hash(&Grid::filled(3, 2, 1).unwrap()),
+            0x818e_549c_9850_1af4
+        );
+    }
+
+    #[test]
+    fn replanned_routes_are_spotted() {
+        let mut grid = Grid::filled(8, 8, 1).unwrap();
+        let options = SearchOptions::default();
+        let before = search(0, 63, &grid, &options).path;
+        grid.set(3, 5, 0);
+        let unchanged = search(0, 63, &grid, &options).path;
+        assert_eq!(path_hash(0, &unchanged), path_hash(0, &before));
+        grid.set(4, 4, 0);
+        let detour = search(0, 63, &grid, &options).path;
+        assert_ne!(path_hash(0, &detour), path_hash(0, &before));
+        assert_ne!(path_hash(1, &before), path_hash(0, &before));
+        let path = Path::new(&grid, 0, before.clone());
+        assert_eq!(path.stable_hash(), path_hash(0, &before));
+    }
+}
@@ -0,0 +1,123 @@
+//! Heatmap PNG export of [`DijkstraMap`]s, enabled by the `png` feature.
+//!
+//! Distances are drawn on a gradient from blue for the sources to red for
+//! the farthest reachable cell. Walls are black and cells that are neither
+//! walls nor reachable are hatched grey.
+
+use crate::{DijkstraMap, GridSource};
+use std::io::Write;
+
+const WALL: [u8; 3] = [0, 0, 0];
+const HATCH_LIGHT: [u8; 3] = [160, 160, 160];
+const HATCH_DARK: [u8; 3] = [96, 96, 96];
+
+/// Stops of the distance gradient, from nearest to farthest.
+const GRADIENT: [[u8; 3]; 5] = [
+    [48, 18, 160],
+    [30, 150, 220],
+    [60, 200, 90],
+    [250, 220, 40],
+    [220, 40, 30],
+];
+
+fn gradient(distance: u32, max: u32) -> [u8; 3] {
+    if max == 0 {
+        return GRADIENT[0];
+    }
+    let steps = (GRADIENT.len() - 1) as u64;
+    // position along the gradient in 1/256ths of a stop
+    let position = distance as u64 * steps * 256 / max as u64;
+    let stop = ((position / 256) as usize).min(GRADIENT.len() - 2);
+    let t = (position - stop as u64 * 256) as i32;
+    let (from, to) = (GRADIENT[stop], GRADIENT[stop + 1]);
+    let mut color = [0; 3];
+    for channel in 0..3 {
+        let (a, b) = (from[channel] as i32, to[channel] as i32);
+        color[channel] = (a + (b - a) * t / 256) as u8;
+    }
+    color
+}
+
+impl DijkstraMap {
+    /// Renders the map as an RGB image of `grid`, which must be the grid it
+    /// was built from, with every cell drawn as a `scale * scale` square.
+    /// Returns the image width, height and row-major pixels.
+    pub fn to_rgb<G: GridSource + ?Sized>(&self, grid: &G, scale: u32) -> (u32, u32, Vec<u8>) {
+        let scale = scale.max(1);
+        let width = grid.width();
+        let height = grid.height();
+        let max = self
+            .distances()
+            .iter()
+            .copied()
+            .filter(|&distance| distance != u32::MAX)
+            .max()
+            .unwrap_or(0);
+        let (image_width, image_height) = (width * scale, height * scale);
+        let mut pixels = Vec::with_capacity(image_width as usize * image_height as usize * 3);
+        for py in 0..image_height {
+            for px in 0..image_width {
+                let index = (py / scale) * width + px / scale;
+                let color = if grid.cost(index) == crate::WALL {
+                    WALL
+                } else {
+                    match self.distance(index) {
+                        Some(distance) => gradient(distance, max),
+                        None if (px + py) % 4 < 2 => HATCH_LIGHT,
+                        None => HATCH_DARK,
+                    }
+                };
+                pixels.extend_from_slice(&color);
+            }
+        }
+        (image_width, image_height, pixels)
+    }
+
+    /// Encodes [`DijkstraMap::to_rgb`] as a PNG into `writer`.
+    pub fn write_png<G: GridSource + ?Sized, W: Write>(
+        &self,
+        grid: &G,
+        scale: u32,
+        writer: W,
+    ) -> Result<(), png::EncodingError> {
+        let (width, height, pixels) = self.to_rgb(grid, scale);
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&pixels)?;
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_draws_walls_gradients_and_hatching() {
+        let grid = Grid::new(vec![
+            1, 1, 0, 1,
+            1, 1, 0, 1,
+        ], 4).unwrap();
+        let map = DijkstraMap::new(&grid, &[0], true);
+        let mut png_bytes = Vec::new();
+        map.write_png(&grid, 4, &mut png_bytes).unwrap();
+        let decoder = png::Decoder::new(png_bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (16, 8));
+        let pixel = |x: usize, y: usize| {
+            let at = (y * 16 + x) * 3;
+            [pixels[at], pixels[at + 1], pixels[at + 2]]
+        };
+        assert_eq!(pixel(0, 0), GRADIENT[0]);
+        assert_eq!(pixel(7, 7), GRADIENT[4]);
+        assert_eq!(pixel(9, 1), WALL);
+        assert_eq!(pixel(12, 0), HATCH_LIGHT);
+        assert_eq!(pixel(14, 0), HATCH_DARK);
+    }
+}
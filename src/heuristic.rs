@@ -0,0 +1,470 @@
+use crate::artifact::{write_u32s, Reader};
+use crate::{manhattan, Artifact, DecodeError, DijkstraMap, FlowField, GridSource, Rng, WALL};
+use std::error::Error;
+use std::fmt;
+
+/// Estimates the cost of the cheapest route between two cells, steering
+/// [`crate::search_with_heuristic`].
+///
+/// Searches stay optimal as long as the estimate never exceeds the real cost.
+/// Estimates must also be deterministic: the same query must always give the
+/// same answer.
+pub trait Heuristic {
+    /// Estimated cost from `cell` to `goal` on a grid `width` cells wide.
+    fn estimate(&self, cell: u32, goal: u32, width: u32) -> u32;
+
+    /// Writes the estimate from each of `neighbors`, the neighbors of `cell`
+    /// that a search is about to consider, into `out`. Searches call this
+    /// once per expansion, so heuristics can estimate them all at once;
+    /// [`Manhattan`] does with SIMD under the `simd` feature.
+    #[inline(always)]
+    fn estimate_neighbors(
+        &self,
+        cell: u32,
+        neighbors: &[u32],
+        goal: u32,
+        width: u32,
+        out: &mut [u32],
+    ) {
+        let _ = cell;
+        for (&neighbor, out) in neighbors.iter().zip(out) {
+            *out = self.estimate(neighbor, goal, width);
+        }
+    }
+
+    /// How many percent of a never-overestimating heuristic this one gives
+    /// at most, rounded up: `100` unless it is inflated on purpose, as
+    /// [`Weighted`] is. Searches scale estimates back down by this to prove
+    /// how close to the cheapest their paths are, and report that in
+    /// [`crate::SearchResult::bound_percent`].
+    fn inflation_percent(&self) -> u32 {
+        100
+    }
+}
+
+/// The distance along both axes. Every step costs at least one per axis it
+/// moves along, so this never overestimates. It is what [`crate::astar`] and
+/// [`crate::search`] use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Manhattan;
+
+impl Heuristic for Manhattan {
+    #[inline(always)]
+    fn estimate(&self, cell: u32, goal: u32, width: u32) -> u32 {
+        manhattan(
+            (cell % width) as i32,
+            (cell / width) as i32,
+            (goal % width) as i32,
+            (goal / width) as i32,
+        )
+    }
+
+    #[inline(always)]
+    fn estimate_neighbors(
+        &self,
+        cell: u32,
+        neighbors: &[u32],
+        goal: u32,
+        width: u32,
+        out: &mut [u32],
+    ) {
+        crate::simd::manhattan_neighbors(cell, neighbors, goal, width, out)
+    }
+}
+
+/// Another heuristic scaled by `numerator / denominator`. Weights above one
+/// make searches expand fewer cells at the price of paths up to that many
+/// times costlier than the cheapest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weighted<H> {
+    pub heuristic: H,
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl<H> Weighted<H> {
+    pub fn new(heuristic: H, numerator: u32, denominator: u32) -> Self {
+        Weighted {
+            heuristic,
+            numerator,
+            denominator: denominator.max(1),
+        }
+    }
+}
+
+impl<H: Heuristic> Heuristic for Weighted<H> {
+    #[inline(always)]
+    fn estimate(&self, cell: u32, goal: u32, width: u32) -> u32 {
+        let estimate = self.heuristic.estimate(cell, goal, width) as u64;
+        (estimate * self.numerator as u64 / self.denominator.max(1) as u64).min(u32::MAX as u64)
+            as u32
+    }
+
+    #[inline(always)]
+    fn estimate_neighbors(
+        &self,
+        cell: u32,
+        neighbors: &[u32],
+        goal: u32,
+        width: u32,
+        out: &mut [u32],
+    ) {
+        self.heuristic
+            .estimate_neighbors(cell, neighbors, goal, width, out);
+        for estimate in out.iter_mut().take(neighbors.len()) {
+            *estimate = (*estimate as u64 * self.numerator as u64 / self.denominator.max(1) as u64)
+                .min(u32::MAX as u64) as u32;
+        }
+    }
+
+    fn inflation_percent(&self) -> u32 {
+        // weights below one leave the estimate admissible
+        let percent = (self.heuristic.inflation_percent() as u64 * self.numerator as u64)
+            .div_ceil(self.denominator.max(1) as u64);
+        percent.clamp(100, u32::MAX as u64) as u32
+    }
+}
+
+/// The most [`Manhattan`] can be scaled by on `grid`, in percent, without
+/// ever overestimating. It depends on the cheapest walkable cell: every
+/// step costs at least that plus one per axis it moves along, which is
+/// one unit of the estimate per axis.
+pub fn admissible_weight_percent<G: GridSource + ?Sized>(
+    grid: &G,
+    cardinal_directions: bool,
+) -> u32 {
+    let cheapest = (0..grid.len())
+        .map(|cell| grid.cost(cell))
+        .filter(|&cost| cost != WALL)
+        .min()
+        .unwrap_or(1) as u64;
+    let percent = if cardinal_directions {
+        (cheapest + 1) * 100
+    } else {
+        // a diagonal step moves along both axes for the price of one cell
+        (cheapest + 2) * 50
+    };
+    percent.min(u32::MAX as u64) as u32
+}
+
+/// A [`Weighted`] heuristic that could overestimate on a grid, from
+/// [`check_weight`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InadmissibleWeight {
+    /// The weight asked for, in percent, rounded down.
+    pub weight_percent: u32,
+    /// The most that is safe; see [`admissible_weight_percent`].
+    pub max_percent: u32,
+}
+
+impl fmt::Display for InadmissibleWeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a heuristic weight of {}% can overestimate where the cheapest cells only allow {}%",
+            self.weight_percent, self.max_percent
+        )
+    }
+}
+
+impl Error for InadmissibleWeight {}
+
+/// Checks that `heuristic` never overestimates on `grid`, so that searches
+/// with it still find the cheapest paths. Worth running whenever layers
+/// such as [`crate::Discounted`] roads can make cells cheaper than the
+/// weight was picked for.
+pub fn check_weight<G: GridSource + ?Sized>(
+    grid: &G,
+    heuristic: &Weighted<Manhattan>,
+    cardinal_directions: bool,
+) -> Result<(), InadmissibleWeight> {
+    let max_percent = admissible_weight_percent(grid, cardinal_directions);
+    let (numerator, denominator) = (
+        heuristic.numerator as u64,
+        heuristic.denominator.max(1) as u64,
+    );
+    if numerator * 100 <= max_percent as u64 * denominator {
+        return Ok(());
+    }
+    Err(InadmissibleWeight {
+        weight_percent: (numerator * 100 / denominator).min(u32::MAX as u64) as u32,
+        max_percent,
+    })
+}
+
+/// A cell from which a heuristic guessed more than reaching the goal
+/// really costs, found by [`find_overestimates`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Overestimate {
+    pub cell: u32,
+    pub goal: u32,
+    pub estimate: u32,
+    /// What the cheapest path from `cell` to `goal` costs.
+    pub distance: u32,
+}
+
+/// Tests `heuristic` against true distances on `grid`: picks `goals`
+/// walkable goals at random from `seed`, measures the cost of reaching
+/// each from every cell, and returns every estimate that came out higher,
+/// goal by goal and in row-major order. An empty result means searches with
+/// the heuristic found the cheapest paths for every query it tried.
+///
+/// Each goal costs a flood of the whole grid, so this is meant for tests
+/// and debug builds rather than for every frame.
+pub fn find_overestimates<G: GridSource + ?Sized, H: Heuristic + ?Sized>(
+    grid: &G,
+    heuristic: &H,
+    cardinal_directions: bool,
+    goals: usize,
+    seed: u64,
+) -> Vec<Overestimate> {
+    let mut candidates: Vec<u32> = (0..grid.len()).filter(|&i| grid.cost(i) != WALL).collect();
+    Rng::new(seed).shuffle(&mut candidates);
+    candidates.truncate(goals);
+    let width = grid.width();
+    let mut overestimates = Vec::new();
+    for &goal in &candidates {
+        let field = FlowField::new(grid, &[goal], cardinal_directions);
+        for cell in 0..grid.len() {
+            let distance = match field.distance(cell) {
+                Some(distance) => distance,
+                None => continue,
+            };
+            let estimate = heuristic.estimate(cell, goal, width);
+            if estimate > distance {
+                overestimates.push(Overestimate {
+                    cell,
+                    goal,
+                    estimate,
+                    distance,
+                });
+            }
+        }
+    }
+    overestimates
+}
+
+/// A tighter estimate from true distances to a few precomputed pivot cells,
+/// falling back on [`Manhattan`] where the pivots don't help.
+///
+/// Costing `pivots * cells` distances to build and store, this is a cheap
+/// way to stop searches on weighted or maze-like maps from flooding dead
+/// ends. It is only valid for the grid and movement mode it was built for,
+/// and only for as long as the grid's costs don't change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PivotHeuristic {
+    pivots: Vec<u32>,
+    /// The distances from each pivot to every cell, one row per pivot, with
+    /// `u32::MAX` marking unreachable cells.
+    distances: Vec<u32>,
+    len: u32,
+}
+
+impl PivotHeuristic {
+    /// Picks up to `pivots` distinct walkable cells at random from `seed`
+    /// and measures the distance from each to every cell.
+    pub fn new<G: GridSource + ?Sized>(
+        grid: &G,
+        pivots: usize,
+        cardinal_directions: bool,
+        seed: u64,
+    ) -> Self {
+        let mut candidates: Vec<u32> = (0..grid.len()).filter(|&i| grid.cost(i) != WALL).collect();
+        Rng::new(seed).shuffle(&mut candidates);
+        candidates.truncate(pivots);
+        Self::from_pivots(grid, &candidates, cardinal_directions)
+    }
+
+    /// Measures distances from the given pivot cells. Pivots outside the grid
+    /// are ignored.
+    pub fn from_pivots<G: GridSource + ?Sized>(
+        grid: &G,
+        pivots: &[u32],
+        cardinal_directions: bool,
+    ) -> Self {
+        let pivots: Vec<u32> = pivots
+            .iter()
+            .copied()
+            .filter(|&pivot| pivot < grid.len())
+            .collect();
+        let mut distances = Vec::with_capacity(pivots.len() * grid.len() as usize);
+        for &pivot in pivots.iter() {
+            distances.extend_from_slice(
+                DijkstraMap::new(grid, &[pivot], cardinal_directions).distances(),
+            );
+        }
+        PivotHeuristic {
+            pivots,
+            distances,
+            len: grid.len(),
+        }
+    }
+
+    pub fn pivots(&self) -> &[u32] {
+        &self.pivots
+    }
+}
+
+impl Heuristic for PivotHeuristic {
+    fn estimate(&self, cell: u32, goal: u32, width: u32) -> u32 {
+        let mut best = Manhattan.estimate(cell, goal, width);
+        if cell >= self.len || goal >= self.len {
+            return best;
+        }
+        // d(pivot, goal) <= d(pivot, cell) + d(cell, goal)
+        for row in self.distances.chunks_exact(self.len as usize) {
+            let to_cell = row[cell as usize];
+            let to_goal = row[goal as usize];
+            if to_cell != u32::MAX && to_goal != u32::MAX {
+                best = best.max(to_goal.saturating_sub(to_cell));
+            }
+        }
+        best
+    }
+}
+
+impl Artifact for PivotHeuristic {
+    const KIND: [u8; 3] = *b"pvt";
+    const VERSION: u8 = 1;
+
+    fn write_payload(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.len.to_le_bytes());
+        write_u32s(bytes, &self.pivots);
+        write_u32s(bytes, &self.distances);
+    }
+
+    fn read_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(payload);
+        let len = reader.u32()?;
+        let pivots = reader.any_u32s()?;
+        let count = pivots
+            .len()
+            .checked_mul(len as usize)
+            .ok_or(DecodeError::Corrupt)?;
+        let distances = reader.u32s(count)?;
+        reader.finish()?;
+        if pivots.iter().any(|&pivot| pivot >= len) {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(PivotHeuristic {
+            pivots,
+            distances,
+            len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_artifact, save_artifact, search, search_with_heuristic, Grid, SearchOptions};
+
+    #[test]
+    fn pivots_keep_searches_optimal() {
+        let mut rng = Rng::new(1);
+        for _ in 0..20 {
+            let grid = rng.grid(16, 12, 25, 8);
+            let cardinal_directions = rng.chance(1, 2);
+            let heuristic = PivotHeuristic::new(&grid, 4, cardinal_directions, rng.next_u64());
+            let options = SearchOptions {
+                cardinal_directions,
+                ..SearchOptions::default()
+            };
+            for _ in 0..20 {
+                let start = rng.below(grid.len());
+                let end = rng.below(grid.len());
+                let plain = search(start, end, &grid, &options);
+                let guided = search_with_heuristic(start, end, &grid, &options, &heuristic);
+                assert_eq!(guided.cost, plain.cost);
+            }
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn pivots_see_around_walls() {
+        let grid = Grid::new(vec![
+            1, 1, 1, 1, 1, 1,
+            1, 0, 0, 0, 0, 1,
+            1, 1, 1, 1, 0, 1,
+            1, 0, 0, 0, 0, 1,
+            1, 1, 1, 1, 1, 1,
+        ], 6).unwrap();
+        let heuristic = PivotHeuristic::from_pivots(&grid, &[12], true);
+        assert_eq!(Manhattan.estimate(15, 17, 6), 2);
+        assert_eq!(heuristic.estimate(15, 17, 6), 12);
+        let options = SearchOptions { cardinal_directions: true, ..SearchOptions::default() };
+        let plain = search(15, 17, &grid, &options);
+        let guided = search_with_heuristic(15, 17, &grid, &options, &heuristic);
+        assert_eq!(guided.cost, plain.cost);
+        assert!(guided.stats.expanded < plain.stats.expanded);
+    }
+
+    #[test]
+    fn pivots_outside_the_grid_are_corrupt() {
+        let grid = Grid::filled(4, 3, 1).unwrap();
+        let mut pivots = PivotHeuristic::from_pivots(&grid, &[0, 11], false);
+        pivots.pivots[1] = 12;
+        let bytes = save_artifact(&pivots, &grid);
+        assert_eq!(
+            load_artifact::<PivotHeuristic, _>(&bytes, &grid),
+            Err(DecodeError::Corrupt)
+        );
+    }
+
+    #[test]
+    fn weights_are_checked_against_the_cheapest_cells() {
+        let grid = Grid::filled(8, 8, 4).unwrap();
+        assert_eq!(admissible_weight_percent(&grid, false), 300);
+        assert_eq!(admissible_weight_percent(&grid, true), 500);
+        let heavy = Weighted::new(Manhattan, 3, 1);
+        assert_eq!(check_weight(&grid, &heavy, false), Ok(()));
+        let road = [3; 64];
+        let roads = crate::Discounted::new(&grid, &road[..], 1);
+        assert_eq!(
+            check_weight(&roads, &heavy, false),
+            Err(InadmissibleWeight {
+                weight_percent: 300,
+                max_percent: 150
+            })
+        );
+        let safe = Weighted::new(Manhattan, 3, 2);
+        assert_eq!(check_weight(&roads, &safe, false), Ok(()));
+        let options = SearchOptions::default();
+        for (start, end) in [(0, 63), (7, 56), (9, 30)] {
+            assert_eq!(
+                search_with_heuristic(start, end, &roads, &options, &safe).cost,
+                search(start, end, &roads, &options).cost
+            );
+        }
+    }
+
+    #[test]
+    fn overestimates_are_found_and_admissible_heuristics_pass() {
+        let mut rng = Rng::new(2);
+        let grid = rng.grid(12, 12, 20, 3);
+        assert_eq!(find_overestimates(&grid, &Manhattan, false, 6, 1), vec![]);
+        let pivots = PivotHeuristic::new(&grid, 3, true, 2);
+        assert_eq!(find_overestimates(&grid, &pivots, true, 6, 1), vec![]);
+        let greedy = Weighted::new(Manhattan, 4, 1);
+        let found = find_overestimates(&grid, &greedy, false, 6, 1);
+        assert!(!found.is_empty());
+        for overestimate in found {
+            assert!(overestimate.estimate > overestimate.distance);
+            assert_eq!(
+                search(
+                    overestimate.cell,
+                    overestimate.goal,
+                    &grid,
+                    &SearchOptions::default()
+                )
+                .cost,
+                Some(overestimate.distance)
+            );
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! An egui widget for inspecting searches interactively, enabled by the
+//! `egui` feature.
+//!
+//! [`SearchInspector`] draws a [`Grid`], picks the start with a left click
+//! and the goal with a right click, and replays the recorded
+//! [`SearchTrace`] one expansion at a time.
+
+use crate::{
+    search_with_heuristic, Grid, GridSource, Heuristic, Manhattan, SearchOptions, SearchResult,
+    SearchTrace,
+};
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Ui, Vec2};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    AStar,
+    /// A* without a heuristic, which expands cells in order of cost alone.
+    Dijkstra,
+}
+
+impl Algorithm {
+    fn label(self) -> &'static str {
+        match self {
+            Algorithm::AStar => "A*",
+            Algorithm::Dijkstra => "Dijkstra",
+        }
+    }
+}
+
+struct Zero;
+
+impl Heuristic for Zero {
+    fn estimate(&self, _cell: u32, _goal: u32, _width: u32) -> u32 {
+        0
+    }
+}
+
+const WALL: Color32 = Color32::from_rgb(30, 30, 30);
+const EXPANDED: Color32 = Color32::from_rgba_premultiplied(40, 90, 160, 140);
+const PATH: Color32 = Color32::from_rgb(250, 200, 40);
+const START: Color32 = Color32::from_rgb(60, 190, 80);
+const GOAL: Color32 = Color32::from_rgb(210, 50, 50);
+
+pub struct SearchInspector {
+    grid: Grid,
+    start: Option<u32>,
+    goal: Option<u32>,
+    algorithm: Algorithm,
+    options: SearchOptions,
+    result: Option<SearchResult>,
+    /// How many expansions of the trace are currently drawn.
+    shown: usize,
+    playing: bool,
+    /// Expansions revealed per frame while playing.
+    speed: usize,
+    /// Side length of a drawn cell, in points.
+    pub cell_size: f32,
+}
+
+impl SearchInspector {
+    pub fn new(grid: Grid) -> Self {
+        SearchInspector {
+            grid,
+            start: None,
+            goal: None,
+            algorithm: Algorithm::AStar,
+            options: SearchOptions::default(),
+            result: None,
+            shown: 0,
+            playing: false,
+            speed: 1,
+            cell_size: 16.0,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Replaces the grid, dropping the current search.
+    pub fn set_grid(&mut self, grid: Grid) {
+        self.grid = grid;
+        self.start = None;
+        self.goal = None;
+        self.result = None;
+    }
+
+    pub fn set_endpoints(&mut self, start: u32, goal: u32) {
+        self.start = Some(start);
+        self.goal = Some(goal);
+        self.rerun();
+    }
+
+    /// The search for the current endpoints, once both are picked.
+    pub fn result(&self) -> Option<&SearchResult> {
+        self.result.as_ref()
+    }
+
+    fn trace(&self) -> Option<&SearchTrace> {
+        self.result
+            .as_ref()
+            .and_then(|result| result.trace.as_ref())
+    }
+
+    fn rerun(&mut self) {
+        let (start, goal) = match (self.start, self.goal) {
+            (Some(start), Some(goal)) => (start, goal),
+            _ => return,
+        };
+        let options = SearchOptions {
+            record_trace: true,
+            ..self.options.clone()
+        };
+        self.result = Some(match self.algorithm {
+            Algorithm::AStar => {
+                search_with_heuristic(start, goal, &self.grid, &options, &Manhattan)
+            }
+            Algorithm::Dijkstra => search_with_heuristic(start, goal, &self.grid, &options, &Zero),
+        });
+        self.shown = 0;
+        self.playing = true;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        self.controls(ui);
+        self.canvas(ui);
+        let total = self.trace().map_or(0, |trace| trace.expansions.len());
+        if self.playing {
+            self.shown = (self.shown + self.speed).min(total);
+            if self.shown == total {
+                self.playing = false;
+            } else {
+                ui.ctx().request_repaint();
+            }
+        }
+    }
+
+    fn controls(&mut self, ui: &mut Ui) {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("simple_astar_algorithm")
+                .selected_text(self.algorithm.label())
+                .show_ui(ui, |ui| {
+                    for algorithm in [Algorithm::AStar, Algorithm::Dijkstra] {
+                        changed |= ui
+                            .selectable_value(&mut self.algorithm, algorithm, algorithm.label())
+                            .changed();
+                    }
+                });
+            changed |= ui
+                .checkbox(&mut self.options.cardinal_directions, "Cardinal only")
+                .changed();
+            if ui
+                .button(if self.playing { "Pause" } else { "Play" })
+                .clicked()
+            {
+                self.playing = !self.playing;
+            }
+            ui.add(egui::Slider::new(&mut self.speed, 1..=64).text("per frame"));
+        });
+        if let Some(total) = self.trace().map(|trace| trace.expansions.len()) {
+            ui.add(egui::Slider::new(&mut self.shown, 0..=total).text("expansions"));
+        }
+        if let Some(result) = &self.result {
+            ui.label(format!(
+                "{}: cost {}, {} expanded, {} reached",
+                result.termination.as_str(),
+                result.cost.map_or("-".to_string(), |cost| cost.to_string()),
+                result.stats.expanded,
+                result.stats.reached
+            ));
+        }
+        if changed {
+            self.rerun();
+        }
+    }
+
+    fn canvas(&mut self, ui: &mut Ui) {
+        let width = self.grid.width();
+        let size = Vec2::new(
+            width as f32 * self.cell_size,
+            self.grid.height() as f32 * self.cell_size,
+        );
+        let (response, painter) = ui.allocate_painter(size, Sense::click());
+        let origin = response.rect.min;
+        let max_cost = self.grid.cells().iter().copied().max().unwrap_or(1).max(1);
+        let cell_rect = |index: u32| {
+            let min = origin
+                + Vec2::new(
+                    (index % width) as f32 * self.cell_size,
+                    (index / width) as f32 * self.cell_size,
+                );
+            Rect::from_min_size(min, Vec2::splat(self.cell_size))
+        };
+        let center = |index: u32| cell_rect(index).center();
+        for index in 0..self.grid.len() {
+            let cost = self.grid.cost(index);
+            let color = if cost == 0 {
+                WALL
+            } else {
+                let shade = 235 - (cost as u64 * 120 / max_cost as u64) as u8;
+                Color32::from_gray(shade)
+            };
+            painter.rect_filled(cell_rect(index).shrink(0.5), 0.0, color);
+        }
+        if let Some(trace) = self.trace() {
+            for expansion in &trace.expansions[..self.shown] {
+                painter.rect_filled(cell_rect(expansion.cell).shrink(0.5), 0.0, EXPANDED);
+            }
+        }
+        if let (Some(result), Some(start)) = (&self.result, self.start) {
+            let total = self.trace().map_or(0, |trace| trace.expansions.len());
+            if self.shown == total {
+                let points: Vec<Pos2> = std::iter::once(start)
+                    .chain(result.path.iter().copied())
+                    .map(center)
+                    .collect();
+                for pair in points.windows(2) {
+                    painter.line_segment([pair[0], pair[1]], Stroke::new(3.0, PATH));
+                }
+            }
+        }
+        for (cell, color) in [(self.start, START), (self.goal, GOAL)] {
+            if let Some(cell) = cell {
+                painter.circle_filled(center(cell), self.cell_size * 0.35, color);
+            }
+        }
+        let clicked = if response.clicked() {
+            Some(true)
+        } else if response.secondary_clicked() {
+            Some(false)
+        } else {
+            None
+        };
+        if let (Some(is_start), Some(position)) = (clicked, response.interact_pointer_pos()) {
+            let offset = (position - origin) / self.cell_size;
+            let (x, y) = (offset.x as u32, offset.y as u32);
+            if x < width && y < self.grid.height() {
+                let cell = y * width + x;
+                if is_start {
+                    self.start = Some(cell);
+                } else {
+                    self.goal = Some(cell);
+                }
+                self.rerun();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_and_replays_a_search() {
+        let mut inspector = SearchInspector::new(Grid::filled(8, 6, 1).unwrap());
+        inspector.set_endpoints(0, 47);
+        let total = inspector.trace().unwrap().expansions.len();
+        let context = egui::Context::default();
+        for _ in 0..total + 1 {
+            let _ = context.run(egui::RawInput::default(), |context| {
+                egui::CentralPanel::default().show(context, |ui| inspector.ui(ui));
+            });
+        }
+        assert_eq!(inspector.shown, total);
+        assert!(!inspector.playing);
+        assert_eq!(inspector.result().unwrap().cost, Some(19));
+    }
+
+    #[test]
+    fn dijkstra_expands_more_than_a_star() {
+        let mut inspector = SearchInspector::new(Grid::filled(8, 6, 1).unwrap());
+        inspector.set_endpoints(0, 47);
+        let a_star = inspector.result().unwrap().clone();
+        inspector.algorithm = Algorithm::Dijkstra;
+        inspector.rerun();
+        let dijkstra = inspector.result().unwrap();
+        assert_eq!(dijkstra.cost, a_star.cost);
+        assert!(dijkstra.stats.expanded > a_star.stats.expanded);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::GridSource;
+
+/// Extra cost added on top of a base grid by [`Layered`].
+pub trait CostLayer {
+    /// Cost added to entering the cell at `index`.
+    fn extra_cost(&self, index: u32) -> u32;
+}
+
+impl<L: CostLayer + ?Sized> CostLayer for &L {
+    #[inline(always)]
+    fn extra_cost(&self, index: u32) -> u32 {
+        (**self).extra_cost(index)
+    }
+}
+
+/// A cost per cell in row-major order, with nothing added past its end.
+impl CostLayer for [u32] {
+    #[inline(always)]
+    fn extra_cost(&self, index: u32) -> u32 {
+        self.get(index as usize).copied().unwrap_or(0)
+    }
+}
+
+/// A base grid with a cost layer added on top. Walls of the base grid stay
+/// walls whatever the layer adds. Layers stack by nesting: a `Layered` is a
+/// [`GridSource`] itself.
+#[derive(Debug, Clone, Copy)]
+pub struct Layered<G, L> {
+    pub base: G,
+    pub layer: L,
+}
+
+impl<G: GridSource, L: CostLayer> Layered<G, L> {
+    pub fn new(base: G, layer: L) -> Self {
+        Layered { base, layer }
+    }
+}
+
+impl<G: GridSource, L: CostLayer> GridSource for Layered<G, L> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.base.width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.base.height()
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        match self.base.cost(index) {
+            0 => 0,
+            cost => cost.saturating_add(self.layer.extra_cost(index)),
+        }
+    }
+}
+
+/// A base grid made cheaper where a layer says, such as along roads that
+/// units should prefer. The layer's costs are taken off instead of added,
+/// no walkable cell is discounted below `floor`, nor below `1`: taking a
+/// cell down to `0` would turn it into a wall.
+///
+/// Cheaper cells let a search find cheaper routes, so a heuristic scaled up
+/// for the base grid may overestimate on the discounted one; see
+/// [`crate::check_weight`].
+#[derive(Debug, Clone, Copy)]
+pub struct Discounted<G, L> {
+    pub base: G,
+    pub discount: L,
+    pub floor: u32,
+}
+
+impl<G: GridSource, L: CostLayer> Discounted<G, L> {
+    pub fn new(base: G, discount: L, floor: u32) -> Self {
+        Discounted {
+            base,
+            discount,
+            floor,
+        }
+    }
+}
+
+impl<G: GridSource, L: CostLayer> GridSource for Discounted<G, L> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.base.width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.base.height()
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        match self.base.cost(index) {
+            0 => 0,
+            cost => {
+                let floor = self.floor.max(1);
+                cost.saturating_sub(self.discount.extra_cost(index))
+                    .max(floor.min(cost))
+            }
+        }
+    }
+}
+
+/// Congestion that builds up where units walk and fades over time, so that
+/// later units spread out over parallel routes instead of piling into the
+/// same corridor.
+///
+/// Every recorded visit adds `heat_per_visit` to a cell, and heat drops by
+/// `decay_per_tick` each [`TrafficLayer::tick`]. Decay is applied lazily
+/// from per-cell timestamps, so ticking is constant time whatever the size
+/// of the map. Heat is added to step costs as is when used as a
+/// [`CostLayer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrafficLayer {
+    heat: Vec<u32>,
+    /// The tick each cell's heat was last brought up to date.
+    stamps: Vec<u32>,
+    now: u32,
+    heat_per_visit: u32,
+    decay_per_tick: u32,
+}
+
+impl TrafficLayer {
+    /// A cold layer over a grid of `len` cells.
+    pub fn new(len: u32, heat_per_visit: u32, decay_per_tick: u32) -> Self {
+        TrafficLayer {
+            heat: vec![0; len as usize],
+            stamps: vec![0; len as usize],
+            now: 0,
+            heat_per_visit,
+            decay_per_tick,
+        }
+    }
+
+    pub fn now(&self) -> u32 {
+        self.now
+    }
+
+    pub fn tick(&mut self) {
+        self.advance(1);
+    }
+
+    pub fn advance(&mut self, ticks: u32) {
+        self.now = self.now.saturating_add(ticks);
+    }
+
+    /// The current heat of `index`, or `0` outside the layer.
+    pub fn heat(&self, index: u32) -> u32 {
+        let index = index as usize;
+        match self.heat.get(index) {
+            Some(&heat) => {
+                let elapsed = self.now - self.stamps[index];
+                heat.saturating_sub(elapsed.saturating_mul(self.decay_per_tick))
+            }
+            None => 0,
+        }
+    }
+
+    /// Heats one cell up. Cells outside the layer are ignored.
+    pub fn record(&mut self, index: u32) {
+        if (index as usize) < self.heat.len() {
+            let heat = self.heat(index).saturating_add(self.heat_per_visit);
+            self.heat[index as usize] = heat;
+            self.stamps[index as usize] = self.now;
+        }
+    }
+
+    /// Heats up every cell of a path a unit is about to walk.
+    pub fn record_path(&mut self, path: &[u32]) {
+        for &cell in path {
+            self.record(cell);
+        }
+    }
+
+    /// Cools every cell down at once.
+    pub fn clear(&mut self) {
+        self.heat.iter_mut().for_each(|heat| *heat = 0);
+    }
+}
+
+impl CostLayer for TrafficLayer {
+    #[inline(always)]
+    fn extra_cost(&self, index: u32) -> u32 {
+        self.heat(index)
+    }
+}
+
+/// Something to keep away from, such as a dragon, whose danger falls off
+/// linearly from `peak` at its cell to nothing beyond `radius` cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Threat {
+    pub x: u32,
+    pub y: u32,
+    pub radius: u32,
+    pub peak: u32,
+}
+
+/// Extra cost around moving threats, stamped afresh each tick with
+/// [`ThreatLayer::restamp`]. Overlapping threats add up.
+///
+/// The layer remembers which cells it stamped, so clearing it only goes
+/// over those: a tick costs as much as the area around the threats,
+/// however big the map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreatLayer {
+    width: u32,
+    height: u32,
+    danger: Vec<u32>,
+    /// Every cell with danger, each once.
+    stamped: Vec<u32>,
+}
+
+impl ThreatLayer {
+    /// A layer with no threats over a `width` by `height` grid.
+    pub fn new(width: u32, height: u32) -> Self {
+        ThreatLayer {
+            width,
+            height,
+            danger: vec![0; width as usize * height as usize],
+            stamped: Vec::new(),
+        }
+    }
+
+    /// The danger at `index`, or `0` outside the layer.
+    pub fn danger(&self, index: u32) -> u32 {
+        self.danger.get(index as usize).copied().unwrap_or(0)
+    }
+
+    /// Adds the danger around `threat`. The parts of it off the grid are
+    /// left out.
+    pub fn stamp(&mut self, threat: Threat) {
+        let radius = threat.radius as i64;
+        let (x, y) = (threat.x as i64, threat.y as i64);
+        for ny in (y - radius).max(0)..=(y + radius).min(self.height as i64 - 1) {
+            for nx in (x - radius).max(0)..=(x + radius).min(self.width as i64 - 1) {
+                let squared = ((nx - x).pow(2) + (ny - y).pow(2)) as f64;
+                // sqrt is correctly rounded, so this is the same everywhere
+                let distance = squared.sqrt() as i64;
+                if distance > radius {
+                    continue;
+                }
+                let danger =
+                    threat.peak as u64 * (radius + 1 - distance) as u64 / (radius + 1) as u64;
+                let index = ny as u32 * self.width + nx as u32;
+                let cell = &mut self.danger[index as usize];
+                if *cell == 0 && danger > 0 {
+                    self.stamped.push(index);
+                }
+                *cell = cell.saturating_add(danger.min(u32::MAX as u64) as u32);
+            }
+        }
+    }
+
+    /// Takes every threat off, touching only the cells they were on.
+    pub fn clear(&mut self) {
+        for index in self.stamped.drain(..) {
+            self.danger[index as usize] = 0;
+        }
+    }
+
+    /// Clears the layer and stamps `threats` where they are now, once per
+    /// tick.
+    pub fn restamp(&mut self, threats: &[Threat]) {
+        self.clear();
+        for &threat in threats {
+            self.stamp(threat);
+        }
+    }
+}
+
+impl CostLayer for ThreatLayer {
+    #[inline(always)]
+    fn extra_cost(&self, index: u32) -> u32 {
+        self.danger(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_grid, Grid};
+
+    #[test]
+    fn heat_decays_lazily() {
+        let mut traffic = TrafficLayer::new(4, 10, 3);
+        traffic.record(1);
+        traffic.record(1);
+        assert_eq!(traffic.heat(1), 20);
+        traffic.advance(4);
+        assert_eq!(traffic.heat(1), 8);
+        traffic.record(1);
+        assert_eq!(traffic.heat(1), 18);
+        traffic.advance(100);
+        assert_eq!(traffic.heat(1), 0);
+        assert_eq!(traffic.heat(9), 0);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn later_units_avoid_busy_corridors() {
+        let grid = Grid::new(vec![
+            1, 1, 1, 1, 1,
+            1, 0, 0, 0, 1,
+            1, 1, 1, 1, 1,
+        ], 5).unwrap();
+        let mut traffic = TrafficLayer::new(grid.len(), 5, 1);
+        let first = astar_grid(5, 9, &Layered::new(&grid, &traffic), true);
+        traffic.record_path(&first);
+        let second = astar_grid(5, 9, &Layered::new(&grid, &traffic), true);
+        assert_ne!(first[0], second[0]);
+        assert!(!second.iter().any(|cell| grid.cells()[*cell as usize] == 0));
+    }
+
+    #[test]
+    fn threats_fall_off_and_move() {
+        let mut threats = ThreatLayer::new(9, 9);
+        let dragon = Threat {
+            x: 4,
+            y: 4,
+            radius: 3,
+            peak: 40,
+        };
+        threats.restamp(&[dragon]);
+        let row: Vec<u32> = (36..45).map(|index| threats.danger(index)).collect();
+        assert_eq!(row, vec![0, 10, 20, 30, 40, 30, 20, 10, 0]);
+        assert_eq!(threats.danger(0), 0);
+        let moved = Threat { x: 0, ..dragon };
+        threats.restamp(&[moved, moved]);
+        assert_eq!(threats.danger(36), 80);
+        assert_eq!(threats.danger(40), 0);
+        let dangerous = threats.danger.iter().filter(|&&danger| danger > 0).count();
+        assert_eq!(threats.stamped.len(), dangerous);
+        threats.clear();
+        assert!(threats.danger.iter().all(|&danger| danger == 0));
+
+        let grid = Grid::filled(9, 9, 1).unwrap();
+        threats.restamp(&[dragon]);
+        let path = astar_grid(36, 44, &Layered::new(&grid, &threats), false);
+        assert_eq!(path.last(), Some(&44));
+        assert!(!path.contains(&40));
+    }
+
+    #[test]
+    fn roads_are_preferred_but_never_free() {
+        let grid = Grid::new(vec![4; 27], 9).unwrap();
+        let mut road = [0; 27];
+        (18..27).for_each(|cell| road[cell] = 10);
+        let roads = Discounted::new(&grid, &road[..], 1);
+        assert_eq!((roads.cost(0), roads.cost(22)), (4, 1));
+        let path = astar_grid(0, 8, &roads, true);
+        assert!(path.contains(&22));
+        let walled = Grid::new(vec![0, 1, 2], 3).unwrap();
+        let floored = Discounted::new(&walled, &[5, 5, 5][..], 3);
+        // walls stay walls, and the floor never raises a cell
+        assert_eq!(
+            (0..3).map(|i| floored.cost(i)).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    get_neighbor_coords, manhattan, search_neighbors, step_cost, GridError, GridSource,
+    NeighborBuffer, Neighbors, WALL,
+};
+use fxhash::FxHashMap;
+use smallvec::SmallVec;
+
+/// A cell on one level of a [`Levels`] stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelCell {
+    pub level: u32,
+    /// Row-major index within the level.
+    pub cell: u32,
+}
+
+impl LevelCell {
+    pub fn new(level: u32, cell: u32) -> Self {
+        LevelCell { level, cell }
+    }
+}
+
+/// A path through a [`Levels`] stack, from the start (exclusive) to the
+/// goal (inclusive), like the paths of every other search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelPath {
+    pub steps: Vec<LevelCell>,
+    pub cost: u32,
+}
+
+impl LevelPath {
+    /// Indices into `steps` of the steps that change level, which are the
+    /// links the path takes between levels.
+    pub fn transitions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.steps
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[0].level != pair[1].level)
+            .map(|(index, _)| index + 1)
+    }
+}
+
+/// Floors of a building or dungeon stacked on top of each other, connected
+/// by links such as stairs, ladders and elevators.
+///
+/// Every level is a grid of the same size. Within a level, movement follows
+/// the usual rules. A link is an extra one-way step between two cells,
+/// usually on different levels, that costs what it was given instead of the
+/// usual step cost. Links into walls are never taken.
+#[derive(Debug, Clone)]
+pub struct Levels<G> {
+    levels: Vec<G>,
+    links: FxHashMap<LevelCell, SmallVec<[(LevelCell, u32); 2]>>,
+    /// Whether every link joins cells at the same position, which keeps the
+    /// position-only heuristic admissible.
+    vertical_links: bool,
+}
+
+impl<G: GridSource> Levels<G> {
+    /// Stacks `levels` bottom to top, failing unless they are all the same
+    /// size and every cell of the stack fits a `u32` index.
+    pub fn new(levels: Vec<G>) -> Result<Self, GridError> {
+        if let Some(first) = levels.first() {
+            let size = (first.width(), first.height());
+            if levels
+                .iter()
+                .any(|level| (level.width(), level.height()) != size)
+            {
+                return Err(GridError::SizeMismatch);
+            }
+            let len = levels.len().saturating_mul(first.len() as usize);
+            if len > u32::MAX as usize {
+                return Err(GridError::TooLarge { len });
+            }
+        }
+        Ok(Levels {
+            levels,
+            links: FxHashMap::default(),
+            vertical_links: true,
+        })
+    }
+
+    pub fn levels(&self) -> &[G] {
+        &self.levels
+    }
+
+    /// Adds a one-way link from `from` to `to`. Links to cells outside the
+    /// stack are never taken.
+    pub fn link(&mut self, from: LevelCell, to: LevelCell, cost: u32) -> &mut Self {
+        self.vertical_links &= from.cell == to.cell;
+        self.links.entry(from).or_default().push((to, cost));
+        self
+    }
+
+    /// Adds links both ways between `a` and `b`.
+    pub fn connect(&mut self, a: LevelCell, b: LevelCell, cost: u32) -> &mut Self {
+        self.link(a, b, cost).link(b, a, cost)
+    }
+
+    fn contains(&self, at: LevelCell) -> bool {
+        self.levels
+            .get(at.level as usize)
+            .is_some_and(|level| at.cell < level.len())
+    }
+
+    /// The cheapest path from `start` to `end`, or `None` when there is none
+    /// or either lies outside the stack.
+    pub fn find_path(
+        &self,
+        start: LevelCell,
+        end: LevelCell,
+        cardinal_directions: bool,
+    ) -> Option<LevelPath> {
+        if !self.contains(start) || !self.contains(end) {
+            return None;
+        }
+        let len = self.levels[0].len();
+        let id = |at: LevelCell| at.level * len + at.cell;
+        let path = search_neighbors(
+            id(start),
+            id(end),
+            &Stack {
+                levels: self,
+                cardinal_directions,
+            },
+        )?;
+        Some(LevelPath {
+            steps: path
+                .nodes
+                .into_iter()
+                .map(|id| LevelCell::new(id / len, id % len))
+                .collect(),
+            cost: path.cost,
+        })
+    }
+}
+
+/// Every level as one graph, numbering the cells of each level after the
+/// ones below.
+struct Stack<'a, G> {
+    levels: &'a Levels<G>,
+    cardinal_directions: bool,
+}
+
+/// Room for walking to all eight neighbors and taking a couple of links.
+const STEPS: usize = 10;
+
+impl<'a, G: GridSource> Stack<'a, G> {
+    fn cells(&self) -> u32 {
+        self.levels.levels[0].len()
+    }
+}
+
+impl<'a, G: GridSource> Neighbors<STEPS> for Stack<'a, G> {
+    fn len(&self) -> u32 {
+        self.levels.levels.len() as u32 * self.cells()
+    }
+
+    fn neighbors(&self, node: u32, out: &mut NeighborBuffer<STEPS>) {
+        let len = self.cells();
+        let current = LevelCell::new(node / len, node % len);
+        let level = &self.levels.levels[current.level as usize];
+        for cell in get_neighbor_coords(current.cell, level, self.cardinal_directions) {
+            out.push(
+                node - current.cell + cell,
+                step_cost(level, current.cell, cell),
+            );
+        }
+        for &(to, step) in self.levels.links.get(&current).into_iter().flatten() {
+            if self.levels.contains(to)
+                && self.levels.levels[to.level as usize].cost(to.cell) != WALL
+            {
+                out.push(to.level * len + to.cell, step);
+            }
+        }
+    }
+
+    fn estimate(&self, node: u32, goal: u32) -> u32 {
+        if !self.levels.vertical_links {
+            return 0;
+        }
+        let (len, width) = (self.cells(), self.levels.levels[0].width());
+        let (cell, goal) = (node % len, goal % len);
+        manhattan(
+            (cell % width) as i32,
+            (cell / width) as i32,
+            (goal % width) as i32,
+            (goal / width) as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid, SearchOptions};
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_takes_the_stairs() {
+        let ground = crate::grid![
+            1, 1, 0, 1;
+            1, 1, 0, 1;
+        ];
+        let upstairs = crate::grid![
+            1, 1, 1, 1;
+            0, 0, 0, 0;
+        ];
+        let mut building = Levels::new(vec![ground, upstairs]).unwrap();
+        building
+            .connect(LevelCell::new(0, 1), LevelCell::new(1, 1), 5)
+            .connect(LevelCell::new(0, 3), LevelCell::new(1, 3), 5);
+        let path = building
+            .find_path(LevelCell::new(0, 4), LevelCell::new(0, 7), true)
+            .unwrap();
+        let cells: Vec<(u32, u32)> = path.steps.iter().map(|at| (at.level, at.cell)).collect();
+        assert_eq!(cells, vec![(0, 5), (0, 1), (1, 1), (1, 2), (1, 3), (0, 3), (0, 7)]);
+        assert_eq!(path.cost, 2 + 2 + 5 + 2 + 2 + 5 + 2);
+        assert_eq!(path.transitions().collect::<Vec<_>>(), vec![2, 5]);
+        let alone = Levels::new(vec![building.levels()[0].clone()]).unwrap();
+        assert_eq!(alone.find_path(LevelCell::new(0, 4), LevelCell::new(0, 7), true), None);
+        let flat = alone.find_path(LevelCell::new(0, 4), LevelCell::new(0, 1), false).unwrap();
+        let single = search(4, 1, &building.levels()[0], &SearchOptions::default());
+        assert_eq!(Some(flat.cost), single.cost);
+        let uneven = Levels::new(vec![Grid::filled(2, 2, 1).unwrap(), Grid::filled(3, 1, 1).unwrap()]);
+        assert_eq!(uneven.err(), Some(GridError::SizeMismatch));
+    }
+
+    #[test]
+    fn links_out_of_the_stack_are_never_taken() {
+        let mut stack = Levels::new(vec![Grid::filled(3, 1, 1).unwrap()]).unwrap();
+        stack
+            .link(LevelCell::new(0, 0), LevelCell::new(4, 0), 1)
+            .link(LevelCell::new(0, 0), LevelCell::new(0, 7), 1);
+        let path = stack
+            .find_path(LevelCell::new(0, 0), LevelCell::new(0, 2), true)
+            .unwrap();
+        assert_eq!(path.steps, vec![LevelCell::new(0, 1), LevelCell::new(0, 2)]);
+        let floor = Grid::filled(256, 256, 1).unwrap();
+        let tower = Levels::new(vec![&floor; 65537]);
+        assert_eq!(
+            tower.err(),
+            Some(GridError::TooLarge { len: 65537 * 65536 })
+        );
+    }
+}
@@ -1,11 +1,144 @@
-use fxhash::FxHashMap;
 use smallvec::{smallvec, SmallVec};
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+
+mod all_pairs;
+mod analysis;
+mod anytime;
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls;
+mod artifact;
+mod assign;
+mod attack;
+#[cfg(feature = "bake")]
+pub mod bake;
+mod bidirectional;
+mod canonical;
+mod capabilities;
+mod clearance;
+mod const_grid;
+mod corridor;
+mod cpd;
+#[cfg(feature = "csv")]
+mod csv;
+mod deadline;
+mod dijkstra;
+mod edges;
+mod elevation;
+mod explore;
+mod flow;
+mod formation;
+mod fov;
+mod from_any;
+#[cfg(feature = "generate")]
+pub mod generate;
+mod goal_bounding;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod grid;
+mod hash;
+#[cfg(feature = "png")]
+mod heatmap;
+mod heuristic;
+#[cfg(feature = "egui")]
+pub mod inspector;
+mod layers;
+mod levels;
+mod nav_map;
+#[cfg(feature = "ndarray")]
+mod ndarray_interop;
+mod neighbor_cache;
+mod neighbors;
+mod next_step;
+mod occupancy;
+mod padded;
+mod patch;
+mod path;
+mod pool;
+mod progress;
+mod reachable;
+mod regions;
+mod rng;
+#[cfg(feature = "scenario")]
+pub mod scenario;
+mod search;
+mod shared;
+mod simd;
+mod solver;
+mod spawn;
+mod stealth;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod tour;
+mod unknown;
+mod until;
+mod wire;
+
+pub use all_pairs::AllPairs;
+pub use analysis::{analyze, analyze_grid, choke_points, ChokePoints, MapAnalysis, OpenArea};
+pub use anytime::{improve_in_background, AnytimeHandle, AnytimeSearch, Improvement};
+pub use artifact::{load_artifact, save_artifact, Artifact};
+pub use assign::{assign_jobs, Assignment, Job};
+pub use attack::{attack_positions, AttackRange, RangeMetric};
+pub use bidirectional::{bidirectional_search, Halves};
+pub use capabilities::{Capabilities, Movement, Terrain};
+pub use clearance::ClearanceMap;
+pub use const_grid::{ConstGrid, ConstPath};
+pub use corridor::Corridor;
+pub use cpd::CompressedPathDatabase;
+#[cfg(feature = "csv")]
+pub use csv::{grid_from_csv, grid_to_csv, CsvError};
+pub use deadline::astar_with_deadline;
+pub use dijkstra::DijkstraMap;
+pub use edges::{search_with_edge_costs, EdgeCosts, EdgeTable};
+pub use elevation::Elevation;
+pub use explore::nearest_unexplored;
+pub use flow::FlowField;
+pub use formation::Formation;
+pub use fov::{field_of_view, line_of_sight, Observer};
+pub use from_any::astar_from_any;
+pub use goal_bounding::GoalBounds;
+#[cfg(feature = "gpu")]
+pub use gpu::{GpuError, GpuFlowFields};
+pub use grid::{Cost, CostsBy, Grid, GridError, GridSource, GridView, UNKNOWN, WALL};
+pub use hash::{map_hash, path_hash};
+pub use heuristic::{
+    admissible_weight_percent, check_weight, find_overestimates, Heuristic, InadmissibleWeight,
+    Manhattan, Overestimate, PivotHeuristic, Weighted,
+};
+pub use layers::{CostLayer, Discounted, Layered, Threat, ThreatLayer, TrafficLayer};
+pub use levels::{LevelCell, LevelPath, Levels};
+pub use nav_map::NavMap;
+pub use neighbor_cache::NeighborCache;
+pub use neighbors::{search_neighbors, NeighborBuffer, Neighbors, NodePath};
+pub use next_step::{next_step, StepTable};
+pub use occupancy::{Occupancy, Occupied, OccupiedCells};
+pub use padded::PaddedGrid;
+pub use patch::{would_block_path, GridPatch, Patched};
+pub use path::Path;
+pub use pool::{Pathfinder, PathfinderPool};
+pub use progress::{search_with_progress, FrontierEntry, Progress, ProgressOptions};
+pub use reachable::reachable_within;
+pub use regions::Regions;
+pub use rng::Rng;
+pub use search::{
+    search, search_with_heuristic, Backend, Expansion, InternalError, PathBack, PhaseTimings,
+    SearchOptions, SearchResult, SearchStats, SearchTrace, TerminationReason, Weighting,
+};
+pub use shared::{GridReader, SharedGrid, Snapshot};
+pub use solver::{MapStats, Solver, Strategy};
+pub use spawn::{random_reachable_cell, SpawnConstraints};
+pub use stealth::{stealth_path, ExposureMap, StealthPath, WeightedExposure};
+pub use tour::{patrol_loop, visit_all, Tour, TourError, MAX_WAYPOINTS};
+pub use unknown::{Believed, UnknownCells};
+pub use until::{search_until, SearchState};
+pub use wire::{decode_path, encode_path, load_grid, save_grid, DecodeError, FirstMoveTable};
+
 // it might be good to implement some different versions of this:
 // maybe one that does no diagonal, one that doesn't cut corners..
-// perhaps also one that caches neighbors and neighbor costs
 
+/// Ordered so that a max-heap pops the lowest cost first and, among equal
+/// costs, the highest position. This ordering is part of the determinism
+/// guarantee documented on [`astar`] and must not change.
 #[derive(Copy, Clone, Eq, PartialEq)]
 struct FrontierItem {
     pub position: u32,
@@ -27,132 +160,197 @@ impl PartialOrd for FrontierItem {
     }
 }
 
+/// Adapts the bare slice taken by [`astar`] to [`GridSource`].
+struct SliceGrid<'a> {
+    cells: &'a [u32],
+    width: u32,
+    height: u32,
+}
+
+impl<'a> SliceGrid<'a> {
+    /// Ignores a trailing partial row, and any rows past what a `u32` index can address.
+    fn new(cells: &'a [u32], width: u32) -> Self {
+        let rows = cells.len() / width as usize;
+        SliceGrid {
+            cells,
+            width,
+            height: rows.min((u32::MAX / width) as usize) as u32,
+        }
+    }
+}
+
+impl<'a> GridSource for SliceGrid<'a> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        self.cells[index as usize]
+    }
+}
+
+/// Lanes of [`get_neighbor_coords`] that leave the grid across each border,
+/// and the lanes that move diagonally. Lanes go up, up-left, up-right, left,
+/// right, down, down-left, down-right.
+const TOP_LANES: u8 = 0b0000_0111;
+const LEFT_LANES: u8 = 0b0100_1010;
+const RIGHT_LANES: u8 = 0b1001_0100;
+const BOTTOM_LANES: u8 = 0b1110_0000;
+const DIAGONAL_LANES: u8 = 0b1100_0110;
+
+/// The walkable neighbors of `current`. Rather than branch on each border
+/// and wall, which random maps make unpredictable, this masks off the lanes
+/// that leave the grid and tests all the rest for walls at once.
 #[inline(always)]
-fn get_neighbor_coords(
+fn get_neighbor_coords<G: GridSource + ?Sized>(
     current: u32,
-    grid: &Vec<u32>,
-    width: u32,
+    grid: &G,
     cardinal_directions: bool,
 ) -> SmallVec<[u32; 8]> {
-    let is_top = current < width;
-    let is_bottom = current >= grid.len() as u32 - width;
-    let x = current % width;
-    let is_left = x == 0;
-    let is_right = x == width - 1;
-    let mut neighbors: SmallVec<[u32; 8]> = smallvec![];
-    if !is_top {
-        let top_index = current - width;
-        if grid[top_index as usize] > 0 {
-            neighbors.push(top_index)
-        }
-        if !cardinal_directions {
-            if !is_left && grid[top_index as usize - 1] > 0 {
-                neighbors.push(top_index - 1)
-            }
-            if !is_right && grid[top_index as usize + 1] > 0 {
-                neighbors.push(top_index + 1)
-            }
-        }
+    let width = grid.width();
+    let mut inside = !(DIAGONAL_LANES * cardinal_directions as u8);
+    // known when this is inlined, and with a border of walls no lane leaves
+    if !grid.has_wall_border() {
+        let x = current % width;
+        inside &= !(TOP_LANES * (current < width) as u8);
+        inside &= !(BOTTOM_LANES * (current >= grid.len() - width) as u8);
+        inside &= !(LEFT_LANES * (x == 0) as u8);
+        inside &= !(RIGHT_LANES * (x == width - 1) as u8);
     }
-    if !is_left && grid[current as usize - 1] > 0 {
-        neighbors.push(current - 1)
+    let above = current.wrapping_sub(width);
+    let below = current.wrapping_add(width);
+    let cells = [
+        above,
+        above.wrapping_sub(1),
+        above.wrapping_add(1),
+        current.wrapping_sub(1),
+        current.wrapping_add(1),
+        below,
+        below.wrapping_sub(1),
+        below.wrapping_add(1),
+    ];
+    let mut costs = [0u32; 8];
+    for (lane, cost) in costs.iter_mut().enumerate() {
+        let keep = (inside >> lane & 1) as u32;
+        // lanes off the grid read the current cell instead, and drop it
+        let cell = if keep != 0 { cells[lane] } else { current };
+        *cost = grid.cost(cell) & keep.wrapping_neg();
     }
-    if !is_right && grid[current as usize + 1] > 0 {
-        neighbors.push(current + 1)
-    }
-    if !is_bottom {
-        let bottom_index = current + width;
-        if grid[bottom_index as usize] > 0 {
-            neighbors.push(bottom_index)
-        }
-        if !cardinal_directions {
-            if !is_left && grid[bottom_index as usize - 1] > 0 {
-                neighbors.push(bottom_index - 1)
-            }
-            if !is_right && grid[bottom_index as usize + 1] > 0 {
-                neighbors.push(bottom_index + 1)
-            }
-        }
+    let mut walkable = simd::walkable(&costs);
+    let mut neighbors: SmallVec<[u32; 8]> = smallvec![];
+    while walkable != 0 {
+        neighbors.push(cells[walkable.trailing_zeros() as usize]);
+        walkable &= walkable - 1;
     }
     neighbors
 }
 
+/// Offsets of the eight neighbors, clockwise from up. A direction is an index
+/// into this table.
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// Index into [`DIRECTIONS`] of the step from `from` to its neighbor `to`.
+#[inline(always)]
+fn direction(from: u32, to: u32, width: u32) -> u8 {
+    let dx = (to % width) as i32 - (from % width) as i32;
+    let dy = (to / width) as i32 - (from / width) as i32;
+    DIRECTIONS
+        .iter()
+        .position(|&offset| offset == (dx, dy))
+        .unwrap_or(7) as u8
+}
+
+/// The neighbor of `from` in `direction`, which must lie inside the grid.
+#[inline(always)]
+fn neighbor_in(from: u32, direction: u8, width: u32) -> u32 {
+    let (dx, dy) = DIRECTIONS[direction as usize];
+    let x = (from % width) as i32 + dx;
+    let y = (from / width) as i32 + dy;
+    y as u32 * width + x as u32
+}
+
 #[inline(always)]
 fn manhattan(x1: i32, y1: i32, x2: i32, y2: i32) -> u32 {
     ((x1 - x2).abs() + (y1 - y2).abs()) as u32
 }
 
+/// Cost of moving from `from` to its neighbor `to`: the cost of the entered
+/// cell plus one per axis moved along, so diagonal steps cost one more.
+#[inline(always)]
+fn step_cost<G: GridSource + ?Sized>(grid: &G, from: u32, to: u32) -> u32 {
+    let width = grid.width();
+    grid.cost(to).saturating_add(manhattan(
+        (from % width) as i32,
+        (from / width) as i32,
+        (to % width) as i32,
+        (to / width) as i32,
+    ))
+}
+
+/// Finds the cheapest path from `start` to `end` on a row-major grid of
+/// movement costs `width` cells wide, where `0` marks a wall.
+///
+/// The returned path excludes `start` and ends at `end`. It is empty when
+/// `end` can't be reached or is `start`, and also when either lies outside
+/// the grid or `width` is `0`. Each step costs the entered cell's
+/// cost plus one, or plus two for diagonal steps.
+///
+/// # Determinism
+///
+/// Results are bit-identical across platforms and compiler versions: the
+/// search only uses integer arithmetic and every tie is broken by a fixed
+/// rule. Among frontier cells with equal estimated cost the one with the
+/// highest index is expanded first, and a cell keeps the first parent found
+/// for it unless a strictly cheaper one comes along later. Neighbors are
+/// visited top, top left, top right, left, right, bottom, bottom left,
+/// bottom right. Any change to these rules is a breaking change.
 pub fn astar(
     start: u32,
     end: u32,
-    grid: &Vec<u32>,
+    grid: &[u32],
     width: u32,
     cardinal_directions: bool,
 ) -> Vec<u32> {
-    let mut frontier = BinaryHeap::with_capacity(grid.len());
-    let mut cost_so_far = FxHashMap::default();
-    let mut came_from = FxHashMap::default();
-    cost_so_far.insert(start, 1);
-    frontier.push(FrontierItem {
-        cost: 0,
-        position: start,
-    });
-    while !frontier.is_empty() {
-        let current_position = frontier.pop().unwrap().position;
-        if current_position == end {
-            break;
-        }
-        let neighbor_coords =
-            get_neighbor_coords(current_position, grid, width, cardinal_directions);
-        for idx in 0..neighbor_coords.len() {
-            let neighbor = neighbor_coords[idx];
-            let neighbor_cost = grid[neighbor as usize];
-            let current_x = current_position % width;
-            let current_y = current_position / width;
-            let neighbor_x = neighbor % width;
-            let neighbor_y = neighbor / width;
-            let cost = cost_so_far.get(&current_position).unwrap()
-                + neighbor_cost
-                + manhattan(
-                    current_x as i32,
-                    current_y as i32,
-                    neighbor_x as i32,
-                    neighbor_y as i32,
-                );
-            let neighbor_cost_so_far = match cost_so_far.get(&neighbor) {
-                Some(amount) => *amount,
-                _ => 0,
-            };
-            if neighbor_cost_so_far == 0 || cost < neighbor_cost_so_far {
-                cost_so_far.insert(neighbor, cost);
-                let end_x = end % width;
-                let end_y = end / width;
-                let priority = cost
-                    + manhattan(
-                        end_x as i32,
-                        end_y as i32,
-                        neighbor_x as i32,
-                        neighbor_y as i32,
-                    );
-                frontier.push(FrontierItem {
-                    cost: priority,
-                    position: neighbor,
-                });
-                came_from.insert(neighbor, current_position);
-            }
-        }
-    }
-    let mut last = end;
-    let mut path: Vec<u32> = Vec::new();
-    while came_from.contains_key(&last) {
-        path.push(last);
-        if last == start {
-            break;
-        }
-        last = *came_from.get(&last).unwrap();
+    if width == 0 {
+        return Vec::new();
     }
-    path.reverse();
-    path
+    astar_grid(
+        start,
+        end,
+        &SliceGrid::new(grid, width),
+        cardinal_directions,
+    )
+}
+
+/// Same as [`astar`], but reads costs from any [`GridSource`], such as a
+/// [`Grid`] or a [`GridView`] borrowed from one.
+pub fn astar_grid<G: GridSource + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    cardinal_directions: bool,
+) -> Vec<u32> {
+    let options = SearchOptions {
+        cardinal_directions,
+        ..SearchOptions::default()
+    };
+    search::run(start, end, grid, &options, &Manhattan, &mut ()).path
 }
 
 #[cfg(test)]
@@ -170,21 +368,32 @@ mod tests {
     }
 
     #[test]
+    #[rustfmt::skip]
     fn it_runs_in_a_straigh_line() {
-        let grid = vec![
-            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        let grid = grid![
+            1, 1, 1, 1, 1;
+            1, 1, 1, 1, 1;
+            1, 1, 1, 1, 1;
+            1, 1, 1, 1, 1;
+            1, 1, 1, 1, 1;
         ];
-        let path = astar(0, 24, &grid, 5, false);
+        let path = astar(0, 24, grid.cells(), 5, false);
         assert_eq!(path, vec![6, 12, 18, 24]);
     }
 
     #[test]
+    #[rustfmt::skip]
     fn it_avoids_walls() {
-        let grid = vec![
-            1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 0, 1, 1, 1, 0, 0, 1, 0, 1, 1, 1, 0, 1, 1, 0, 1, 1,
-            1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        let grid = grid![
+            1, 1, 1, 1, 1, 1, 1;
+            1, 1, 0, 1, 1, 0, 1;
+            1, 1, 0, 0, 1, 0, 1;
+            1, 1, 0, 1, 1, 0, 1;
+            1, 1, 0, 0, 0, 0, 1;
+            1, 1, 1, 1, 1, 1, 1;
+            1, 1, 1, 1, 1, 1, 1;
         ];
-        let path = astar(0, 48, &grid, 7, false);
+        let path = astar(0, 48, grid.cells(), 7, false);
         assert_eq!(path, vec![8, 15, 22, 29, 37, 45, 46, 47, 48]);
     }
 
@@ -228,4 +437,75 @@ mod tests {
             xy_to_idx(3, 3, width),
         ]);
     }
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_searches_inside_a_view() {
+        let grid = Grid::new(vec![
+            0, 0, 0, 0, 0, 0,
+            0, 1, 0, 1, 1, 0,
+            0, 1, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0,
+        ], 6).unwrap();
+        let view = grid.view(1, 1, 4, 2).unwrap();
+        let path = astar_grid(0, 3, &view, true);
+        assert_eq!(path, vec![4, 5, 6, 7, 3]);
+        assert_eq!(
+            path.iter().map(|&i| view.to_parent(i)).collect::<Vec<_>>(),
+            vec![13, 14, 15, 16, 10]
+        );
+    }
+
+    #[test]
+    fn it_borrows_slices_and_boxes() {
+        let boxed: Box<[u32]> = vec![1; 9].into_boxed_slice();
+        assert_eq!(astar(0, 8, &boxed, 3, false), vec![4, 8]);
+        let view = GridView::new(&boxed[3..], 3).unwrap();
+        assert_eq!(astar_grid(0, 5, &view, true), vec![3, 4, 5]);
+        let pointer = boxed.as_ptr();
+        let grid = Grid::new(boxed, 3).unwrap();
+        assert_eq!(grid.cells().as_ptr(), pointer);
+    }
+
+    #[test]
+    fn it_returns_no_path_for_out_of_range_queries() {
+        let grid = vec![1; 6];
+        assert!(astar(0, 6, &grid, 3, false).is_empty());
+        assert!(astar(7, 0, &grid, 3, false).is_empty());
+        assert!(astar(0, 5, &grid, 0, false).is_empty());
+        assert!(astar(0, 5, &grid, 4, false).is_empty());
+        assert!(astar(0, 0, &[], 1, false).is_empty());
+    }
+
+    #[test]
+    fn it_saturates_huge_costs() {
+        let grid = vec![1, u32::MAX, u32::MAX, 1];
+        assert_eq!(astar(0, 3, &grid, 4, false), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn it_finds_the_neighbors_inside_the_grid() {
+        let mut rng = Rng::new(1);
+        for &(width, height) in &[(1, 1), (1, 4), (4, 1), (2, 2), (5, 3), (7, 7)] {
+            let cells: Vec<u32> = (0..width * height).map(|_| rng.range(0, 3)).collect();
+            let grid = Grid::new(cells, width).unwrap();
+            for cell in 0..grid.len() {
+                for &cardinal in &[false, true] {
+                    let (x, y) = ((cell % width) as i32, (cell / width) as i32);
+                    let mut expected: Vec<u32> = DIRECTIONS
+                        .iter()
+                        .filter(|&&(dx, dy)| !cardinal || dx == 0 || dy == 0)
+                        .map(|&(dx, dy)| (x + dx, y + dy))
+                        .filter(|&(x, y)| x >= 0 && y >= 0 && x < width as i32 && y < height as i32)
+                        .map(|(x, y)| y as u32 * width + x as u32)
+                        .filter(|&neighbor| grid.cost(neighbor) > 0)
+                        .collect();
+                    let mut neighbors = get_neighbor_coords(cell, &grid, cardinal).to_vec();
+                    expected.sort_unstable();
+                    neighbors.sort_unstable();
+                    assert_eq!(neighbors, expected);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,126 @@
+use crate::{
+    Grid, GridSource, NeighborCache, Path, Regions, SearchOptions, SearchResult, TerminationReason,
+    WALL,
+};
+
+/// A map together with everything worked out from it, kept up to date as
+/// cells change, for games that just want paths. Each method routes to the
+/// free functions and caches that suit it, which all stay available for
+/// finer control:
+///
+/// - [`NavMap::find_path`] and [`NavMap::search`] search through a
+///   [`NeighborCache`];
+/// - [`NavMap::is_reachable`] looks up [`Regions`], which also lets
+///   `find_path` give up at once on goals walled off from the start.
+#[derive(Debug, Clone)]
+pub struct NavMap {
+    grid: Grid<u32>,
+    options: SearchOptions,
+    regions: Regions,
+    neighbors: NeighborCache,
+}
+
+impl NavMap {
+    /// Takes over `grid`, searching it with `options` from now on.
+    pub fn new(grid: Grid<u32>, options: SearchOptions) -> Self {
+        let cardinal_directions = options.cardinal_directions;
+        NavMap {
+            regions: Regions::new(&grid, cardinal_directions),
+            neighbors: NeighborCache::new(&grid, cardinal_directions),
+            grid,
+            options,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid<u32> {
+        &self.grid
+    }
+
+    pub fn options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    /// Changes how later searches run, working everything out again when
+    /// the movement mode changes.
+    pub fn set_options(&mut self, options: SearchOptions) {
+        if options.cardinal_directions != self.options.cardinal_directions {
+            self.regions = Regions::new(&self.grid, options.cardinal_directions);
+            self.neighbors = NeighborCache::new(&self.grid, options.cardinal_directions);
+        }
+        self.options = options;
+    }
+
+    /// The cheapest path from `start` to `end`, or `None` when there is
+    /// none or the search gave up first. The path always runs from the
+    /// start, whatever [`SearchOptions::reverse_path`] says.
+    pub fn find_path(&self, start: u32, end: u32) -> Option<Path> {
+        let walkable = |cell: u32| cell < self.grid.len() && self.grid.cost(cell) != WALL;
+        if walkable(start) && walkable(end) && !self.is_reachable(start, end) {
+            return None;
+        }
+        let options = SearchOptions {
+            partial_path: false,
+            reverse_path: false,
+            ..self.options.clone()
+        };
+        let result = self.neighbors.search(start, end, &self.grid, &options);
+        if result.termination != TerminationReason::GoalReached {
+            return None;
+        }
+        Some(Path::new(&self.grid, start, result.path))
+    }
+
+    /// Like [`crate::search`] on the grid, with everything it reports.
+    pub fn search(&self, start: u32, end: u32) -> SearchResult {
+        self.neighbors.search(start, end, &self.grid, &self.options)
+    }
+
+    /// Whether a path leads from `from` to `to`, both walkable.
+    pub fn is_reachable(&self, from: u32, to: u32) -> bool {
+        self.regions.connected(from, to)
+    }
+
+    /// Sets the cost of the cell at `(x, y)`, returning the previous one,
+    /// or `None` when the coordinates are outside the grid.
+    pub fn update_cell(&mut self, x: u32, y: u32, cost: u32) -> Option<u32> {
+        let previous = self.grid.set(x, y, cost)?;
+        let cell = self.grid.index(x, y);
+        self.neighbors.invalidate(&self.grid, cell);
+        self.regions.update(&self.grid, cell);
+        Some(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search;
+
+    #[test]
+    fn it_follows_cell_updates() {
+        let grid = Grid::filled(5, 3, 1).unwrap();
+        let options = SearchOptions {
+            cardinal_directions: true,
+            ..SearchOptions::default()
+        };
+        let mut map = NavMap::new(grid, options.clone());
+        assert_eq!(map.find_path(0, 4).unwrap().cost(), 8);
+        for y in 0..3 {
+            assert_eq!(map.update_cell(2, y, 0), Some(1));
+        }
+        assert!(!map.is_reachable(0, 4));
+        assert_eq!(map.find_path(0, 4), None);
+        assert_eq!(map.update_cell(2, 1, 5), Some(0));
+        assert!(map.is_reachable(0, 4));
+        let path = map.find_path(0, 4).unwrap();
+        assert!(path.cells.contains(&7));
+        assert_eq!(map.search(0, 4), search(0, 4, map.grid(), &options));
+        assert_eq!(map.update_cell(9, 9, 1), None);
+        map.set_options(SearchOptions::default());
+        assert_eq!(map.search(0, 4), search(0, 4, map.grid(), map.options()));
+    }
+}
@@ -0,0 +1,113 @@
+//! Conversions between [`Grid`] and `ndarray::Array2`, enabled by the
+//! `ndarray` feature.
+//!
+//! Arrays are indexed `[[y, x]]`, so an array of shape `(height, width)`
+//! corresponds to a grid `width` cells wide.
+
+use crate::{Grid, GridError, GridSource};
+use ndarray::{Array2, ArrayBase, ArrayView2, Data, Ix2};
+use std::convert::TryFrom;
+
+impl<T: Clone> TryFrom<Array2<T>> for Grid<T> {
+    type Error = GridError;
+
+    /// Takes over the array's buffer when it is in standard (row-major)
+    /// layout. Arrays in any other layout are copied into row-major order.
+    fn try_from(array: Array2<T>) -> Result<Self, GridError> {
+        let (_, width) = array.dim();
+        if width > u32::MAX as usize {
+            return Err(GridError::TooLarge { len: array.len() });
+        }
+        let cells = if array.is_standard_layout() {
+            let len = array.len();
+            let (mut cells, offset) = array.into_raw_vec_and_offset();
+            let offset = offset.unwrap_or(0);
+            if offset != 0 || cells.len() != len {
+                cells.truncate(offset + len);
+                cells.drain(..offset);
+            }
+            cells
+        } else {
+            array.iter().cloned().collect()
+        };
+        Grid::new(cells, width as u32)
+    }
+}
+
+impl<T> From<Grid<T>> for Array2<T> {
+    fn from(grid: Grid<T>) -> Self {
+        let shape = (grid.height() as usize, grid.width() as usize);
+        Array2::from_shape_vec(shape, grid.into_cells())
+            .expect("grid dimensions always match its cell count")
+    }
+}
+
+impl<T> Grid<T> {
+    /// Borrows the grid as a `(height, width)` array view.
+    pub fn as_array_view(&self) -> ArrayView2<'_, T> {
+        let shape = (self.height() as usize, self.width() as usize);
+        ArrayView2::from_shape(shape, self.cells())
+            .expect("grid dimensions always match its cell count")
+    }
+}
+
+/// Lets any two dimensional array or array view be searched directly, in
+/// whatever memory layout it has.
+impl<S: Data<Elem = u32>> GridSource for ArrayBase<S, Ix2> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.ncols() as u32
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.nrows() as u32
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        let width = self.ncols();
+        let index = index as usize;
+        self[[index / width, index % width]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar_grid;
+    use ndarray::{array, s, ShapeBuilder};
+
+    #[test]
+    fn it_takes_over_standard_layout_buffers() {
+        let array = array![[1, 2, 3], [4, 5, 6]];
+        let pointer = array.as_ptr();
+        let grid = Grid::try_from(array).unwrap();
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.cells().as_ptr(), pointer);
+        assert_eq!(grid.get(2, 1), Some(6));
+    }
+
+    #[test]
+    fn it_reorders_column_major_arrays() {
+        let array = Array2::from_shape_vec((2, 3).f(), vec![1, 4, 2, 5, 3, 6]).unwrap();
+        let grid = Grid::try_from(array).unwrap();
+        assert_eq!(grid.cells(), &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn it_round_trips_through_arrays() {
+        let grid = Grid::new(vec![1, 0, 1, 1, 1, 1], 2).unwrap();
+        assert_eq!(grid.as_array_view()[[2, 0]], 1);
+        let array = Array2::from(grid.clone());
+        assert_eq!(array.dim(), (3, 2));
+        assert_eq!(Grid::try_from(array).unwrap(), grid);
+    }
+
+    #[test]
+    fn it_searches_array_slices() {
+        let array = array![[1, 1, 1, 1], [1, 0, 0, 1], [1, 1, 1, 1]];
+        let window = array.slice(s![.., 1..]);
+        assert_eq!(astar_grid(0, 8, &window, true), vec![1, 2, 5, 8]);
+    }
+}
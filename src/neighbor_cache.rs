@@ -0,0 +1,194 @@
+use crate::search::{search_with, Hooks, SearchOptions, SearchResult};
+use crate::{
+    get_neighbor_coords, step_cost, GridSource, Manhattan, NeighborBuffer, UnknownCells, DIRECTIONS,
+};
+
+/// Each cell's walkable neighbors and what stepping to them costs, worked
+/// out once for a map that rarely changes, so that searches through
+/// [`NeighborCache::search`] read them from a flat table instead of
+/// checking edges and walls on every expansion.
+///
+/// The cache only knows the grid it was built from. After changing a cell,
+/// call [`NeighborCache::invalidate`] with it, or [`NeighborCache::rebuild`]
+/// after changing many.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NeighborCache {
+    width: u32,
+    height: u32,
+    cardinal_directions: bool,
+    counts: Vec<u8>,
+    /// Eight slots per cell, of which the first `counts[cell]` are used.
+    neighbors: Vec<u32>,
+    step_costs: Vec<u32>,
+}
+
+const SLOTS: usize = 8;
+
+impl NeighborCache {
+    pub fn new<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> Self {
+        let len = grid.len() as usize;
+        let mut cache = NeighborCache {
+            width: grid.width(),
+            height: grid.height(),
+            cardinal_directions,
+            counts: vec![0; len],
+            neighbors: vec![0; len * SLOTS],
+            step_costs: vec![0; len * SLOTS],
+        };
+        cache.rebuild(grid);
+        cache
+    }
+
+    pub fn cardinal_directions(&self) -> bool {
+        self.cardinal_directions
+    }
+
+    /// The walkable neighbors of `cell`, empty outside the grid.
+    pub fn neighbors(&self, cell: u32) -> &[u32] {
+        self.slots(cell).map_or(&[], |slots| &self.neighbors[slots])
+    }
+
+    /// What stepping to each of [`NeighborCache::neighbors`] costs.
+    pub fn step_costs(&self, cell: u32) -> &[u32] {
+        self.slots(cell)
+            .map_or(&[], |slots| &self.step_costs[slots])
+    }
+
+    fn slots(&self, cell: u32) -> Option<std::ops::Range<usize>> {
+        let count = *self.counts.get(cell as usize)? as usize;
+        let first = cell as usize * SLOTS;
+        Some(first..first + count)
+    }
+
+    /// Catches up with a change to the cost of `cell`, which changes what
+    /// stepping into it costs from each of its neighbors.
+    pub fn invalidate<G: GridSource + ?Sized>(&mut self, grid: &G, cell: u32) {
+        if cell >= self.counts.len() as u32 {
+            return;
+        }
+        let (x, y) = ((cell % self.width) as i32, (cell / self.width) as i32);
+        for &(dx, dy) in DIRECTIONS.iter() {
+            let (x, y) = (x + dx, y + dy);
+            if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+                self.refresh(grid, y as u32 * self.width + x as u32);
+            }
+        }
+    }
+
+    /// Starts over from `grid`, which must be as big as the one the cache
+    /// was built from; a differently sized one is ignored.
+    pub fn rebuild<G: GridSource + ?Sized>(&mut self, grid: &G) {
+        if (grid.width(), grid.height()) != (self.width, self.height) {
+            return;
+        }
+        for cell in 0..grid.len() {
+            self.refresh(grid, cell);
+        }
+    }
+
+    fn refresh<G: GridSource + ?Sized>(&mut self, grid: &G, cell: u32) {
+        let neighbors = get_neighbor_coords(cell, grid, self.cardinal_directions);
+        let first = cell as usize * SLOTS;
+        for slot in 0..SLOTS {
+            let (neighbor, step) = match neighbors.get(slot) {
+                Some(&neighbor) => (neighbor, step_cost(grid, cell, neighbor)),
+                None => (0, 0),
+            };
+            self.neighbors[first + slot] = neighbor;
+            self.step_costs[first + slot] = step;
+        }
+        self.counts[cell as usize] = neighbors.len() as u8;
+    }
+
+    /// Like [`crate::search`] on `grid`, the grid the cache is kept up to
+    /// date with. Searches the grid directly when the options ask for
+    /// different movement than the cache was built for, read unknown cells
+    /// as anything but their cost, or the grid isn't the same size.
+    pub fn search<G: GridSource + ?Sized>(
+        &self,
+        start: u32,
+        end: u32,
+        grid: &G,
+        options: &SearchOptions,
+    ) -> SearchResult {
+        if options.cardinal_directions != self.cardinal_directions
+            || options.unknown_cells != UnknownCells::Cost
+            || (grid.width(), grid.height()) != (self.width, self.height)
+        {
+            return crate::search(start, end, grid, options);
+        }
+        search_with(start, end, grid, options, &Manhattan, Cached(self))
+    }
+}
+
+struct Cached<'a>(&'a NeighborCache);
+
+impl<'a> Hooks for Cached<'a> {
+    #[inline(always)]
+    fn cached_neighbors(&mut self, cell: u32, out: &mut NeighborBuffer<8>) -> bool {
+        let steps = self.0.neighbors(cell).iter().zip(self.0.step_costs(cell));
+        steps.for_each(|(&neighbor, &cost)| out.push(neighbor, cost));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Rng, UNKNOWN};
+
+    #[test]
+    fn it_searches_like_the_grid_and_follows_edits() {
+        let mut rng = Rng::new(1);
+        let mut grid = rng.grid(20, 15, 25, 5);
+        for &cardinal in &[false, true] {
+            let mut cache = NeighborCache::new(&grid, cardinal);
+            let options = SearchOptions {
+                cardinal_directions: cardinal,
+                record_trace: true,
+                ..SearchOptions::default()
+            };
+            for _ in 0..30 {
+                let (x, y) = (rng.range(0, 20), rng.range(0, 15));
+                let cost = if rng.chance(1, 3) { 0 } else { rng.range(1, 6) };
+                grid.set(x, y, cost);
+                cache.invalidate(&grid, y * 20 + x);
+                assert_eq!(cache, NeighborCache::new(&grid, cardinal));
+                let (start, end) = (rng.range(0, grid.len()), rng.range(0, grid.len()));
+                assert_eq!(
+                    cache.search(start, end, &grid, &options),
+                    search(start, end, &grid, &options)
+                );
+            }
+        }
+        // the cache holds costs as they are, so it can't read unknown cells
+        // any other way
+        for x in 5..15 {
+            grid.set(x, 7, UNKNOWN);
+        }
+        let cache = NeighborCache::new(&grid, true);
+        for unknown_cells in [
+            UnknownCells::Cost,
+            UnknownCells::Blocked,
+            UnknownCells::Open { cost: 1 },
+        ] {
+            let options = SearchOptions {
+                cardinal_directions: true,
+                unknown_cells,
+                ..SearchOptions::default()
+            };
+            for _ in 0..10 {
+                let (start, end) = (rng.range(0, grid.len()), rng.range(0, grid.len()));
+                assert_eq!(
+                    cache.search(start, end, &grid, &options),
+                    search(start, end, &grid, &options)
+                );
+            }
+        }
+        assert_eq!(
+            NeighborCache::new(&grid, false).neighbors(grid.len()),
+            &[] as &[u32]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::search::{run_in, Hooks, Scratch};
+use crate::{GridSource, Heuristic, SearchOptions};
+use smallvec::SmallVec;
+
+/// The steps out of a node, gathered without allocating as long as there
+/// are at most `N` of them. Sizing `N` to the largest step pattern, such as
+/// 6 on hexes or 26 between voxels, keeps every expansion on the stack.
+#[derive(Debug, Clone, Default)]
+pub struct NeighborBuffer<const N: usize> {
+    cells: SmallVec<[u32; N]>,
+    costs: SmallVec<[u32; N]>,
+}
+
+impl<const N: usize> NeighborBuffer<N> {
+    pub fn new() -> Self {
+        NeighborBuffer {
+            cells: SmallVec::new(),
+            costs: SmallVec::new(),
+        }
+    }
+
+    /// Adds a step to `to` that costs `cost`.
+    #[inline(always)]
+    pub fn push(&mut self, to: u32, cost: u32) {
+        self.cells.push(to);
+        self.costs.push(cost);
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Whether more than `N` steps were pushed, so that they moved to the
+    /// heap.
+    pub fn spilled(&self) -> bool {
+        self.cells.spilled()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.cells.iter().copied().zip(self.costs.iter().copied())
+    }
+
+    /// Where the steps lead, in the order they were pushed.
+    pub(crate) fn cells(&self) -> &[u32] {
+        &self.cells
+    }
+
+    /// What the steps cost, in the order they were pushed.
+    pub(crate) fn costs(&self) -> &[u32] {
+        &self.costs
+    }
+
+    #[inline(always)]
+    pub(crate) fn clear(&mut self) {
+        self.cells.clear();
+        self.costs.clear();
+    }
+}
+
+/// Any graph of nodes numbered from zero, for searching with step patterns
+/// the grid searches don't cover, through [`search_neighbors`].
+pub trait Neighbors<const N: usize> {
+    /// How many nodes there are. Nodes are numbered below this.
+    fn len(&self) -> u32;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Pushes every step out of `node` onto `out`, which starts empty.
+    fn neighbors(&self, node: u32, out: &mut NeighborBuffer<N>);
+
+    /// A lower bound on the cost from `node` to `goal`. Zero, the default,
+    /// turns the search into Dijkstra's.
+    fn estimate(&self, _node: u32, _goal: u32) -> u32 {
+        0
+    }
+}
+
+/// A path found by [`search_neighbors`], from the start (exclusive) to the
+/// goal (inclusive), like the paths of every other search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodePath {
+    pub nodes: Vec<u32>,
+    pub cost: u32,
+}
+
+/// The cheapest path from `start` to `end` through `graph`, or `None` when
+/// there is none. This is the search behind every grid search, steps
+/// gathered `N` at a time on the stack, so ties break like they do on grids.
+pub fn search_neighbors<S: Neighbors<N> + ?Sized, const N: usize>(
+    start: u32,
+    end: u32,
+    graph: &S,
+) -> Option<NodePath> {
+    let result = run_in::<_, _, _, N>(
+        &mut Scratch::default(),
+        start,
+        end,
+        &Nodes(graph.len()),
+        &SearchOptions::default(),
+        &Estimate::<_, N>(graph),
+        &mut Steps(graph),
+    );
+    Some(NodePath {
+        nodes: result.path,
+        cost: result.cost?,
+    })
+}
+
+/// The nodes of a graph laid out as one row of cells, which is all the
+/// search asks of a grid once [`Steps`] gives it every step.
+struct Nodes(u32);
+
+impl GridSource for Nodes {
+    fn width(&self) -> u32 {
+        self.0
+    }
+
+    fn height(&self) -> u32 {
+        1
+    }
+
+    fn cost(&self, _index: u32) -> u32 {
+        1
+    }
+}
+
+/// [`Neighbors::estimate`] as a heuristic.
+struct Estimate<'a, S: ?Sized, const N: usize>(&'a S);
+
+impl<S: Neighbors<N> + ?Sized, const N: usize> Heuristic for Estimate<'_, S, N> {
+    #[inline(always)]
+    fn estimate(&self, cell: u32, goal: u32, _width: u32) -> u32 {
+        self.0.estimate(cell, goal)
+    }
+}
+
+/// Hands the search the steps out of each node instead of the grid's.
+struct Steps<'a, S: ?Sized>(&'a S);
+
+impl<S: Neighbors<N> + ?Sized, const N: usize> Hooks<N> for Steps<'_, S> {
+    #[inline(always)]
+    fn cached_neighbors(&mut self, cell: u32, out: &mut NeighborBuffer<N>) -> bool {
+        self.0.neighbors(cell, out);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unit cubes that can step to any of the 26 around them.
+    struct Voxels(i32);
+
+    impl Voxels {
+        fn at(&self, node: u32) -> (i32, i32, i32) {
+            let (size, node) = (self.0, node as i32);
+            (node % size, node / size % size, node / (size * size))
+        }
+    }
+
+    impl Neighbors<26> for Voxels {
+        fn len(&self) -> u32 {
+            (self.0 * self.0 * self.0) as u32
+        }
+
+        fn neighbors(&self, node: u32, out: &mut NeighborBuffer<26>) {
+            let (x, y, z) = self.at(node);
+            for dz in -1..=1 {
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        let (x, y, z) = (x + dx, y + dy, z + dz);
+                        let inside = [x, y, z].iter().all(|&c| c >= 0 && c < self.0);
+                        if (dx, dy, dz) != (0, 0, 0) && inside {
+                            out.push(((z * self.0 + y) * self.0 + x) as u32, 1);
+                        }
+                    }
+                }
+            }
+            assert!(!out.spilled());
+        }
+
+        fn estimate(&self, node: u32, goal: u32) -> u32 {
+            let ((x, y, z), (gx, gy, gz)) = (self.at(node), self.at(goal));
+            (x - gx).abs().max((y - gy).abs()).max((z - gz).abs()) as u32
+        }
+    }
+
+    /// Hexes in axial coordinates on a parallelogram, with a wall down the
+    /// middle but for one gap.
+    struct Hexes;
+
+    impl Neighbors<6> for Hexes {
+        fn len(&self) -> u32 {
+            25
+        }
+
+        fn neighbors(&self, node: u32, out: &mut NeighborBuffer<6>) {
+            let (q, r) = ((node % 5) as i32, (node / 5) as i32);
+            for &(dq, dr) in &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, -1), (-1, 1)] {
+                let (q, r) = (q + dq, r + dr);
+                let wall = q == 2 && r != 4;
+                if (0..5).contains(&q) && (0..5).contains(&r) && !wall {
+                    out.push((r * 5 + q) as u32, 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_searches_voxels_and_hexes() {
+        let corner = search_neighbors(0, 63, &Voxels(4)).unwrap();
+        assert_eq!(
+            corner,
+            NodePath {
+                nodes: vec![21, 42, 63],
+                cost: 3
+            }
+        );
+        let around = search_neighbors(0, 4, &Hexes).unwrap();
+        assert_eq!(around.cost, 10);
+        assert_eq!(around.nodes.last(), Some(&4));
+        assert!(around.nodes.contains(&22));
+        assert_eq!(search_neighbors(0, 25, &Hexes), None);
+        assert_eq!(search_neighbors(3, 3, &Hexes), Some(NodePath::default()));
+    }
+}
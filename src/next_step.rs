@@ -0,0 +1,116 @@
+use crate::{
+    search, AllPairs, CompressedPathDatabase, GridSource, SearchOptions, TerminationReason,
+};
+
+/// Precomputed first moves that can answer [`next_step`] without a search.
+pub trait StepTable {
+    /// Whether the table was built for a grid of this shape moved through
+    /// this way. Whether the grid's costs still match is up to the caller.
+    fn covers(&self, width: u32, height: u32, cardinal_directions: bool) -> bool;
+
+    /// The cell to step into from `from` on a cheapest route to `goal`.
+    fn first_step(&self, from: u32, goal: u32) -> Option<u32>;
+}
+
+impl StepTable for CompressedPathDatabase {
+    fn covers(&self, width: u32, height: u32, cardinal_directions: bool) -> bool {
+        (self.width(), self.height(), self.cardinal_directions())
+            == (width, height, cardinal_directions)
+    }
+
+    fn first_step(&self, from: u32, goal: u32) -> Option<u32> {
+        self.next_step(from, goal)
+    }
+}
+
+impl StepTable for AllPairs {
+    fn covers(&self, width: u32, height: u32, cardinal_directions: bool) -> bool {
+        (self.width(), self.height(), self.cardinal_directions())
+            == (width, height, cardinal_directions)
+    }
+
+    fn first_step(&self, from: u32, goal: u32) -> Option<u32> {
+        self.next_step(from, goal)
+    }
+}
+
+/// The cell to step into from `start` toward `end`, for agents that replan
+/// every tick and would throw the rest of the path away. `None` when `start`
+/// is `end` or nothing leads there.
+///
+/// A `table` built for this grid and movement mode answers with a lookup.
+/// Otherwise this searches, and with [`SearchOptions::max_expansions`] set
+/// it takes the first step toward the closest cell reached when the limit
+/// is hit, which may turn out to be a detour.
+pub fn next_step<G: GridSource + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    table: Option<&dyn StepTable>,
+) -> Option<u32> {
+    if let Some(table) =
+        table.filter(|table| table.covers(grid.width(), grid.height(), options.cardinal_directions))
+    {
+        return table.first_step(start, end);
+    }
+    let options = SearchOptions {
+        record_trace: false,
+        partial_path: true,
+        reverse_path: false,
+        ..options.clone()
+    };
+    let result = search(start, end, grid, &options);
+    if result.termination == TerminationReason::Exhausted {
+        return None;
+    }
+    result.path.first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_grid, Grid, Rng};
+
+    #[test]
+    fn lookups_and_searches_agree() {
+        let mut rng = Rng::new(1);
+        let grid = rng.grid(12, 9, 20, 4);
+        let database = CompressedPathDatabase::new(&grid, false);
+        let all_pairs = AllPairs::new(&grid, false);
+        let options = SearchOptions::default();
+        for _ in 0..50 {
+            let start = rng.below(grid.len());
+            let end = rng.below(grid.len());
+            let searched = next_step(start, end, &grid, &options, None);
+            assert_eq!(
+                searched,
+                astar_grid(start, end, &grid, false).first().copied()
+            );
+            for table in [&database as &dyn StepTable, &all_pairs] {
+                let looked_up = next_step(start, end, &grid, &options, Some(table));
+                assert_eq!(looked_up.is_some(), searched.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn mismatched_tables_fall_back_to_searching() {
+        let grid = Grid::filled(5, 5, 1).unwrap();
+        let diagonal = CompressedPathDatabase::new(&grid, false);
+        let options = SearchOptions {
+            cardinal_directions: true,
+            ..SearchOptions::default()
+        };
+        assert_eq!(next_step(0, 24, &grid, &options, Some(&diagonal)), Some(5));
+        let truncated = SearchOptions {
+            max_expansions: Some(2),
+            ..options
+        };
+        assert_eq!(
+            next_step(0, 24, &grid, &truncated, Some(&diagonal)),
+            Some(5)
+        );
+        assert_eq!(next_step(6, 6, &grid, &truncated, None), None);
+    }
+}
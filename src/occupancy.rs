@@ -0,0 +1,212 @@
+use crate::{GridSource, WALL};
+
+/// Marks a cell nobody stands on.
+const VACANT: u32 = u32::MAX;
+
+/// Which unit stands on each cell, kept up to date as they move with
+/// [`Occupancy::occupy`] and [`Occupancy::vacate`], so that searches can
+/// keep out of each other's way through [`Occupancy::over`]. Units are
+/// numbered by the caller, with any ids but `u32::MAX`; each cell holds at
+/// most one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Occupancy {
+    occupants: Vec<u32>,
+}
+
+/// What searches through [`Occupancy::over`] make of occupied cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OccupiedCells {
+    /// As walls, for units that can't pass each other.
+    Blocked,
+    /// As costing `extra` more, for units that would rather go round but
+    /// can wait for the way to clear, or push through.
+    Cost { extra: u32 },
+}
+
+impl Occupancy {
+    /// A layer over a grid of `len` cells with nobody on it.
+    pub fn new(len: u32) -> Self {
+        Occupancy {
+            occupants: vec![VACANT; len as usize],
+        }
+    }
+
+    /// Puts `entity` on `cell`, returning whoever stood there before.
+    /// Cells outside the layer are left alone.
+    pub fn occupy(&mut self, cell: u32, entity: u32) -> Option<u32> {
+        let occupant = self.occupants.get_mut(cell as usize)?;
+        let previous = std::mem::replace(occupant, entity);
+        Some(previous).filter(|&previous| previous != VACANT)
+    }
+
+    /// Clears `cell`, returning whoever stood there.
+    pub fn vacate(&mut self, cell: u32) -> Option<u32> {
+        let occupant = self.occupants.get_mut(cell as usize)?;
+        let previous = std::mem::replace(occupant, VACANT);
+        Some(previous).filter(|&previous| previous != VACANT)
+    }
+
+    /// Who stands on `cell`, if anyone.
+    pub fn occupant(&self, cell: u32) -> Option<u32> {
+        match self.occupants.get(cell as usize) {
+            Some(&entity) if entity != VACANT => Some(entity),
+            _ => None,
+        }
+    }
+
+    /// Clears every cell.
+    pub fn clear(&mut self) {
+        self.occupants
+            .iter_mut()
+            .for_each(|entity| *entity = VACANT);
+    }
+
+    /// `grid` with the cells others stand on read as `occupied` says, for
+    /// searching around them. Cells held by one of `ignoring`, such as the
+    /// unit searching and whoever it is following, are read as they are, so
+    /// that a unit doesn't get in its own way.
+    pub fn over<'a, G: GridSource>(
+        &'a self,
+        grid: G,
+        occupied: OccupiedCells,
+        ignoring: &'a [u32],
+    ) -> Occupied<'a, G> {
+        Occupied {
+            grid,
+            occupancy: self,
+            occupied,
+            ignoring,
+            swappable: &[],
+            swap_cost: 0,
+        }
+    }
+}
+
+/// A grid read around the units of an [`Occupancy`], made by
+/// [`Occupancy::over`]. Walls stay walls whoever stands on them.
+#[derive(Debug, Clone, Copy)]
+pub struct Occupied<'a, G> {
+    grid: G,
+    occupancy: &'a Occupancy,
+    occupied: OccupiedCells,
+    ignoring: &'a [u32],
+    swappable: &'a [u32],
+    swap_cost: u32,
+}
+
+impl<'a, G> Occupied<'a, G> {
+    /// The same view, letting units step onto cells held by one of
+    /// `friends` by swapping places with them, for `cost` more than the
+    /// cell costs, however other occupied cells are read. This is for
+    /// puzzle games and tactics games where friendly units trade places
+    /// rather than block each other; the swap itself is for the caller to
+    /// carry out, along the path, with [`Occupancy::occupy`].
+    pub fn swapping(self, friends: &'a [u32], cost: u32) -> Self {
+        Occupied {
+            swappable: friends,
+            swap_cost: cost,
+            ..self
+        }
+    }
+}
+
+impl<G: GridSource> GridSource for Occupied<'_, G> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.grid.width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.grid.height()
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        let cost = self.grid.cost(index);
+        match self.occupancy.occupant(index) {
+            Some(entity) if cost != WALL && !self.ignoring.contains(&entity) => {
+                if self.swappable.contains(&entity) {
+                    return cost.saturating_add(self.swap_cost);
+                }
+                match self.occupied {
+                    OccupiedCells::Blocked => WALL,
+                    OccupiedCells::Cost { extra } => cost.saturating_add(extra),
+                }
+            }
+            _ => cost,
+        }
+    }
+
+    #[inline(always)]
+    fn has_wall_border(&self) -> bool {
+        self.grid.has_wall_border()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid, SearchOptions};
+
+    #[test]
+    fn units_keep_out_of_each_others_way() {
+        let grid = Grid::filled(5, 3, 1).unwrap();
+        let mut occupancy = Occupancy::new(grid.len());
+        assert_eq!(occupancy.occupy(5, 1), None);
+        assert_eq!(occupancy.occupy(7, 2), None);
+        assert_eq!(occupancy.occupy(7, 3), Some(2));
+        assert_eq!(occupancy.occupy(20, 4), None);
+        assert_eq!(occupancy.occupant(7), Some(3));
+        let options = SearchOptions {
+            cardinal_directions: true,
+            ..SearchOptions::default()
+        };
+        // unit 1 walks right along the middle row, where 3 stands
+        let blocked = occupancy.over(&grid, OccupiedCells::Blocked, &[1]);
+        let around = search(5, 9, &blocked, &options);
+        assert!(!around.path.contains(&7));
+        assert_eq!(around.cost, Some(12));
+        let costly = occupancy.over(&grid, OccupiedCells::Cost { extra: 1 }, &[1]);
+        assert_eq!(search(5, 9, &costly, &options).cost, Some(9));
+        let squad = occupancy.over(&grid, OccupiedCells::Blocked, &[1, 3]);
+        assert_eq!(search(5, 9, &squad, &options).cost, Some(8));
+        // like any start, the cell a search leaves is left even when blocked
+        let nobody = occupancy.over(&grid, OccupiedCells::Blocked, &[]);
+        assert_eq!(search(5, 6, &nobody, &options).cost, Some(2));
+        assert_eq!(occupancy.vacate(7), Some(3));
+        assert_eq!(occupancy.vacate(7), None);
+        let cleared = occupancy.over(&grid, OccupiedCells::Blocked, &[1]);
+        assert_eq!(search(5, 9, &cleared, &options).cost, Some(8));
+        occupancy.clear();
+        assert_eq!(occupancy.occupant(5), None);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn friends_swap_places_for_a_price() {
+        // a corridor with a friend and then a stranger in it
+        let grid = Grid::new(vec![
+            1, 1, 1, 1, 1,
+            0, 0, 1, 0, 1,
+            1, 1, 1, 1, 1,
+        ], 5).unwrap();
+        let mut occupancy = Occupancy::new(grid.len());
+        occupancy.occupy(0, 1);
+        occupancy.occupy(7, 2);
+        occupancy.occupy(4, 3);
+        let options = SearchOptions { cardinal_directions: true, ..SearchOptions::default() };
+        let blocked = occupancy.over(&grid, OccupiedCells::Blocked, &[1]);
+        assert_eq!(search(0, 10, &blocked, &options).cost, None);
+        let swapping = blocked.swapping(&[2], 3);
+        let through = search(0, 10, &swapping, &options);
+        assert_eq!(through.path, vec![1, 2, 7, 12, 11, 10]);
+        assert_eq!(through.cost, Some(15));
+        // only those named can be swapped with
+        let other = search(0, 14, &blocked.swapping(&[3], 3), &options);
+        assert_eq!(other.path, vec![1, 2, 3, 4, 9, 14]);
+        assert_eq!(other.cost, Some(15));
+    }
+}
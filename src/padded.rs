@@ -0,0 +1,167 @@
+use crate::search::{self, SearchOptions, SearchResult};
+use crate::{GridError, GridSource, Manhattan};
+
+/// A copy of a grid's costs surrounded by a border of walls, so that every
+/// neighbor of a cell a search can stand on lies inside the grid and no
+/// step has to check where the edges are.
+///
+/// Cells are still addressed as in the grid it was made from, both as a
+/// [`GridSource`] and by [`PaddedGrid::search`]; the border is never seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PaddedGrid {
+    width: u32,
+    height: u32,
+    /// Row-major, two cells wider and taller than the grid.
+    cells: Vec<u32>,
+}
+
+impl PaddedGrid {
+    /// Copies `grid`, failing with [`GridError::TooLarge`] when the border
+    /// would make it too big for a `u32` index.
+    pub fn new<G: GridSource + ?Sized>(grid: &G) -> Result<Self, GridError> {
+        let (width, height) = (grid.width(), grid.height());
+        let padded_width = width as u64 + 2;
+        let len = padded_width * (height as u64 + 2);
+        if len > u32::MAX as u64 {
+            return Err(GridError::TooLarge { len: len as usize });
+        }
+        let mut cells = vec![0; len as usize];
+        for index in 0..grid.len() {
+            let (x, y) = (index % width, index / width);
+            cells[((y as u64 + 1) * padded_width + x as u64 + 1) as usize] = grid.cost(index);
+        }
+        Ok(PaddedGrid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Like [`crate::search`] on the grid this was made from, with the same
+    /// results, but without checking for edges on each step. Jittered
+    /// searches shuffle cells by their index, so they run on the grid as it
+    /// was to keep their results.
+    pub fn search(&self, start: u32, end: u32, options: &SearchOptions) -> SearchResult {
+        if start >= self.len() || end >= self.len() || options.jitter.is_some() {
+            return crate::search(start, end, self, options);
+        }
+        let walled = Walled(self);
+        let padded = |cell: u32| walled.pad(cell);
+        // padding keeps cells in the same order, so ties break the same way
+        let mut result =
+            search::search_with(padded(start), padded(end), &walled, options, &Manhattan, ());
+        let unpadded = |cell: u32| walled.unpad(cell);
+        result
+            .path
+            .iter_mut()
+            .for_each(|cell| *cell = unpadded(*cell));
+        result.closest = unpadded(result.closest);
+        if let Some(trace) = &mut result.trace {
+            trace.width = self.width;
+            trace.start = start;
+            trace.goal = end;
+            for expansion in &mut trace.expansions {
+                expansion.cell = unpadded(expansion.cell);
+                expansion.parent = expansion.parent.map(unpadded);
+            }
+        }
+        result
+    }
+}
+
+impl GridSource for PaddedGrid {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        self.cells[Walled(self).pad(index) as usize]
+    }
+}
+
+/// The padded cells themselves, border and all, which is what searches on a
+/// [`PaddedGrid`] run over.
+struct Walled<'a>(&'a PaddedGrid);
+
+impl<'a> Walled<'a> {
+    #[inline(always)]
+    fn pad(&self, cell: u32) -> u32 {
+        let width = self.0.width;
+        (cell / width + 1) * (width + 2) + cell % width + 1
+    }
+
+    #[inline(always)]
+    fn unpad(&self, cell: u32) -> u32 {
+        let padded_width = self.0.width + 2;
+        (cell / padded_width - 1) * self.0.width + cell % padded_width - 1
+    }
+}
+
+impl<'a> GridSource for Walled<'a> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.0.width + 2
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.0.height + 2
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        self.0.cells[index as usize]
+    }
+
+    #[inline(always)]
+    fn has_wall_border(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid, Rng};
+
+    #[test]
+    fn it_searches_like_the_grid_it_pads() {
+        let mut rng = Rng::new(1);
+        for &(width, height) in &[(1, 6), (6, 1), (9, 7), (16, 12)] {
+            let grid = rng.grid(width, height, 25, 4);
+            let padded = PaddedGrid::new(&grid).unwrap();
+            assert_eq!((padded.width(), padded.height()), (width, height));
+            for cell in 0..grid.len() {
+                assert_eq!(padded.cost(cell), grid.cost(cell));
+            }
+            for _ in 0..20 {
+                let (start, end) = (rng.range(0, grid.len()), rng.range(0, grid.len()));
+                for &(cardinal, jitter) in &[(false, None), (true, None), (false, Some(7))] {
+                    let options = SearchOptions {
+                        cardinal_directions: cardinal,
+                        jitter,
+                        record_trace: true,
+                        partial_path: true,
+                        ..SearchOptions::default()
+                    };
+                    assert_eq!(
+                        padded.search(start, end, &options),
+                        search(start, end, &grid, &options)
+                    );
+                }
+            }
+        }
+        let grid = Grid::filled(3, 3, 1).unwrap();
+        let padded = PaddedGrid::new(&grid).unwrap();
+        let options = SearchOptions::default();
+        assert_eq!(padded.search(0, 9, &options), search(0, 9, &grid, &options));
+    }
+}
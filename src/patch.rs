@@ -0,0 +1,251 @@
+use crate::{Cost, DijkstraMap, Grid, GridError, GridSource};
+use fxhash::FxHashMap;
+
+/// A set of cell changes that can be applied to a [`Grid`] and reverted again,
+/// for undo and for trying out edits before committing to them.
+///
+/// Applying swaps each changed cell with the value held in the patch, so the
+/// patch holds the previous values until it is reverted. Either direction
+/// only touches the changed cells.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridPatch<T = u32> {
+    changes: Vec<(u32, T)>,
+    applied: bool,
+}
+
+impl<T> GridPatch<T> {
+    pub fn new() -> Self {
+        GridPatch {
+            changes: Vec::new(),
+            applied: false,
+        }
+    }
+
+    /// The changes that would turn `before` into `after`, or an error when
+    /// the two aren't the same size.
+    pub fn diff(before: &Grid<T>, after: &Grid<T>) -> Result<Self, GridError>
+    where
+        T: Clone + PartialEq,
+    {
+        if (before.width(), before.height()) != (after.width(), after.height()) {
+            return Err(GridError::SizeMismatch);
+        }
+        let changes = before
+            .cells()
+            .iter()
+            .zip(after.cells())
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(index, (_, new))| (index as u32, new.clone()))
+            .collect();
+        Ok(GridPatch {
+            changes,
+            applied: false,
+        })
+    }
+
+    /// Adds a change setting the cell at `index` to `value`. Later changes to
+    /// the same cell win.
+    ///
+    /// # Panics
+    ///
+    /// When the patch is applied.
+    pub fn set(&mut self, index: u32, value: T) -> &mut Self {
+        assert!(!self.applied, "an applied patch can't be extended");
+        self.changes.push((index, value));
+        self
+    }
+
+    /// The cells the patch changes, in the order the changes were added.
+    pub fn cells(&self) -> impl Iterator<Item = u32> + '_ {
+        self.changes.iter().map(|&(index, _)| index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn is_applied(&self) -> bool {
+        self.applied
+    }
+
+    /// Makes the changes to `grid`. Fails without changing anything when a
+    /// change lies outside the grid.
+    ///
+    /// # Panics
+    ///
+    /// When the patch is already applied.
+    pub fn apply(&mut self, grid: &mut Grid<T>) -> Result<(), GridError> {
+        assert!(!self.applied, "the patch is already applied");
+        if self
+            .changes
+            .iter()
+            .any(|&(index, _)| index as usize >= grid.cells().len())
+        {
+            return Err(GridError::OutOfBounds);
+        }
+        let cells = grid.cells_mut();
+        for (index, value) in self.changes.iter_mut() {
+            std::mem::swap(&mut cells[*index as usize], value);
+        }
+        self.applied = true;
+        Ok(())
+    }
+
+    /// Undoes [`GridPatch::apply`] on the same grid, which must not have had
+    /// the changed cells edited in between.
+    ///
+    /// # Panics
+    ///
+    /// When the patch isn't applied.
+    pub fn revert(&mut self, grid: &mut Grid<T>) {
+        assert!(self.applied, "the patch isn't applied");
+        let cells = grid.cells_mut();
+        for (index, value) in self.changes.iter_mut().rev() {
+            std::mem::swap(&mut cells[*index as usize], value);
+        }
+        self.applied = false;
+    }
+}
+
+impl<T: Cost> GridPatch<T> {
+    /// Reads `grid` as if the patch were applied, without changing it.
+    ///
+    /// # Panics
+    ///
+    /// When the patch is applied, since it then holds the previous values.
+    pub fn over<G: GridSource>(&self, grid: G) -> Patched<G> {
+        assert!(!self.applied, "an applied patch holds the previous values");
+        Patched {
+            grid,
+            costs: self
+                .changes
+                .iter()
+                .map(|(index, value)| (*index, value.cost()))
+                .collect(),
+        }
+    }
+}
+
+/// A grid read through a [`GridPatch`] that hasn't been applied to it, made
+/// by [`GridPatch::over`].
+#[derive(Debug, Clone)]
+pub struct Patched<G> {
+    grid: G,
+    costs: FxHashMap<u32, u32>,
+}
+
+impl<G: GridSource> GridSource for Patched<G> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.grid.width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.grid.height()
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        match self.costs.get(&index) {
+            Some(&cost) => cost,
+            None => self.grid.cost(index),
+        }
+    }
+}
+
+/// Whether applying `patch` to `grid` would cut off any of the
+/// `critical_pairs` of start and goal cells that can reach each other now,
+/// such as a tower defense spawn and exit when a tower is about to be
+/// placed. Nothing is changed; pairs that were already cut off don't count.
+pub fn would_block_path<G: GridSource + ?Sized, T: Cost>(
+    grid: &G,
+    patch: &GridPatch<T>,
+    critical_pairs: &[(u32, u32)],
+    cardinal_directions: bool,
+) -> bool {
+    let patched = patch.over(grid);
+    let mut starts: Vec<u32> = critical_pairs.iter().map(|&(start, _)| start).collect();
+    starts.sort_unstable();
+    starts.dedup();
+    starts.into_iter().any(|start| {
+        let after = DijkstraMap::new(&patched, &[start], cardinal_directions);
+        let mut cut_off = critical_pairs
+            .iter()
+            .filter(|&&(from, goal)| from == start && after.distance(goal).is_none())
+            .peekable();
+        if cut_off.peek().is_none() {
+            return false;
+        }
+        let before = DijkstraMap::new(grid, &[start], cardinal_directions);
+        cut_off.any(|&(_, goal)| before.distance(goal).is_some())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_applies_and_reverts() {
+        let mut grid = crate::grid![
+            1, 1, 1;
+            1, 0, 1;
+            1, 1, 1;
+        ];
+        let original = grid.clone();
+        let mut patch = GridPatch::new();
+        patch.set(1, 0).set(3, 0).set(1, 5);
+        patch.apply(&mut grid).unwrap();
+        assert_eq!(grid.cells(), &[1, 5, 1, 0, 0, 1, 1, 1, 1]);
+        patch.revert(&mut grid);
+        assert_eq!(grid, original);
+        patch.apply(&mut grid).unwrap();
+        let after = grid.clone();
+        patch.revert(&mut grid);
+        let mut diff = GridPatch::diff(&grid, &after).unwrap();
+        assert_eq!(diff.cells().collect::<Vec<_>>(), vec![1, 3]);
+        diff.apply(&mut grid).unwrap();
+        assert_eq!(grid, after);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_spots_placements_that_seal_the_exit() {
+        let grid = crate::grid![
+            1, 1, 1, 0, 1;
+            1, 1, 1, 1, 1;
+            1, 1, 1, 0, 1;
+        ];
+        let pairs = [(0, 4), (10, 14), (0, 2)];
+        let mut tower = GridPatch::new();
+        tower.set(8, 0);
+        assert!(would_block_path(&grid, &tower, &pairs, true));
+        assert_eq!(tower.over(&grid).cost(8), 0);
+        assert_eq!(grid.cells()[8], 1);
+        let mut harmless = GridPatch::new();
+        harmless.set(6, 0);
+        assert!(!would_block_path(&grid, &harmless, &pairs, true));
+        let walled_in = crate::grid![1, 0, 1];
+        let mut anything = GridPatch::new();
+        anything.set(0, 1);
+        assert!(!would_block_path(&walled_in, &anything, &[(0, 2)], true));
+    }
+
+    #[test]
+    fn it_rejects_changes_outside_the_grid() {
+        let mut grid = Grid::filled(2, 2, 1).unwrap();
+        let mut patch = GridPatch::new();
+        patch.set(0, 0).set(4, 0);
+        assert_eq!(patch.apply(&mut grid), Err(GridError::OutOfBounds));
+        assert!(!patch.is_applied());
+        assert_eq!(grid.cells(), &[1, 1, 1, 1]);
+    }
+}
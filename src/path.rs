@@ -0,0 +1,336 @@
+use crate::{line_of_sight, step_cost, GridSource, WALL};
+
+/// Dense samples per spline segment when measuring its length.
+const SEGMENT_SAMPLES: usize = 16;
+
+/// A path together with the cost of each of its steps, so it can be cut
+/// down to a movement budget without looking at the grid again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Path {
+    /// The cell the path leaves from.
+    pub start: u32,
+    /// The cells after the start, as every search returns them.
+    pub cells: Vec<u32>,
+    /// What entering each of `cells` costs from the cell before it.
+    pub step_costs: Vec<u32>,
+}
+
+impl Path {
+    /// Prices each step of `cells`, a path leaving `start`, on `grid`.
+    pub fn new<G: GridSource + ?Sized>(grid: &G, start: u32, cells: Vec<u32>) -> Self {
+        let mut from = start;
+        let step_costs = cells
+            .iter()
+            .map(|&to| {
+                let cost = step_cost(grid, from, to);
+                from = to;
+                cost
+            })
+            .collect();
+        Path {
+            start,
+            cells,
+            step_costs,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn cost(&self) -> u32 {
+        self.step_costs
+            .iter()
+            .fold(0u32, |total, &cost| total.saturating_add(cost))
+    }
+
+    /// Keeps the longest start of the path costing at most `budget` and
+    /// returns what the dropped steps cost, which is the cost still to pay to
+    /// reach the goal when the path led there.
+    pub fn truncate_to_cost(&mut self, budget: u32) -> u32 {
+        let mut spent = 0u32;
+        let kept = self
+            .step_costs
+            .iter()
+            .take_while(|&&cost| {
+                spent = spent.saturating_add(cost);
+                spent <= budget
+            })
+            .count();
+        let remaining = self.step_costs[kept..]
+            .iter()
+            .fold(0u32, |total, &cost| total.saturating_add(cost));
+        self.cells.truncate(kept);
+        self.step_costs.truncate(kept);
+        remaining
+    }
+
+    /// Whether both paths walk the same cells from the same start, whatever
+    /// the steps cost now, such as to tell whether a route changed enough
+    /// to send again. `==` compares everything, step costs included.
+    pub fn same_cells(&self, other: &Path) -> bool {
+        self.start == other.start && self.cells == other.cells
+    }
+
+    /// [`crate::path_hash`] of the start and cells, equal between paths
+    /// exactly when [`Path::same_cells`] is, bar collisions, and the same
+    /// in every version of this crate.
+    pub fn stable_hash(&self) -> u64 {
+        crate::path_hash(self.start, &self.cells)
+    }
+
+    /// Whether both paths lead between the same two cells for the same
+    /// cost, so that either would do, even along different cells.
+    pub fn same_cost(&self, other: &Path) -> bool {
+        self.start == other.start && self.last() == other.last() && self.cost() == other.cost()
+    }
+
+    /// How many cells after the start both paths walk before they part,
+    /// `0` when they leave from different cells.
+    pub fn shared_prefix(&self, other: &Path) -> usize {
+        if self.start != other.start {
+            return 0;
+        }
+        self.cells
+            .iter()
+            .zip(&other.cells)
+            .take_while(|(a, b)| a == b)
+            .count()
+    }
+
+    /// The furthest any cell of either path, starts included, lies from the
+    /// nearest cell of the other on a grid `width` cells wide, counting a
+    /// diagonal step as one. `0` means they cover the same cells, and a
+    /// route that wanders one cell aside gives `1`. This compares every cell
+    /// against every other, so it is meant for paths rather than floods. A
+    /// `width` of `0` is read as `1`.
+    pub fn deviation(&self, other: &Path, width: u32) -> u32 {
+        let width = width.max(1);
+        let steps = |a: u32, b: u32| {
+            let dx = (a % width).abs_diff(b % width);
+            let dy = (a / width).abs_diff(b / width);
+            dx.max(dy)
+        };
+        let furthest = |from: &Path, to: &Path| {
+            from.all_cells()
+                .map(|cell| {
+                    to.all_cells()
+                        .map(|near| steps(cell, near))
+                        .min()
+                        .unwrap_or(0)
+                })
+                .max()
+                .unwrap_or(0)
+        };
+        furthest(self, other).max(furthest(other, self))
+    }
+
+    /// Where the path ends, which is its start when it has no steps.
+    fn last(&self) -> u32 {
+        self.cells.last().copied().unwrap_or(self.start)
+    }
+
+    fn all_cells(&self) -> impl Iterator<Item = u32> + '_ {
+        std::iter::once(self.start).chain(self.cells.iter().copied())
+    }
+
+    /// The cells the path turns at, ending with its last cell, such that
+    /// each can be seen from the one before it (starting from the start),
+    /// so an agent can walk straight lines between them instead of along
+    /// every cell.
+    pub fn smoothed<G: GridSource + ?Sized>(&self, grid: &G) -> Vec<u32> {
+        let width = grid.width();
+        let xy = |cell: u32| (cell % width, cell / width);
+        let mut waypoints = Vec::new();
+        let mut anchor = self.start;
+        for (index, &cell) in self.cells.iter().enumerate().skip(1) {
+            if !line_of_sight(grid, xy(anchor), xy(cell)) {
+                anchor = self.cells[index - 1];
+                waypoints.push(anchor);
+            }
+        }
+        waypoints.extend(self.cells.last());
+        waypoints
+    }
+
+    /// Points `spacing` apart along a Catmull-Rom spline through the start
+    /// and the [`Path::smoothed`] waypoints, for agents that move
+    /// continuously. A cell `(x, y)` covers `x..x + 1` and `y..y + 1`, and
+    /// the spline passes through cell centers. Points where the curve
+    /// swings into a wall are pulled back into the nearer waypoint's cell.
+    ///
+    /// The first point is the start's center and the last the goal's, which
+    /// may be closer than `spacing` to the point before it. A `spacing` that
+    /// isn't positive gives just the waypoints.
+    pub fn to_spline_samples<G: GridSource + ?Sized>(
+        &self,
+        grid: &G,
+        spacing: f32,
+    ) -> Vec<(f32, f32)> {
+        let width = grid.width();
+        let mut cells = vec![self.start];
+        cells.extend(self.smoothed(grid));
+        let center = |cell: u32| ((cell % width) as f32 + 0.5, (cell / width) as f32 + 0.5);
+        let points: Vec<(f32, f32)> = cells.iter().map(|&cell| center(cell)).collect();
+        if points.len() < 2 || spacing.is_nan() || spacing <= 0.0 {
+            return points;
+        }
+        // the spline densely sampled, each point with the segment it lies on
+        let mut dense = Vec::with_capacity((points.len() - 1) * SEGMENT_SAMPLES + 1);
+        for segment in 0..points.len() - 1 {
+            let p0 = points[segment.saturating_sub(1)];
+            let p1 = points[segment];
+            let p2 = points[segment + 1];
+            let p3 = points[(segment + 2).min(points.len() - 1)];
+            for step in 0..SEGMENT_SAMPLES {
+                let t = step as f32 / SEGMENT_SAMPLES as f32;
+                dense.push((catmull_rom(p0, p1, p2, p3, t), segment));
+            }
+        }
+        dense.push((points[points.len() - 1], points.len() - 2));
+        let mut samples = vec![dense[0].0];
+        let mut travelled = 0.0;
+        let mut next = spacing;
+        for pair in dense.windows(2) {
+            let ((from, segment), (to, _)) = (pair[0], pair[1]);
+            let length = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+            while travelled + length >= next && length > 0.0 {
+                let t = (next - travelled) / length;
+                let point = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+                samples.push(clamp_to_walkable(
+                    grid,
+                    point,
+                    cells[segment],
+                    cells[segment + 1],
+                ));
+                next += spacing;
+            }
+            travelled += length;
+        }
+        let goal = points[points.len() - 1];
+        if samples.last() != Some(&goal) {
+            samples.push(goal);
+        }
+        samples
+    }
+}
+
+fn catmull_rom(
+    p0: (f32, f32),
+    p1: (f32, f32),
+    p2: (f32, f32),
+    p3: (f32, f32),
+    t: f32,
+) -> (f32, f32) {
+    let blend = |a: f32, b: f32, c: f32, d: f32| {
+        0.5 * (2.0 * b
+            + (c - a) * t
+            + (2.0 * a - 5.0 * b + 4.0 * c - d) * t * t
+            + (3.0 * b - a - 3.0 * c + d) * t * t * t)
+    };
+    (blend(p0.0, p1.0, p2.0, p3.0), blend(p0.1, p1.1, p2.1, p3.1))
+}
+
+/// `point` if it lies in a walkable cell, otherwise the closest point of
+/// whichever of `from` and `to` has the closer center.
+fn clamp_to_walkable<G: GridSource + ?Sized>(
+    grid: &G,
+    point: (f32, f32),
+    from: u32,
+    to: u32,
+) -> (f32, f32) {
+    let width = grid.width();
+    let (x, y) = (point.0.floor(), point.1.floor());
+    let inside = x >= 0.0 && y >= 0.0 && (x as u32) < width && (y as u32) < grid.height();
+    if inside && grid.cost(y as u32 * width + x as u32) != WALL {
+        return point;
+    }
+    let distance = |cell: u32| {
+        let (cx, cy) = ((cell % width) as f32 + 0.5, (cell / width) as f32 + 0.5);
+        (point.0 - cx).powi(2) + (point.1 - cy).powi(2)
+    };
+    let cell = if distance(from) <= distance(to) {
+        from
+    } else {
+        to
+    };
+    let (left, top) = ((cell % width) as f32, (cell / width) as f32);
+    (
+        point.0.clamp(left + 0.001, left + 0.999),
+        point.1.clamp(top + 0.001, top + 0.999),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_grid, search, Grid, SearchOptions};
+
+    #[test]
+    #[rustfmt::skip]
+    fn samples_follow_the_path_evenly() {
+        let grid = crate::grid![
+            1, 1, 1, 1, 1, 1;
+            1, 0, 0, 0, 0, 1;
+            1, 0, 1, 1, 1, 1;
+        ];
+        let path = Path::new(&grid, 12, astar_grid(12, 16, &grid, true));
+        assert_eq!(path.smoothed(&grid), vec![0, 5, 17, 16]);
+        let samples = path.to_spline_samples(&grid, 0.5);
+        assert_eq!(samples.first(), Some(&(0.5, 2.5)));
+        assert_eq!(samples.last(), Some(&(4.5, 2.5)));
+        for pair in samples.windows(2) {
+            let gap = ((pair[1].0 - pair[0].0).powi(2) + (pair[1].1 - pair[0].1).powi(2)).sqrt();
+            assert!(gap <= 0.5 + 1e-3);
+        }
+        for &(x, y) in &samples {
+            assert!(grid.cost(grid.index(x as u32, y as u32)) > 0, "({}, {})", x, y);
+        }
+        assert!(samples.len() > 18);
+        assert_eq!(path.to_spline_samples(&grid, 0.0).len(), 5);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_spends_the_budget_step_by_step() {
+        let grid = Grid::new(vec![
+            1, 3, 1, 1,
+        ], 4).unwrap();
+        let mut path = Path::new(&grid, 0, astar_grid(0, 3, &grid, true));
+        assert_eq!(path.step_costs, vec![4, 2, 2]);
+        assert_eq!(path.cost(), search(0, 3, &grid, &SearchOptions::default()).cost.unwrap());
+        let mut whole = path.clone();
+        assert_eq!(whole.truncate_to_cost(8), 0);
+        assert_eq!(whole, path);
+        assert_eq!(path.truncate_to_cost(7), 2);
+        assert_eq!(path.cells, vec![1, 2]);
+        assert_eq!(path.truncate_to_cost(3), 6);
+        assert!(path.is_empty());
+    }
+
+    #[test]
+    fn paths_compare_by_cells_cost_and_distance() {
+        let grid = Grid::filled(4, 3, 1).unwrap();
+        // along the top row, and the same way dipping into the middle one
+        let straight = Path::new(&grid, 0, vec![1, 2, 3]);
+        let dipping = Path::new(&grid, 0, vec![5, 2, 3]);
+        let low = Path::new(&grid, 0, vec![4, 9, 10, 7]);
+        assert!(straight.same_cells(&straight.clone()));
+        assert!(!straight.same_cells(&dipping));
+        assert!(!straight.same_cost(&dipping));
+        assert!(dipping.same_cost(&Path::new(&grid, 0, vec![5, 6, 3])));
+        assert_eq!(straight.shared_prefix(&Path::new(&grid, 0, vec![1, 6])), 1);
+        assert_eq!(straight.shared_prefix(&Path::new(&grid, 1, vec![2])), 0);
+        assert_eq!(straight.deviation(&straight, 4), 0);
+        assert_eq!(straight.deviation(&dipping, 4), 1);
+        assert_eq!(straight.deviation(&low, 4), 2);
+        assert_eq!(low.deviation(&straight, 4), 2);
+        // cells one above another, without a width to divide by
+        assert_eq!(straight.deviation(&dipping, 0), 2);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::search::{search_in, Scratch};
+use crate::{GridSource, Manhattan, PathBack, SearchOptions, SearchResult};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Searches one after another in the same buffers, which are kept from one
+/// search to the next, as is where the last search came from at each cell
+/// it reached: [`Pathfinder::path_back`] reads paths out of them lazily,
+/// for searches run with [`SearchOptions::lazy_path`] that skip allocating
+/// them.
+#[derive(Default)]
+pub struct Pathfinder {
+    scratch: Scratch,
+}
+
+impl Pathfinder {
+    pub fn new() -> Self {
+        Pathfinder::default()
+    }
+
+    /// [`search`](crate::search) in the pathfinder's buffers.
+    pub fn search<G: GridSource + ?Sized>(
+        &mut self,
+        start: u32,
+        end: u32,
+        grid: &G,
+        options: &SearchOptions,
+    ) -> SearchResult {
+        search_in(&mut self.scratch, start, end, grid, options, &Manhattan, ())
+    }
+
+    /// The path the last search found to `cell`, from `cell` (inclusive)
+    /// back to the start (exclusive), such as from
+    /// [`SearchResult::closest`] or the goal. Empty when that search didn't
+    /// reach `cell`, and before any search.
+    /// [`SearchOptions::reverse_path`] and [`SearchOptions::path_budget`] have
+    /// no say in it: take the steps wanted off the front, or collect and
+    /// reverse them.
+    pub fn path_back(&self, cell: u32) -> PathBack<'_> {
+        self.scratch.path_back(cell)
+    }
+}
+
+impl fmt::Debug for Pathfinder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pathfinder").finish_non_exhaustive()
+    }
+}
+
+/// Searches one shared grid from any number of threads at once, reusing the
+/// buffers of earlier searches instead of allocating new ones each time.
+///
+/// The grid is never locked. Each search takes a set of buffers off a free
+/// list for as long as it runs, so as many searches can run at once as there
+/// are threads calling [`PathfinderPool::search`], and the list grows to that
+/// many sets.
+pub struct PathfinderPool<G> {
+    grid: Arc<G>,
+    scratch: Mutex<Vec<Scratch>>,
+}
+
+impl<G: GridSource> PathfinderPool<G> {
+    pub fn new(grid: Arc<G>) -> Self {
+        PathfinderPool {
+            grid,
+            scratch: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn grid(&self) -> &Arc<G> {
+        &self.grid
+    }
+
+    /// Searches from now on read `grid` instead. Searches already running
+    /// finish on the grid they started with.
+    pub fn set_grid(&mut self, grid: Arc<G>) {
+        self.grid = grid;
+    }
+
+    /// Sets of buffers currently waiting to be reused.
+    pub fn idle(&self) -> usize {
+        self.free_list().len()
+    }
+
+    /// [`search`](crate::search) on the pool's grid.
+    pub fn search(&self, start: u32, end: u32, options: &SearchOptions) -> SearchResult {
+        let mut scratch = self.free_list().pop().unwrap_or_default();
+        let result = search_in(
+            &mut scratch,
+            start,
+            end,
+            &*self.grid,
+            options,
+            &Manhattan,
+            (),
+        );
+        self.free_list().push(scratch);
+        result
+    }
+
+    fn free_list(&self) -> std::sync::MutexGuard<'_, Vec<Scratch>> {
+        // the buffers are cleared before every use, so a search that panicked
+        // while holding the lock can't have left anything behind that matters
+        self.scratch
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<G: fmt::Debug> fmt::Debug for PathfinderPool<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PathfinderPool")
+            .field("grid", &self.grid)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Backend, Grid, Rng};
+    use std::thread;
+
+    #[test]
+    fn paths_are_walked_back_lazily() {
+        let mut grid = Grid::filled(8, 6, 1).unwrap();
+        for y in 0..5 {
+            grid.set(4, y, 0);
+        }
+        grid.set(2, 3, 5);
+        let mut pathfinder = Pathfinder::new();
+        assert_eq!(pathfinder.path_back(3).next(), None);
+        for backend in [Backend::Dense, Backend::Sparse, Backend::Buckets] {
+            let options = SearchOptions {
+                backend,
+                ..SearchOptions::default()
+            };
+            let eager = search(8, 47, &grid, &options);
+            let lazy_options = SearchOptions {
+                lazy_path: true,
+                ..options
+            };
+            let lazy = pathfinder.search(8, 47, &grid, &lazy_options);
+            assert!(lazy.path.is_empty());
+            assert_eq!(lazy.cost, eager.cost);
+            let mut back: Vec<u32> = pathfinder.path_back(47).collect();
+            back.reverse();
+            assert_eq!(back, eager.path);
+            assert_eq!(pathfinder.path_back(47).take(2).count(), 2);
+            assert_eq!(pathfinder.path_back(8).next(), None);
+        }
+    }
+
+    #[test]
+    fn lazy_paths_keep_to_their_budget() {
+        let grid = Grid::filled(9, 1, 2).unwrap();
+        let mut pathfinder = Pathfinder::new();
+        let options = SearchOptions {
+            path_budget: Some(10),
+            lazy_path: true,
+            ..SearchOptions::default()
+        };
+        let lazy = pathfinder.search(0, 8, &grid, &options);
+        let eager = search(
+            0,
+            8,
+            &grid,
+            &SearchOptions {
+                lazy_path: false,
+                ..options
+            },
+        );
+        assert_eq!(lazy.remaining_cost, eager.remaining_cost);
+        assert_eq!(lazy.remaining_cost, Some(15));
+        // the walk still runs from the goal
+        assert_eq!(pathfinder.path_back(8).count(), 8);
+        // out of range searches leave nothing to walk
+        pathfinder.search(0, 9, &grid, &options);
+        assert_eq!(pathfinder.path_back(8).next(), None);
+    }
+
+    #[test]
+    fn it_searches_from_many_threads() {
+        let mut rng = Rng::new(1);
+        let grid = Arc::new(rng.grid(40, 40, 20, 5));
+        let pool = Arc::new(PathfinderPool::new(Arc::clone(&grid)));
+        let queries: Vec<(u32, u32)> = (0..64)
+            .map(|_| (rng.below(grid.len()), rng.below(grid.len())))
+            .collect();
+        let options = SearchOptions::default();
+        let workers: Vec<_> = queries
+            .chunks(16)
+            .map(|chunk| {
+                let pool = Arc::clone(&pool);
+                let chunk = chunk.to_vec();
+                thread::spawn(move || {
+                    chunk
+                        .into_iter()
+                        .map(|(start, end)| pool.search(start, end, &SearchOptions::default()))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let results: Vec<SearchResult> = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect();
+        for (&(start, end), result) in queries.iter().zip(&results) {
+            assert_eq!(*result, search(start, end, &*grid, &options));
+        }
+        assert!((1..=4).contains(&pool.idle()));
+    }
+}
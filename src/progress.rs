@@ -0,0 +1,213 @@
+use crate::search::{search_with, Hooks};
+use crate::{GridSource, Manhattan, SearchOptions, SearchResult, TerminationReason};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+/// A snapshot of a search in progress, sent by [`search_with_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Cells expanded so far.
+    pub expanded: u32,
+    /// The lowest estimated total cost left on the frontier, which only
+    /// grows as the search goes on and bounds the cost of the final path
+    /// from below.
+    pub best_f: u32,
+    /// The reached cell closest to the goal, as in [`SearchResult::closest`].
+    pub closest: u32,
+    /// The cheapest known path from the start (exclusive) to `closest`.
+    pub partial_path: Vec<u32>,
+    /// The [`ProgressOptions::frontier_entries`] cells next to be expanded,
+    /// in the order they will be, starting with the one about to be.
+    pub frontier: Vec<FrontierEntry>,
+}
+
+/// A cell waiting on the frontier of a search, for drawing its wavefront.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrontierEntry {
+    pub cell: u32,
+    /// Cost of the cheapest known route from the start to `cell`.
+    pub g: u32,
+    /// What the frontier orders `cell` by: `g` plus the heuristic estimate
+    /// to the goal, weighted when [`SearchOptions::weighting`] says so.
+    pub f: u32,
+}
+
+/// Where and how often [`search_with_progress`] reports.
+#[derive(Debug, Clone)]
+pub struct ProgressOptions {
+    pub sender: Sender<Progress>,
+    /// Send a report every this many expansions. `0` is treated as `1`.
+    pub interval: u32,
+    /// Setting this flag stops the search with
+    /// [`TerminationReason::Cancelled`] at the next expansion.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// How many frontier cells each report lists in [`Progress::frontier`].
+    /// Listing them means going through the whole frontier, so the default
+    /// is none.
+    pub frontier_entries: usize,
+}
+
+impl ProgressOptions {
+    /// Reports to `sender` every 1000 expansions, without a cancel flag.
+    pub fn new(sender: Sender<Progress>) -> Self {
+        ProgressOptions {
+            sender,
+            interval: 1000,
+            cancel: None,
+            frontier_entries: 0,
+        }
+    }
+}
+
+struct Reporter<'a> {
+    options: &'a ProgressOptions,
+    hung_up: bool,
+}
+
+impl<'a> Hooks for Reporter<'a> {
+    fn interrupt(&mut self, _expanded: u32) -> Option<TerminationReason> {
+        let cancelled = self
+            .options
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+        if cancelled || self.hung_up {
+            Some(TerminationReason::Cancelled)
+        } else {
+            None
+        }
+    }
+
+    fn frontier_entries(&self) -> usize {
+        self.options.frontier_entries
+    }
+
+    fn wants_progress(&mut self, expanded: u32) -> bool {
+        expanded > 0 && expanded.is_multiple_of(self.options.interval.max(1))
+    }
+
+    fn progress(&mut self, progress: Progress) {
+        self.hung_up = self.options.sender.send(progress).is_err();
+    }
+}
+
+/// [`search`](crate::search) that reports its progress over a channel, for
+/// long searches run on a worker thread. The search stops as cancelled when
+/// the `cancel` flag is set or the receiving end of the channel is dropped.
+pub fn search_with_progress<G: GridSource + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    progress: &ProgressOptions,
+) -> SearchResult {
+    let reporter = Reporter {
+        options: progress,
+        hung_up: false,
+    };
+    search_with(start, end, grid, options, &Manhattan, reporter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    #[test]
+    fn it_reports_every_interval() {
+        let grid = Grid::filled(50, 1, 1).unwrap();
+        let (sender, receiver) = channel();
+        let options = ProgressOptions {
+            interval: 10,
+            ..ProgressOptions::new(sender)
+        };
+        let result = search_with_progress(0, 49, &grid, &SearchOptions::default(), &options);
+        drop(options);
+        assert_eq!(result.termination, TerminationReason::GoalReached);
+        let reports: Vec<Progress> = receiver.iter().collect();
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| report.expanded)
+                .collect::<Vec<_>>(),
+            vec![10, 20, 30, 40]
+        );
+        assert_eq!(reports[0].closest, 10);
+        assert_eq!(reports[0].partial_path, (1..=10).collect::<Vec<_>>());
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].best_f <= pair[1].best_f));
+    }
+
+    #[test]
+    fn it_lists_what_comes_off_the_frontier_next() {
+        let grid = Grid::filled(7, 7, 1).unwrap();
+        let (sender, receiver) = channel();
+        let options = ProgressOptions {
+            interval: 1,
+            frontier_entries: 3,
+            ..ProgressOptions::new(sender)
+        };
+        let search_options = SearchOptions {
+            record_trace: true,
+            ..SearchOptions::default()
+        };
+        let result = search_with_progress(0, 48, &grid, &search_options, &options);
+        drop(options);
+        let expansions = result.trace.unwrap().expansions;
+        for report in receiver.iter() {
+            assert!(!report.frontier.is_empty() && report.frontier.len() <= 3);
+            assert!(report
+                .frontier
+                .windows(2)
+                .all(|pair| pair[0].f <= pair[1].f));
+            let next = report.frontier[0];
+            assert_eq!(next.f, report.best_f);
+            let expansion = expansions[report.expanded as usize];
+            assert_eq!((next.cell, next.g), (expansion.cell, expansion.g));
+        }
+    }
+
+    #[test]
+    fn it_can_be_cancelled_from_another_thread() {
+        let grid = Grid::filled(300, 300, 1).unwrap();
+        let (sender, receiver) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let options = ProgressOptions {
+            interval: 100,
+            cancel: Some(Arc::clone(&cancel)),
+            ..ProgressOptions::new(sender)
+        };
+        // walled off, so the search would otherwise flood the whole grid
+        let mut cells = grid.into_cells();
+        cells[299] = 0;
+        cells[598] = 0;
+        cells[599] = 0;
+        let grid = Grid::new(cells, 300).unwrap();
+        let worker = thread::spawn(move || {
+            search_with_progress(0, 299, &grid, &SearchOptions::default(), &options)
+        });
+        receiver.recv().unwrap();
+        cancel.store(true, Ordering::Relaxed);
+        let result = worker.join().unwrap();
+        assert_eq!(result.termination, TerminationReason::Cancelled);
+        assert!(result.path.is_empty());
+    }
+
+    #[test]
+    fn dropping_the_receiver_stops_the_search() {
+        let grid = Grid::filled(100, 100, 1).unwrap();
+        let (sender, receiver) = channel();
+        drop(receiver);
+        let options = ProgressOptions {
+            interval: 5,
+            ..ProgressOptions::new(sender)
+        };
+        let result = search_with_progress(0, 9999, &grid, &SearchOptions::default(), &options);
+        assert_eq!(result.termination, TerminationReason::Cancelled);
+        assert_eq!(result.stats.expanded, 5);
+    }
+}
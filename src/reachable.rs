@@ -0,0 +1,90 @@
+use crate::{get_neighbor_coords, step_cost, FrontierItem, GridSource};
+use fxhash::FxHashMap;
+use std::collections::BinaryHeap;
+
+/// Every cell a unit at `start` can reach spending at most `budget`, with
+/// what the cheapest way there costs, such as for highlighting the movement
+/// range of a unit in a tactics game. Cells come cheapest first, ties in
+/// the order searches break them, beginning with `start` itself at `0`.
+/// Empty when `start` lies outside the grid.
+///
+/// This is a Dijkstra flood cut at the budget, and only keeps the cells it
+/// reaches, so a small range on a huge map costs no more than the range.
+/// As with the start of any search, `start` is left from even when it is a
+/// wall.
+pub fn reachable_within<G: GridSource + ?Sized>(
+    start: u32,
+    budget: u32,
+    grid: &G,
+    cardinal_directions: bool,
+) -> Vec<(u32, u32)> {
+    let mut reachable = Vec::new();
+    if start >= grid.len() {
+        return reachable;
+    }
+    let mut distances = FxHashMap::default();
+    let mut frontier = BinaryHeap::new();
+    distances.insert(start, 0);
+    frontier.push(FrontierItem {
+        cost: 0,
+        position: start,
+    });
+    while let Some(FrontierItem { cost, position }) = frontier.pop() {
+        if distances.get(&position).is_some_and(|&known| cost > known) {
+            continue;
+        }
+        reachable.push((position, cost));
+        for &neighbor in get_neighbor_coords(position, grid, cardinal_directions).iter() {
+            let next = cost.saturating_add(step_cost(grid, position, neighbor));
+            if next <= budget && distances.get(&neighbor).is_none_or(|&known| next < known) {
+                distances.insert(neighbor, next);
+                frontier.push(FrontierItem {
+                    cost: next,
+                    position: neighbor,
+                });
+            }
+        }
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid, DijkstraMap, Rng};
+
+    #[test]
+    fn it_floods_up_to_the_budget() {
+        let grid = grid![
+            1, 1, 1, 1;
+            1, 0, 3, 1;
+            1, 1, 1, 1;
+        ];
+        let reachable = reachable_within(0, 4, &grid, true);
+        assert_eq!(reachable, vec![(0, 0), (4, 2), (1, 2), (8, 4), (2, 4)]);
+        assert_eq!(reachable_within(0, 1, &grid, true), vec![(0, 0)]);
+        assert_eq!(reachable_within(12, 9, &grid, true), Vec::new());
+    }
+
+    #[test]
+    fn it_agrees_with_a_full_flood() {
+        let mut rng = Rng::new(1);
+        for _ in 0..50 {
+            let (width, height) = (rng.range(1, 16), rng.range(1, 16));
+            let grid = rng.grid(width, height, 25, 4);
+            let start = rng.below(grid.len());
+            let budget = rng.range(0, 30);
+            let cardinal = rng.chance(1, 2);
+            let map = DijkstraMap::new(&grid, &[start], cardinal);
+            let mut expected: Vec<(u32, u32)> = (0..grid.len())
+                .filter_map(|cell| map.distance(cell).map(|cost| (cell, cost)))
+                .filter(|&(_, cost)| cost <= budget)
+                .collect();
+            let mut found = reachable_within(start, budget, &grid, cardinal);
+            assert!(found.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+            found.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(found, expected);
+        }
+    }
+}
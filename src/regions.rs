@@ -0,0 +1,305 @@
+use crate::artifact::{write_u32s, Reader};
+use crate::{get_neighbor_coords, Artifact, DecodeError, GridSource, WALL};
+use smallvec::SmallVec;
+use std::collections::VecDeque;
+
+/// Marks a wall, which belongs to no region.
+const NO_REGION: u32 = u32::MAX;
+
+/// Which walkable cells can reach each other. Each region is a group of
+/// cells connected under one movement mode, with a number of its own.
+///
+/// Freshly built regions are numbered from `0` in the order their first
+/// cells come in row-major order. [`Regions::update`] then keeps the labels
+/// current one cell at a time: opening a cell merges the regions around it
+/// in a union-find, and closing one only floods the map again when the
+/// cells around it don't still connect among themselves. Numbers may stop
+/// being consecutive after updates, but each region keeps one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Regions {
+    cardinal_directions: bool,
+    /// The label each cell was given, which names its region through
+    /// `parents`.
+    labels: Vec<u32>,
+    /// The union-find forest over labels.
+    parents: Vec<u32>,
+    /// Cells per region, kept for labels that are roots of `parents`.
+    sizes: Vec<u32>,
+    count: u32,
+}
+
+impl Regions {
+    /// Labels every walkable cell of `grid` with a flood fill from each
+    /// cell not yet labelled.
+    pub fn new<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> Self {
+        let mut labels = vec![NO_REGION; grid.len() as usize];
+        let mut sizes = Vec::new();
+        let mut queue = VecDeque::new();
+        for cell in 0..grid.len() {
+            if labels[cell as usize] != NO_REGION || grid.cost(cell) == WALL {
+                continue;
+            }
+            let region = sizes.len() as u32;
+            let mut size = 0;
+            labels[cell as usize] = region;
+            queue.push_back(cell);
+            while let Some(current) = queue.pop_front() {
+                size += 1;
+                for &neighbor in get_neighbor_coords(current, grid, cardinal_directions).iter() {
+                    if labels[neighbor as usize] == NO_REGION {
+                        labels[neighbor as usize] = region;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        Regions {
+            cardinal_directions,
+            labels,
+            parents: (0..sizes.len() as u32).collect(),
+            count: sizes.len() as u32,
+            sizes,
+        }
+    }
+
+    pub fn cardinal_directions(&self) -> bool {
+        self.cardinal_directions
+    }
+
+    /// How many regions there are.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// The region `cell` belongs to, or `None` for walls and cells outside
+    /// the grid.
+    pub fn region(&self, cell: u32) -> Option<u32> {
+        match self.labels.get(cell as usize) {
+            Some(&label) if label != NO_REGION => Some(self.root(label)),
+            _ => None,
+        }
+    }
+
+    /// How many cells `region` has, `0` for one that doesn't exist.
+    pub fn size(&self, region: u32) -> u32 {
+        match self.parents.get(region as usize) {
+            Some(&parent) if parent == region => self.sizes[region as usize],
+            _ => 0,
+        }
+    }
+
+    /// Whether a path leads from `from` to `to`.
+    pub fn connected(&self, from: u32, to: u32) -> bool {
+        self.region(from).is_some() && self.region(from) == self.region(to)
+    }
+
+    /// The cells of `region`, in row-major order.
+    pub fn cells(&self, region: u32) -> impl Iterator<Item = u32> + '_ {
+        (0..self.labels.len() as u32).filter(move |&cell| self.region(cell) == Some(region))
+    }
+
+    /// Catches up with a change to the cost of `cell` in `grid`, which must
+    /// otherwise be the grid the regions were built from. Changes between
+    /// walkable costs change nothing.
+    pub fn update<G: GridSource + ?Sized>(&mut self, grid: &G, cell: u32) {
+        let was_walkable = match self.labels.get(cell as usize) {
+            Some(&label) => label != NO_REGION,
+            None => return,
+        };
+        let walkable = grid.cost(cell) != WALL;
+        if walkable && !was_walkable {
+            self.open(grid, cell);
+        } else if was_walkable && !walkable {
+            self.close(grid, cell);
+        }
+    }
+
+    /// Starts over from `grid`.
+    pub fn rebuild<G: GridSource + ?Sized>(&mut self, grid: &G) {
+        *self = Regions::new(grid, self.cardinal_directions);
+    }
+
+    fn root(&self, mut label: u32) -> u32 {
+        while self.parents[label as usize] != label {
+            label = self.parents[label as usize];
+        }
+        label
+    }
+
+    fn open<G: GridSource + ?Sized>(&mut self, grid: &G, cell: u32) {
+        let mut region = None;
+        for &neighbor in get_neighbor_coords(cell, grid, self.cardinal_directions).iter() {
+            let other = self.root(self.labels[neighbor as usize]);
+            region = Some(match region {
+                Some(region) if region != other => self.union(region, other),
+                _ => other,
+            });
+        }
+        let region = region.unwrap_or_else(|| {
+            self.parents.push(self.parents.len() as u32);
+            self.sizes.push(0);
+            self.count += 1;
+            self.parents.len() as u32 - 1
+        });
+        self.labels[cell as usize] = region;
+        self.sizes[region as usize] += 1;
+    }
+
+    /// Joins two regions under the bigger one, which keeps the trees
+    /// shallow, and returns the region they now form.
+    fn union(&mut self, a: u32, b: u32) -> u32 {
+        let (big, small) = if self.sizes[a as usize] >= self.sizes[b as usize] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[small as usize] = big;
+        self.sizes[big as usize] += self.sizes[small as usize];
+        self.count -= 1;
+        big
+    }
+
+    fn close<G: GridSource + ?Sized>(&mut self, grid: &G, cell: u32) {
+        let region = self.root(self.labels[cell as usize]);
+        self.labels[cell as usize] = NO_REGION;
+        self.sizes[region as usize] -= 1;
+        if self.sizes[region as usize] == 0 {
+            self.count -= 1;
+        } else if !self.still_joined_around(grid, cell) {
+            self.rebuild(grid);
+        }
+    }
+
+    /// Whether the walkable neighbors of `cell` connect to each other
+    /// without leaving the cells around it, so that walling `cell` off
+    /// can't have split its region. Steps between other cells never depend
+    /// on `cell`, so any route that went through it can go around instead.
+    fn still_joined_around<G: GridSource + ?Sized>(&self, grid: &G, cell: u32) -> bool {
+        let width = grid.width() as i64;
+        let (x, y) = ((cell as i64 % width), (cell as i64 / width));
+        let around = |other: u32| {
+            let (ox, oy) = (other as i64 % width, other as i64 / width);
+            other != cell && (ox - x).abs() <= 1 && (oy - y).abs() <= 1
+        };
+        let neighbors = get_neighbor_coords(cell, grid, self.cardinal_directions);
+        let mut reached: SmallVec<[u32; 8]> = SmallVec::new();
+        let mut queue: SmallVec<[u32; 8]> = neighbors.iter().take(1).copied().collect();
+        while let Some(current) = queue.pop() {
+            if reached.contains(&current) {
+                continue;
+            }
+            reached.push(current);
+            for &next in get_neighbor_coords(current, grid, self.cardinal_directions).iter() {
+                if around(next) && !reached.contains(&next) {
+                    queue.push(next);
+                }
+            }
+        }
+        neighbors.iter().all(|neighbor| reached.contains(neighbor))
+    }
+}
+
+impl Artifact for Regions {
+    const KIND: [u8; 3] = *b"rgn";
+    const VERSION: u8 = 1;
+
+    fn write_payload(&self, bytes: &mut Vec<u8>) {
+        bytes.push(self.cardinal_directions as u8);
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        write_u32s(bytes, &self.labels);
+        write_u32s(bytes, &self.parents);
+        write_u32s(bytes, &self.sizes);
+    }
+
+    fn read_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(payload);
+        let cardinal_directions = reader.bool()?;
+        let count = reader.u32()?;
+        let labels = reader.any_u32s()?;
+        let parents = reader.any_u32s()?;
+        let sizes = reader.u32s(parents.len())?;
+        reader.finish()?;
+        let labelled = |label: u32| (label as usize) < parents.len();
+        // every label leads up to a root, without going round in circles
+        let rooted = |mut label: u32| {
+            (0..=parents.len()).any(|_| {
+                let parent = parents[label as usize];
+                let root = parent == label;
+                label = parent;
+                root
+            })
+        };
+        let valid = labels
+            .iter()
+            .all(|&label| label == NO_REGION || labelled(label))
+            && parents.iter().all(|&parent| labelled(parent))
+            && (0..parents.len() as u32).all(rooted);
+        if !valid {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(Regions {
+            cardinal_directions,
+            labels,
+            parents,
+            sizes,
+            count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid, Rng};
+
+    #[test]
+    fn it_labels_connected_cells() {
+        let grid = grid![
+            1, 0, 1;
+            1, 0, 1;
+            0, 1, 0;
+        ];
+        let cardinal = Regions::new(&grid, true);
+        assert_eq!(cardinal.count(), 3);
+        let labels: Vec<i64> = (0..9)
+            .map(|cell| cardinal.region(cell).map_or(-1, i64::from))
+            .collect();
+        assert_eq!(labels, vec![0, -1, 1, 0, -1, 1, -1, 2, -1]);
+        assert_eq!((cardinal.size(1), cardinal.size(3)), (2, 0));
+        assert_eq!(cardinal.cells(1).collect::<Vec<_>>(), vec![2, 5]);
+        assert!(!cardinal.connected(0, 7));
+        let diagonal = Regions::new(&grid, false);
+        assert_eq!(diagonal.count(), 1);
+        assert!(diagonal.connected(0, 7));
+        assert!(!diagonal.connected(1, 1));
+        assert_eq!(diagonal.region(9), None);
+    }
+
+    #[test]
+    fn updates_match_a_fresh_flood() {
+        let mut rng = Rng::new(1);
+        for &cardinal in &[false, true] {
+            let mut grid = rng.grid(16, 12, 33, 1);
+            let mut regions = Regions::new(&grid, cardinal);
+            for _ in 0..300 {
+                let (x, y) = (rng.range(0, 16), rng.range(0, 12));
+                grid.set(x, y, if rng.chance(1, 2) { 0 } else { rng.range(1, 4) });
+                regions.update(&grid, y * 16 + x);
+                let fresh = Regions::new(&grid, cardinal);
+                assert_eq!(regions.count(), fresh.count());
+                for cell in 0..grid.len() {
+                    let region = regions.region(cell);
+                    assert_eq!(region.is_some(), fresh.region(cell).is_some());
+                    if let Some(region) = region {
+                        let fresh_region = fresh.region(cell).unwrap();
+                        assert_eq!(regions.size(region), fresh.size(fresh_region));
+                        let other = rng.range(0, grid.len());
+                        assert_eq!(regions.connected(cell, other), fresh.connected(cell, other));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+/// A small seedable pseudo random number generator (SplitMix64).
+///
+/// It only uses integer arithmetic, so the sequence produced from a seed is
+/// the same on every platform. Everything in this crate that takes a seed
+/// uses it, which keeps generated maps and randomized queries reproducible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// A number in `0..bound`. Returns `0` when `bound` is `0`.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        ((self.next_u32() as u64 * bound as u64) >> 32) as u32
+    }
+
+    /// A number in `low..high`. Returns `low` when the range is empty.
+    pub fn range(&mut self, low: u32, high: u32) -> u32 {
+        low + self.below(high.saturating_sub(low))
+    }
+
+    /// `true` with a probability of `numerator / denominator`.
+    pub fn chance(&mut self, numerator: u32, denominator: u32) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+impl Rng {
+    /// A `width` by `height` grid whose cells are walls `walls` times in a
+    /// hundred, and otherwise cost from 1 to `max_cost`.
+    pub(crate) fn grid(
+        &mut self,
+        width: u32,
+        height: u32,
+        walls: u32,
+        max_cost: u32,
+    ) -> crate::Grid {
+        let cells: Vec<u32> = (0..width * height)
+            .map(|_| {
+                if self.chance(walls, 100) {
+                    0
+                } else {
+                    self.range(1, max_cost + 1)
+                }
+            })
+            .collect();
+        crate::Grid::new(cells, width).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_is_reproducible() {
+        let mut rng = Rng::new(7);
+        let first: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        let mut rng = Rng::new(7);
+        let second: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(first, second);
+        assert_eq!(Rng::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+    }
+
+    #[test]
+    fn it_stays_in_range() {
+        let mut rng = Rng::new(1);
+        for _ in 0..1000 {
+            assert!(rng.below(10) < 10);
+            let value = rng.range(5, 8);
+            assert!((5..8).contains(&value));
+        }
+        assert_eq!(rng.below(0), 0);
+        assert_eq!(rng.range(4, 4), 4);
+    }
+}
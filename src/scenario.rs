@@ -0,0 +1,295 @@
+//! Query suites that can be shared as files, enabled by the `scenario`
+//! feature. A scenario is a JSON document naming a map and the queries to
+//! run on it, along with the cost each should come to:
+//!
+//! ```text
+//! {
+//!     "map": "corridors.map",
+//!     "cardinal_directions": false,
+//!     "queries": [
+//!         { "start": [0, 0], "goal": [9, 4], "cost": 17 },
+//!         { "start": [0, 0], "goal": [5, 2], "cost": null }
+//!     ]
+//! }
+//! ```
+//!
+//! The map path is relative to the scenario file, and the map itself uses
+//! the ASCII format of the golden tests: `.` is floor, `#` is a wall and
+//! `1` to `9` are floor with that cost (`S` and `G` are read as floor). A
+//! `null` cost expects the goal to be unreachable. The test suite runs the
+//! scenarios under `tests/fixtures`, and `simple_astar_scenario` runs any
+//! others, so a slow or wrong query can be attached to a bug report as is.
+
+use crate::{search, Grid, GridError, GridSource, SearchOptions};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scenario {
+    /// Where the map is, relative to the scenario file.
+    pub map: String,
+    #[serde(default)]
+    pub cardinal_directions: bool,
+    pub queries: Vec<ScenarioQuery>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScenarioQuery {
+    /// `[x, y]` of the start.
+    pub start: [u32; 2],
+    /// `[x, y]` of the goal.
+    pub goal: [u32; 2],
+    /// What the cheapest path costs, or `None` when there is none.
+    pub cost: Option<u32>,
+}
+
+/// A query of a scenario whose search didn't cost what was expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The position of the query in [`Scenario::queries`].
+    pub query: usize,
+    pub expected: Option<u32>,
+    pub found: Option<u32>,
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    /// The scenario or its map couldn't be read.
+    Io(io::Error),
+    /// The scenario isn't valid JSON of the right shape.
+    Json(serde_json::Error),
+    /// A map cell isn't one of the known symbols. Rows and columns count
+    /// from 1.
+    UnknownSymbol {
+        row: usize,
+        column: usize,
+        symbol: char,
+    },
+    /// A map row doesn't have as many cells as the first.
+    UnevenRow {
+        row: usize,
+        len: usize,
+        width: usize,
+    },
+    Grid(GridError),
+    /// A query starts or ends outside the map.
+    OutOfBounds {
+        query: usize,
+    },
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(error) => write!(f, "couldn't read scenario: {}", error),
+            ScenarioError::Json(error) => write!(f, "invalid scenario: {}", error),
+            ScenarioError::UnknownSymbol {
+                row,
+                column,
+                symbol,
+            } => write!(
+                f,
+                "unknown map symbol {:?} at row {}, column {}",
+                symbol, row, column
+            ),
+            ScenarioError::UnevenRow { row, len, width } => {
+                write!(f, "map row {} has {} cells instead of {}", row, len, width)
+            }
+            ScenarioError::Grid(error) => error.fmt(f),
+            ScenarioError::OutOfBounds { query } => {
+                write!(f, "query {} lies outside the map", query)
+            }
+        }
+    }
+}
+
+impl Error for ScenarioError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ScenarioError::Io(error) => Some(error),
+            ScenarioError::Json(error) => Some(error),
+            ScenarioError::Grid(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ScenarioError {
+    fn from(error: io::Error) -> Self {
+        ScenarioError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ScenarioError {
+    fn from(error: serde_json::Error) -> Self {
+        ScenarioError::Json(error)
+    }
+}
+
+impl Scenario {
+    pub fn from_json(source: &str) -> Result<Self, ScenarioError> {
+        Ok(serde_json::from_str(source)?)
+    }
+
+    /// Writes the scenario out with one query per line, which keeps diffs
+    /// of recorded costs readable.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\n");
+        out += &format!(
+            "  \"map\": {},\n",
+            serde_json::to_string(&self.map).expect("strings always serialize")
+        );
+        out += &format!("  \"cardinal_directions\": {},\n", self.cardinal_directions);
+        out += "  \"queries\": [";
+        for (index, query) in self.queries.iter().enumerate() {
+            out += if index == 0 { "\n    " } else { ",\n    " };
+            out += &serde_json::to_string(query).expect("queries always serialize");
+        }
+        out += if self.queries.is_empty() {
+            "]\n}"
+        } else {
+            "\n  ]\n}"
+        };
+        out
+    }
+
+    /// Reads the scenario at `path` and the map it names.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<(Self, Grid), ScenarioError> {
+        let path = path.as_ref();
+        let scenario = Scenario::from_json(&fs::read_to_string(path)?)?;
+        let map_path = path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(&scenario.map);
+        let grid = grid_from_ascii(&fs::read_to_string(map_path)?)?;
+        Ok((scenario, grid))
+    }
+
+    /// Runs every query on `grid` and returns those that didn't cost what
+    /// they should, in order.
+    pub fn run<G: GridSource + ?Sized>(&self, grid: &G) -> Result<Vec<Mismatch>, ScenarioError> {
+        let options = SearchOptions {
+            cardinal_directions: self.cardinal_directions,
+            ..SearchOptions::default()
+        };
+        let mut mismatches = Vec::new();
+        for (index, query) in self.queries.iter().enumerate() {
+            let (start, goal) =
+                query_cells(query, grid).ok_or(ScenarioError::OutOfBounds { query: index })?;
+            let found = search(start, goal, grid, &options).cost;
+            if found != query.cost {
+                mismatches.push(Mismatch {
+                    query: index,
+                    expected: query.cost,
+                    found,
+                });
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Sets every expected cost to what searching `grid` finds, for writing
+    /// new scenarios or accepting an intended change.
+    pub fn record<G: GridSource + ?Sized>(&mut self, grid: &G) -> Result<(), ScenarioError> {
+        for mismatch in self.run(grid)? {
+            self.queries[mismatch.query].cost = mismatch.found;
+        }
+        Ok(())
+    }
+}
+
+fn query_cells<G: GridSource + ?Sized>(query: &ScenarioQuery, grid: &G) -> Option<(u32, u32)> {
+    let index = |[x, y]: [u32; 2]| {
+        if x < grid.width() && y < grid.height() {
+            Some(y * grid.width() + x)
+        } else {
+            None
+        }
+    };
+    Some((index(query.start)?, index(query.goal)?))
+}
+
+/// Reads a map in the ASCII format described in the [module
+/// docs](self). Blank lines are skipped.
+pub fn grid_from_ascii(source: &str) -> Result<Grid, ScenarioError> {
+    let rows: Vec<(usize, &str)> = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .collect();
+    let width = rows.first().map_or(0, |(_, row)| row.chars().count());
+    let mut cells = Vec::with_capacity(width * rows.len());
+    for &(line, row) in &rows {
+        let len = row.chars().count();
+        if len != width {
+            return Err(ScenarioError::UnevenRow {
+                row: line + 1,
+                len,
+                width,
+            });
+        }
+        for (column, symbol) in row.chars().enumerate() {
+            cells.push(match symbol {
+                '#' => 0,
+                '.' | 'S' | 'G' => 1,
+                '1'..='9' => symbol as u32 - '0' as u32,
+                _ => {
+                    return Err(ScenarioError::UnknownSymbol {
+                        row: line + 1,
+                        column: column + 1,
+                        symbol,
+                    })
+                }
+            });
+        }
+    }
+    Grid::new(cells, width as u32).map_err(ScenarioError::Grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_checks_and_records_costs() {
+        let grid = grid_from_ascii("S.#\n.2#\n..G\n").unwrap();
+        let source = r#"{
+            "map": "unused.map",
+            "cardinal_directions": true,
+            "queries": [
+                { "start": [0, 0], "goal": [2, 2], "cost": 12 },
+                { "start": [0, 0], "goal": [2, 0], "cost": null },
+                { "start": [1, 0], "goal": [1, 1], "cost": 3 }
+            ]
+        }"#;
+        let mut scenario = Scenario::from_json(source).unwrap();
+        assert_eq!(
+            scenario.run(&grid).unwrap(),
+            vec![Mismatch {
+                query: 0,
+                expected: Some(12),
+                found: Some(8),
+            }]
+        );
+        scenario.record(&grid).unwrap();
+        assert_eq!(scenario.queries[0].cost, Some(8));
+        assert_eq!(Scenario::from_json(&scenario.to_json()).unwrap(), scenario);
+        scenario.queries[1].goal = [3, 0];
+        assert!(matches!(
+            scenario.run(&grid),
+            Err(ScenarioError::OutOfBounds { query: 1 })
+        ));
+        assert!(matches!(
+            grid_from_ascii("..\n.x\n"),
+            Err(ScenarioError::UnknownSymbol {
+                row: 2,
+                column: 2,
+                symbol: 'x'
+            })
+        ));
+    }
+}
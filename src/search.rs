@@ -0,0 +1,2119 @@
+use crate::{
+    canonical, direction, get_neighbor_coords, step_cost, FrontierEntry, FrontierItem, GridSource,
+    Heuristic, Manhattan, NeighborBuffer, Progress, Rng, SearchState, UnknownCells,
+};
+use fxhash::FxHashMap;
+use smallvec::SmallVec;
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::time::{Duration, Instant};
+
+/// Options for [`search`]. The defaults match [`crate::astar_grid`] with
+/// diagonal movement.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Only move up, down, left and right.
+    pub cardinal_directions: bool,
+    /// Record every expansion into [`SearchResult::trace`].
+    pub record_trace: bool,
+    /// Measure where the search spends its time into
+    /// [`SearchStats::timings`]. Reading the clock between every phase slows
+    /// the search down, so this is for profiling slow queries rather than
+    /// for leaving on.
+    pub time_phases: bool,
+    /// Give up after expanding this many cells. Being a count rather than a
+    /// duration, the limit stops a search at the same point on every machine,
+    /// which lockstep simulations rely on.
+    pub max_expansions: Option<u32>,
+    /// Give up once every route left to try would cost more than this.
+    pub max_cost: Option<u32>,
+    /// Only consider paths of at most this many steps, such as targets
+    /// within 30 tiles. A goal further than that as the crow flies is given
+    /// up on before anything is expanded, and cells that couldn't reach the
+    /// goal in the steps left aren't pushed, so distant candidates cost
+    /// next to nothing to rule out. Cells are still reached by their
+    /// cheapest route, which is cut when it takes too many steps: a costlier
+    /// route with fewer steps to the same cell isn't tried, so the path
+    /// found may cost more than the cheapest within the limit, or be missed
+    /// on maps where every cheap route winds.
+    pub max_steps: Option<u32>,
+    /// Charge the cost of the start cell on top of the cells the path
+    /// enters, for rules that make a unit pay for the tile it stands on.
+    /// Off by default, so the start is free and a path costs what stepping
+    /// into each of its cells does. The charge counts toward
+    /// [`SearchResult::cost`] and against [`SearchOptions::max_cost`] and
+    /// [`SearchOptions::path_budget`], but isn't a step of the path, so a
+    /// [`crate::Path`] built from it leaves it out.
+    pub pay_for_start: bool,
+    /// When the goal isn't reached, return the path toward
+    /// [`SearchResult::closest`] instead of an empty one.
+    pub partial_path: bool,
+    /// Only return as much of the path as costs at most this much, such as
+    /// the movement points a unit has left this turn. The search itself still
+    /// runs to the goal; see [`SearchResult::remaining_cost`].
+    pub path_budget: Option<u32>,
+    /// Return [`SearchResult::path`] from the goal (inclusive) back to the
+    /// start (exclusive), the order it is walked out of the search, which
+    /// saves reversing it when it is going to be played backwards anyway.
+    pub reverse_path: bool,
+    /// Leave [`SearchResult::path`] empty instead of walking it out of the
+    /// search, for callers that only need its first few steps or keep paths
+    /// in buffers of their own: a [`crate::Pathfinder`] walks it lazily
+    /// afterwards with [`crate::Pathfinder::path_back`], goal first, without
+    /// allocating. The cost and [`SearchResult::remaining_cost`] come out the
+    /// same as without it.
+    pub lazy_path: bool,
+    /// Break ties between equally promising cells in an order shuffled by
+    /// this seed rather than by position, so that units given different
+    /// seeds spread out over the routes that cost the same instead of all
+    /// walking the one. Paths cost no more for it, and the same seed always
+    /// gives the same path.
+    pub jitter: Option<u64>,
+    /// How much to lean on the heuristic, trading path cost for speed.
+    pub weighting: Weighting,
+    /// How many cells to make room for up front. By default this grows with
+    /// the distance between the start and the goal rather than the size of
+    /// the grid, so that short searches on big maps allocate little.
+    pub initial_capacity: Option<usize>,
+    /// Where to keep what the search learns about each cell.
+    pub backend: Backend,
+    /// Skip neighbors that a canonical ordering of steps, diagonal ones
+    /// first, can reach as cheaply without passing the cell being expanded,
+    /// as jump point search does without the jumping. Of the many equally
+    /// cheap paths across open ground, this keeps the search to one, which
+    /// pushes far fewer cells on uniform-cost maps. Paths cost the same as
+    /// without it: cells are only pruned where everything around them costs
+    /// the same, so weighted maps fall back to trying every neighbor and
+    /// gain less the patchier they are. Costs added on top of the grid, as
+    /// [`crate::search_with_edge_costs`] does, aren't seen by the pruning
+    /// and can make paths costlier. Only diagonal movement is pruned; with
+    /// [`SearchOptions::cardinal_directions`] this does nothing.
+    pub canonical_ordering: bool,
+    /// Whether [`crate::UNKNOWN`] cells may be entered. Costs stay as they
+    /// are everywhere else, so the heuristic keeps holding whichever way
+    /// they are read.
+    pub unknown_cells: UnknownCells,
+}
+
+impl SearchOptions {
+    /// Whether searches keep how many steps each cell is from the start.
+    fn counts_steps(&self) -> bool {
+        self.weighting != Weighting::None || self.max_steps.is_some()
+    }
+}
+
+/// How a search stores its per-cell state and its frontier. Every backend
+/// finds the same paths: they all take cells off the frontier in the same
+/// order, ties included, which is part of the determinism guarantee, and
+/// differ only in speed and memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backend {
+    /// Dense arrays on grids of up to about a million cells, unless the
+    /// search looks short next to the size of the grid; hash maps otherwise.
+    #[default]
+    Auto,
+    /// Arrays with a slot for every cell: the fastest to read and write, at
+    /// eight bytes per cell and map, kept between searches that reuse them.
+    Dense,
+    /// Hash maps holding only the cells reached, for huge or sparse maps.
+    Sparse,
+    /// [`Backend::Dense`] arrays, with the frontier in a bucket per
+    /// priority instead of a heap, which is faster when step costs are
+    /// small integers and priorities stay low. Entries costing `65536` or
+    /// more spill into a heap of their own. [`Backend::Auto`] doesn't pick
+    /// this: whether it pays off depends on the costs and the heuristic,
+    /// which the search can't tell up front.
+    Buckets,
+}
+
+/// How the heuristic is weighted against the cost so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Weighting {
+    /// Plain A*, which finds a cheapest path.
+    #[default]
+    None,
+    /// Inflates the heuristic by `epsilon_percent` percent at the start, and
+    /// less the more steps a cell is from the start, down to none once the
+    /// steps match the heuristic's estimate from the start to the goal. This
+    /// expands fewer cells early on, yet returns paths that cost at most
+    /// `100 + epsilon_percent` percent of the cheapest.
+    Dynamic { epsilon_percent: u32 },
+}
+
+impl Weighting {
+    /// The most a path found this way can cost, in percent of the cheapest.
+    pub fn bound_percent(&self) -> u32 {
+        match self {
+            Weighting::None => 100,
+            Weighting::Dynamic { epsilon_percent } => 100u32.saturating_add(*epsilon_percent),
+        }
+    }
+}
+
+/// Why a search stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TerminationReason {
+    GoalReached,
+    /// Every cell reachable from the start was expanded without finding the goal.
+    Exhausted,
+    /// [`SearchOptions::max_expansions`] was hit first.
+    ExpansionLimit,
+    /// No path within [`SearchOptions::max_steps`] was found, though one
+    /// with more steps might have been.
+    StepLimit,
+    /// Reaching the goal would cost more than [`SearchOptions::max_cost`].
+    CostLimit,
+    /// The caller asked the search to stop, or stopped listening to it.
+    Cancelled,
+    /// The deadline given to [`crate::astar_with_deadline`] passed first.
+    DeadlineReached,
+    /// The condition given to [`crate::search_until`] asked to stop, and
+    /// the path leads to the cell it was shown.
+    Stopped,
+    /// The search caught itself in an inconsistent state and stopped rather
+    /// than panic.
+    Internal(InternalError),
+}
+
+/// A broken invariant that a search ran into, reported through
+/// [`TerminationReason::Internal`] so that a path query can never bring
+/// down the program asking it. Either the crate has a bug or a
+/// [`GridSource`] contradicts itself, such as by changing size while it is
+/// searched; the search that reports one returns no path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InternalError {
+    /// A cell came off the frontier without a cost recorded for it.
+    UnreachedCell { cell: u32 },
+    /// Walking parents back from `cell` didn't lead to the start.
+    BrokenPath { cell: u32 },
+    /// A neighbor of `cell` lies outside the grid.
+    NeighborOutOfBounds { cell: u32, neighbor: u32 },
+}
+
+impl fmt::Display for InternalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InternalError::UnreachedCell { cell } => {
+                write!(f, "cell {} was expanded before it was reached", cell)
+            }
+            InternalError::BrokenPath { cell } => {
+                write!(
+                    f,
+                    "the path back from cell {} doesn't reach the start",
+                    cell
+                )
+            }
+            InternalError::NeighborOutOfBounds { cell, neighbor } => write!(
+                f,
+                "cell {} has neighbor {} outside the grid",
+                cell, neighbor
+            ),
+        }
+    }
+}
+
+impl Error for InternalError {}
+
+impl From<InternalError> for TerminationReason {
+    fn from(error: InternalError) -> Self {
+        TerminationReason::Internal(error)
+    }
+}
+
+impl TerminationReason {
+    /// A short `snake_case` name, for logs and metric labels.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TerminationReason::GoalReached => "goal_reached",
+            TerminationReason::Exhausted => "exhausted",
+            TerminationReason::ExpansionLimit => "expansion_limit",
+            TerminationReason::StepLimit => "step_limit",
+            TerminationReason::CostLimit => "cost_limit",
+            TerminationReason::Cancelled => "cancelled",
+            TerminationReason::DeadlineReached => "deadline_reached",
+            TerminationReason::Stopped => "stopped",
+            TerminationReason::Internal(_) => "internal_error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchStats {
+    /// Cells taken off the frontier and expanded. The goal doesn't count.
+    pub expanded: u32,
+    /// Cells a route from the start was found to, expanded or not.
+    pub reached: u32,
+    /// The most entries the frontier held at once, counting those a cheaper
+    /// route later made stale.
+    pub peak_frontier: u32,
+    /// Entries in the per-cell maps of costs, parents and, for
+    /// [`Weighting::Dynamic`], steps. Nothing is removed from them during a
+    /// search, so this is also their peak.
+    pub stored: u32,
+    /// The bytes the frontier and the per-cell maps needed at their peak,
+    /// going by `peak_frontier` and `stored`, so that it comes out the same
+    /// on every backend and however buffers were reused. Backends take more
+    /// on top: hash maps keep spare room, and [`Backend::Dense`] keeps a slot
+    /// for every cell of the grid.
+    pub bytes: usize,
+    /// Present when [`SearchOptions::time_phases`] was set.
+    pub timings: Option<PhaseTimings>,
+}
+
+/// How long a search spent on each part of its work. The phases take turns
+/// on one clock, so together they cover the whole search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseTimings {
+    /// Pushing onto and popping off the frontier, including entries popped
+    /// after a cheaper route made them stale.
+    pub heap: Duration,
+    /// Listing the walkable neighbors of expanded cells, with their step
+    /// costs and heuristic estimates.
+    pub neighbors: Duration,
+    /// Looking up and recording costs and parents, and checking limits.
+    pub bookkeeping: Duration,
+    /// Walking the path back from where the search ended.
+    pub reconstruction: Duration,
+}
+
+impl PhaseTimings {
+    pub fn total(&self) -> Duration {
+        self.heap + self.neighbors + self.bookkeeping + self.reconstruction
+    }
+}
+
+/// Charges the time since it was last read to one phase at a time, or does
+/// nothing when phases aren't being timed.
+struct Stopwatch {
+    last: Option<Instant>,
+    timings: PhaseTimings,
+}
+
+impl Stopwatch {
+    fn new(enabled: bool) -> Self {
+        Stopwatch {
+            last: if enabled { Some(Instant::now()) } else { None },
+            timings: PhaseTimings::default(),
+        }
+    }
+
+    /// Adds the time since the last lap to the phase `phase` picks out.
+    #[inline(always)]
+    fn lap(&mut self, phase: fn(&mut PhaseTimings) -> &mut Duration) {
+        if let Some(last) = self.last {
+            let now = Instant::now();
+            *phase(&mut self.timings) += now.saturating_duration_since(last);
+            self.last = Some(now);
+        }
+    }
+
+    fn finish(self) -> Option<PhaseTimings> {
+        self.last.map(|_| self.timings)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The cells from the start (exclusive) to the goal (inclusive). Empty
+    /// when the goal is the start, and when it wasn't reached unless
+    /// [`SearchOptions::partial_path`] or the entry point asks for a partial
+    /// path to [`SearchResult::closest`] instead; see
+    /// [`SearchResult::is_partial`].
+    pub path: Vec<u32>,
+    /// The summed step costs of the path to the goal, or `None` when the goal
+    /// wasn't reached.
+    pub cost: Option<u32>,
+    /// What reaching the goal costs beyond the end of `path`: more than zero
+    /// only when [`SearchOptions::path_budget`] cut the path short, and `None`
+    /// when the goal wasn't reached.
+    pub remaining_cost: Option<u32>,
+    /// The path costs at most this many percent of the cheapest one, as long
+    /// as the heuristic never overestimates once its
+    /// [`Heuristic::inflation_percent`] is taken out; [`Manhattan`] doesn't.
+    /// `100` unless [`SearchOptions::weighting`] or a [`crate::Weighted`]
+    /// heuristic traded cost for speed. Then it starts out as what they
+    /// promise together, and a search that reaches the goal tightens it to
+    /// what the cells left on its frontier prove, so callers can tell
+    /// whether a path is good enough or worth refining.
+    pub bound_percent: u32,
+    pub termination: TerminationReason,
+    /// The reached cell with the lowest heuristic distance to the goal, which
+    /// is the goal itself when it was reached. Ties go to the cheaper cell.
+    /// After [`TerminationReason::Stopped`] it is the cell stopped at.
+    pub closest: u32,
+    pub stats: SearchStats,
+    /// Present when [`SearchOptions::record_trace`] was set.
+    pub trace: Option<SearchTrace>,
+}
+
+impl SearchResult {
+    /// Whether `path` leads toward the goal without reaching it.
+    pub fn is_partial(&self) -> bool {
+        self.termination != TerminationReason::GoalReached && !self.path.is_empty()
+    }
+}
+
+/// One cell taken off the frontier and expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expansion {
+    pub cell: u32,
+    /// Cost of the cheapest known route from the start to `cell`.
+    pub g: u32,
+    /// `g` plus the heuristic estimate from `cell` to the goal.
+    pub f: u32,
+    /// The cell `cell` was reached from, `None` for the start.
+    pub parent: Option<u32>,
+}
+
+/// Every expansion of a search, in the order they happened, for replaying
+/// the search in a visualizer. Each cell is expanded at most once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchTrace {
+    pub width: u32,
+    pub start: u32,
+    pub goal: u32,
+    pub expansions: Vec<Expansion>,
+}
+
+/// Lets variants of the search observe and steer it without copying the loop.
+/// `N` is how many neighbors an expansion holds on the stack.
+pub(crate) trait Hooks<const N: usize = 8> {
+    /// Whether the step from `from` to its walkable neighbor `to` may be taken.
+    #[inline(always)]
+    fn allow_step(&mut self, _from: u32, _to: u32) -> bool {
+        true
+    }
+
+    /// Cost added to an allowed step on top of the grid's.
+    #[inline(always)]
+    fn extra_cost(&mut self, _from: u32, _to: u32) -> u32 {
+        0
+    }
+
+    /// Called for every cell taken off the frontier, including the goal.
+    #[inline(always)]
+    fn expanded(&mut self, _expansion: Expansion) {}
+
+    /// Called before expanding any cell but the goal, once `expanded` cells
+    /// have been, and after any progress report for that point. Returning a
+    /// reason stops the search with it.
+    #[inline(always)]
+    fn interrupt(&mut self, _expanded: u32) -> Option<TerminationReason> {
+        None
+    }
+
+    /// Whether to stop at `state`, a cell taken off the frontier that isn't
+    /// the goal, before its neighbors are looked at.
+    #[inline(always)]
+    fn stop_at(&mut self, _state: &SearchState) -> bool {
+        false
+    }
+
+    /// How many of the entries next to come off the frontier a [`Progress`]
+    /// report should list.
+    #[inline(always)]
+    fn frontier_entries(&self) -> usize {
+        0
+    }
+
+    /// Whether to build a [`Progress`] report before the next expansion.
+    #[inline(always)]
+    fn wants_progress(&mut self, _expanded: u32) -> bool {
+        false
+    }
+
+    fn progress(&mut self, _progress: Progress) {}
+
+    /// Whether a search that doesn't reach the goal should still return the
+    /// path to the closest cell it reached.
+    #[inline(always)]
+    fn wants_partial_path(&self) -> bool {
+        false
+    }
+
+    /// Pushes the walkable neighbors of `cell`, with what stepping to each
+    /// costs, onto `out` when they are known without asking the grid,
+    /// returning whether they were.
+    #[inline(always)]
+    fn cached_neighbors(&mut self, _cell: u32, _out: &mut NeighborBuffer<N>) -> bool {
+        false
+    }
+}
+
+impl<const N: usize> Hooks<N> for () {}
+
+impl<const N: usize> Hooks<N> for Vec<Expansion> {
+    fn expanded(&mut self, expansion: Expansion) {
+        self.push(expansion);
+    }
+}
+
+impl<A: Hooks<N>, B: Hooks<N>, const N: usize> Hooks<N> for (A, B) {
+    #[inline(always)]
+    fn allow_step(&mut self, from: u32, to: u32) -> bool {
+        self.0.allow_step(from, to) && self.1.allow_step(from, to)
+    }
+
+    #[inline(always)]
+    fn extra_cost(&mut self, from: u32, to: u32) -> u32 {
+        self.0
+            .extra_cost(from, to)
+            .saturating_add(self.1.extra_cost(from, to))
+    }
+
+    #[inline(always)]
+    fn expanded(&mut self, expansion: Expansion) {
+        self.0.expanded(expansion);
+        self.1.expanded(expansion);
+    }
+
+    #[inline(always)]
+    fn interrupt(&mut self, expanded: u32) -> Option<TerminationReason> {
+        self.0
+            .interrupt(expanded)
+            .or_else(|| self.1.interrupt(expanded))
+    }
+
+    #[inline(always)]
+    fn stop_at(&mut self, state: &SearchState) -> bool {
+        self.0.stop_at(state) || self.1.stop_at(state)
+    }
+
+    #[inline(always)]
+    fn frontier_entries(&self) -> usize {
+        self.0.frontier_entries().max(self.1.frontier_entries())
+    }
+
+    #[inline(always)]
+    fn wants_progress(&mut self, expanded: u32) -> bool {
+        self.0.wants_progress(expanded) | self.1.wants_progress(expanded)
+    }
+
+    fn progress(&mut self, progress: Progress) {
+        self.0.progress(progress.clone());
+        self.1.progress(progress);
+    }
+
+    #[inline(always)]
+    fn wants_partial_path(&self) -> bool {
+        self.0.wants_partial_path() || self.1.wants_partial_path()
+    }
+
+    #[inline(always)]
+    fn cached_neighbors(&mut self, cell: u32, out: &mut NeighborBuffer<N>) -> bool {
+        self.0.cached_neighbors(cell, out) || self.1.cached_neighbors(cell, out)
+    }
+}
+
+/// Walks `came_from` back from `cell`, giving the path from `start`
+/// (exclusive) to `cell` (inclusive), or the other way around when
+/// `reversed`. A walk that takes more steps than there are parents, or
+/// runs out of them, has found a broken chain rather than the start.
+fn reconstruct<M: CellMap>(
+    came_from: &M,
+    start: u32,
+    cell: u32,
+    reversed: bool,
+) -> Result<Vec<u32>, InternalError> {
+    let mut path = Vec::new();
+    let mut last = cell;
+    while last != start {
+        match came_from.get(&last) {
+            Some(&parent) if path.len() < came_from.len() => {
+                path.push(last);
+                last = parent;
+            }
+            _ => return Err(InternalError::BrokenPath { cell: last }),
+        }
+    }
+    if !reversed {
+        path.reverse();
+    }
+    Ok(path)
+}
+
+/// A shuffle of cell indices that frontier entries are stored under, which
+/// changes the order the heap breaks ties in. It is a bijection, so every
+/// entry can be turned back into its cell.
+#[derive(Clone, Copy)]
+struct Jitter {
+    key: u32,
+    multiplier: u32,
+    inverse: u32,
+}
+
+impl Jitter {
+    fn new(seed: Option<u64>) -> Self {
+        let seed = match seed {
+            Some(seed) => seed,
+            None => {
+                return Jitter {
+                    key: 0,
+                    multiplier: 1,
+                    inverse: 1,
+                }
+            }
+        };
+        let mut rng = Rng::new(seed);
+        let key = rng.next_u64() as u32;
+        let multiplier = rng.next_u64() as u32 | 1;
+        // Newton's method doubles the correct low bits of the inverse of an
+        // odd number each round, starting from three
+        let mut inverse = multiplier;
+        for _ in 0..4 {
+            inverse = inverse.wrapping_mul(2u32.wrapping_sub(multiplier.wrapping_mul(inverse)));
+        }
+        Jitter {
+            key,
+            multiplier,
+            inverse,
+        }
+    }
+
+    #[inline(always)]
+    fn hide(self, cell: u32) -> u32 {
+        (cell ^ self.key).wrapping_mul(self.multiplier)
+    }
+
+    #[inline(always)]
+    fn reveal(self, position: u32) -> u32 {
+        position.wrapping_mul(self.inverse) ^ self.key
+    }
+}
+
+/// The buffers a search works in, which can be kept between searches so
+/// later ones don't have to allocate them again.
+#[derive(Default)]
+pub(crate) struct Scratch {
+    frontier: BinaryHeap<FrontierItem>,
+    buckets: BucketQueue,
+    sparse: Maps<FxHashMap<u32, u32>>,
+    dense: Maps<DenseMap>,
+    /// The start of the last search run in these buffers, and whether it
+    /// used the dense maps.
+    last: Option<(u32, bool)>,
+}
+
+impl Scratch {
+    /// Walks the parents left by the last search back from `cell`.
+    pub(crate) fn path_back(&self, cell: u32) -> PathBack<'_> {
+        let (start, came_from) = match self.last {
+            Some((start, true)) => (start, Some(&self.dense.came_from as &dyn CellMap)),
+            Some((start, false)) => (start, Some(&self.sparse.came_from as &dyn CellMap)),
+            None => (cell, None),
+        };
+        PathBack {
+            came_from,
+            start,
+            cell,
+            left: came_from.map_or(0, |came_from| came_from.len()),
+        }
+    }
+}
+
+/// The cells of a path from a cell (inclusive) back to the start of the
+/// search that reached it (exclusive), read one at a time out of the
+/// search's buffers. Made by [`crate::Pathfinder::path_back`].
+pub struct PathBack<'a> {
+    came_from: Option<&'a dyn CellMap>,
+    start: u32,
+    cell: u32,
+    /// Steps left before the walk has taken more than there are parents,
+    /// which only a broken chain could make it do.
+    left: usize,
+}
+
+impl Iterator for PathBack<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.cell == self.start || self.left == 0 {
+            return None;
+        }
+        let parent = *self.came_from?.get(&self.cell)?;
+        self.left -= 1;
+        Some(mem::replace(&mut self.cell, parent))
+    }
+}
+
+impl fmt::Debug for PathBack<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PathBack")
+            .field("start", &self.start)
+            .field("cell", &self.cell)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The cells waiting to be expanded, taken off cheapest first and, among
+/// equal costs, highest position first, as [`FrontierItem`] orders them.
+/// Entries are never equal but for the same cell pushed twice at the same
+/// cost, so every frontier gives the same order.
+trait Frontier {
+    fn push(&mut self, item: FrontierItem);
+    fn pop(&mut self) -> Option<FrontierItem>;
+    fn len(&self) -> usize;
+    fn clear(&mut self);
+    /// Every entry, in no particular order.
+    fn items(&self) -> impl Iterator<Item = FrontierItem> + '_;
+}
+
+impl Frontier for BinaryHeap<FrontierItem> {
+    #[inline(always)]
+    fn push(&mut self, item: FrontierItem) {
+        BinaryHeap::push(self, item);
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<FrontierItem> {
+        BinaryHeap::pop(self)
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        BinaryHeap::clear(self);
+    }
+
+    fn items(&self) -> impl Iterator<Item = FrontierItem> + '_ {
+        self.iter().copied()
+    }
+}
+
+/// Priorities below this get a bucket of their own in a [`BucketQueue`].
+const BUCKETS: usize = 1 << 16;
+
+/// The frontier of [`Backend::Buckets`]: the positions pushed at each
+/// priority in a bucket of their own, emptied lowest first, and each a heap
+/// so that ties still come off highest position first.
+#[derive(Default)]
+struct BucketQueue {
+    buckets: Vec<BinaryHeap<u32>>,
+    /// No bucket below this holds anything.
+    lowest: usize,
+    /// Entries too costly for a bucket.
+    overflow: BinaryHeap<FrontierItem>,
+    len: usize,
+}
+
+impl Frontier for BucketQueue {
+    #[inline(always)]
+    fn push(&mut self, item: FrontierItem) {
+        let priority = item.cost as usize;
+        if priority < BUCKETS {
+            if self.buckets.len() <= priority {
+                self.buckets.resize_with(priority + 1, BinaryHeap::new);
+            }
+            self.buckets[priority].push(item.position);
+            self.lowest = self.lowest.min(priority);
+        } else {
+            self.overflow.push(item);
+        }
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<FrontierItem> {
+        while let Some(bucket) = self.buckets.get_mut(self.lowest) {
+            if let Some(position) = bucket.pop() {
+                self.len -= 1;
+                return Some(FrontierItem {
+                    cost: self.lowest as u32,
+                    position,
+                });
+            }
+            self.lowest += 1;
+        }
+        let item = self.overflow.pop()?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Empties every bucket, keeping their room for the next search.
+    fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(BinaryHeap::clear);
+        self.overflow.clear();
+        self.lowest = usize::MAX;
+        self.len = 0;
+    }
+
+    fn items(&self) -> impl Iterator<Item = FrontierItem> + '_ {
+        let bucketed = self.buckets.iter().enumerate().flat_map(|(cost, bucket)| {
+            bucket.iter().map(move |&position| FrontierItem {
+                cost: cost as u32,
+                position,
+            })
+        });
+        bucketed.chain(self.overflow.iter().copied())
+    }
+}
+
+/// What a search knows about each cell it reached.
+#[derive(Default)]
+struct Maps<M> {
+    cost_so_far: M,
+    came_from: M,
+    /// Steps from the start, only kept for [`Weighting::Dynamic`] and
+    /// [`SearchOptions::max_steps`].
+    depth: M,
+}
+
+impl<M: CellMap> Maps<M> {
+    fn reset(&mut self, cells: usize, capacity: usize, depths: bool) {
+        self.cost_so_far.reset(cells, capacity);
+        self.came_from.reset(cells, capacity);
+        // only make room for depths when they will be kept
+        if depths {
+            self.depth.reset(cells, capacity);
+        } else {
+            self.depth.reset(0, 0);
+        }
+    }
+}
+
+/// Grids with at most this many cells can be searched with [`DenseMap`]s
+/// when [`Backend::Auto`] is asked for.
+const DENSE_LIMIT: usize = 1 << 20;
+/// [`Backend::Auto`] sticks to hash maps when the search looks like it will
+/// reach fewer than one in this many cells, judging by its initial capacity.
+const DENSE_RATIO: usize = 64;
+
+/// A map from cells to values, the part of a hash map's interface that the
+/// search needs.
+trait CellMap {
+    /// Empties the map for a grid of `cells` cells, making room for about
+    /// `capacity` of them.
+    fn reset(&mut self, cells: usize, capacity: usize);
+    fn get(&self, cell: &u32) -> Option<&u32>;
+    fn insert(&mut self, cell: u32, value: u32);
+    fn len(&self) -> usize;
+}
+
+impl CellMap for FxHashMap<u32, u32> {
+    fn reset(&mut self, _cells: usize, capacity: usize) {
+        self.clear();
+        self.reserve(capacity);
+    }
+
+    #[inline(always)]
+    fn get(&self, cell: &u32) -> Option<&u32> {
+        FxHashMap::get(self, cell)
+    }
+
+    #[inline(always)]
+    fn insert(&mut self, cell: u32, value: u32) {
+        FxHashMap::insert(self, cell, value);
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        FxHashMap::len(self)
+    }
+}
+
+/// A slot for every cell of the grid, stamped with the search that wrote it
+/// so that emptying the map doesn't have to touch every slot.
+#[derive(Default)]
+struct DenseMap {
+    /// (stamp, value) per cell.
+    slots: Vec<(u32, u32)>,
+    stamp: u32,
+    len: usize,
+}
+
+impl CellMap for DenseMap {
+    fn reset(&mut self, cells: usize, _capacity: usize) {
+        if self.slots.len() < cells {
+            self.slots.resize(cells, (0, 0));
+        }
+        self.stamp = self.stamp.wrapping_add(1);
+        if self.stamp == 0 {
+            self.slots.iter_mut().for_each(|slot| *slot = (0, 0));
+            self.stamp = 1;
+        }
+        self.len = 0;
+    }
+
+    #[inline(always)]
+    fn get(&self, cell: &u32) -> Option<&u32> {
+        match self.slots.get(*cell as usize) {
+            Some((stamp, value)) if *stamp == self.stamp => Some(value),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    fn insert(&mut self, cell: u32, value: u32) {
+        let slot = &mut self.slots[cell as usize];
+        if slot.0 != self.stamp {
+            self.len += 1;
+        }
+        *slot = (self.stamp, value);
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Cells to make room for per step between the start and the goal.
+const CAPACITY_PER_STEP: usize = 8;
+
+/// Room for the cells a search from `start` to `end` is likely to reach,
+/// which on open maps grows with the distance between them.
+fn initial_capacity(start: u32, end: u32, width: u32) -> usize {
+    let steps = Manhattan.estimate(start, end, width) as usize;
+    steps.saturating_add(1).saturating_mul(CAPACITY_PER_STEP)
+}
+
+/// The search behind every A* entry point, in buffers of its own.
+pub(crate) fn run<G: GridSource + ?Sized, E: Heuristic + ?Sized, H: Hooks>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    heuristic: &E,
+    hooks: &mut H,
+) -> SearchResult {
+    let mut scratch = Scratch::default();
+    run_in::<_, _, _, 8>(&mut scratch, start, end, grid, options, heuristic, hooks)
+}
+
+/// [`run`] in reused buffers, holding up to `N` neighbors of each cell on
+/// the stack.
+pub(crate) fn run_in<G: GridSource + ?Sized, E: Heuristic + ?Sized, H: Hooks<N>, const N: usize>(
+    scratch: &mut Scratch,
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    heuristic: &E,
+    hooks: &mut H,
+) -> SearchResult {
+    if start >= grid.len() || end >= grid.len() {
+        scratch.last = None;
+        return SearchResult {
+            path: Vec::new(),
+            cost: None,
+            remaining_cost: None,
+            bound_percent: bound_percent(options.weighting, heuristic),
+            termination: TerminationReason::Exhausted,
+            closest: start,
+            stats: SearchStats::default(),
+            trace: None,
+        };
+    }
+    let width = grid.width();
+    // reused buffers keep whatever room earlier searches needed
+    let capacity = options
+        .initial_capacity
+        .unwrap_or_else(|| initial_capacity(start, end, width))
+        .min(grid.len() as usize);
+    let cells = grid.len() as usize;
+    let dense = match options.backend {
+        Backend::Auto => cells <= DENSE_LIMIT && cells / DENSE_RATIO <= capacity,
+        Backend::Dense | Backend::Buckets => true,
+        Backend::Sparse => false,
+    };
+    let Scratch {
+        frontier,
+        buckets,
+        sparse,
+        dense: dense_maps,
+        last,
+    } = scratch;
+    *last = Some((start, dense));
+    if options.backend == Backend::Buckets {
+        buckets.clear();
+        dense_maps.reset(cells, capacity, options.counts_steps());
+        return run_believing::<_, _, _, _, _, N>(
+            buckets, dense_maps, start, end, grid, options, heuristic, hooks,
+        );
+    }
+    frontier.clear();
+    frontier.reserve(capacity);
+    if dense {
+        dense_maps.reset(cells, capacity, options.counts_steps());
+        run_believing::<_, _, _, _, _, N>(
+            frontier, dense_maps, start, end, grid, options, heuristic, hooks,
+        )
+    } else {
+        sparse.reset(cells, capacity, options.counts_steps());
+        run_believing::<_, _, _, _, _, N>(
+            frontier, sparse, start, end, grid, options, heuristic, hooks,
+        )
+    }
+}
+
+/// [`run_on`] with unknown cells read as [`SearchOptions::unknown_cells`]
+/// says, leaving grids be when they are read as costs anyway.
+#[allow(clippy::too_many_arguments)]
+fn run_believing<
+    G: GridSource + ?Sized,
+    E: Heuristic + ?Sized,
+    H: Hooks<N>,
+    F: Frontier,
+    M: CellMap,
+    const N: usize,
+>(
+    frontier: &mut F,
+    maps: &mut Maps<M>,
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    heuristic: &E,
+    hooks: &mut H,
+) -> SearchResult {
+    match options.unknown_cells {
+        UnknownCells::Cost => {
+            run_on::<_, _, _, _, _, N>(frontier, maps, start, end, grid, options, heuristic, hooks)
+        }
+        unknown_cells => run_on::<_, _, _, _, _, N>(
+            frontier,
+            maps,
+            start,
+            end,
+            &unknown_cells.over(grid),
+            options,
+            heuristic,
+            hooks,
+        ),
+    }
+}
+
+/// The body of [`run_in`], over either kind of per-cell state.
+#[allow(clippy::too_many_arguments)]
+fn run_on<
+    G: GridSource + ?Sized,
+    E: Heuristic + ?Sized,
+    H: Hooks<N>,
+    F: Frontier,
+    M: CellMap,
+    const N: usize,
+>(
+    frontier: &mut F,
+    maps: &mut Maps<M>,
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    heuristic: &E,
+    hooks: &mut H,
+) -> SearchResult {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("astar", start, end, width = grid.width()).entered();
+    #[cfg(feature = "metrics")]
+    let started = std::time::Instant::now();
+    let cardinal_directions = options.cardinal_directions;
+    let canonical = options.canonical_ordering && !cardinal_directions;
+    let width = grid.width();
+    let Maps {
+        cost_so_far,
+        came_from,
+        depth,
+    } = maps;
+    let mut stopwatch = Stopwatch::new(options.time_phases);
+    let jitter = Jitter::new(options.jitter);
+    let start_cost = if options.pay_for_start {
+        grid.cost(start)
+    } else {
+        0
+    };
+    cost_so_far.insert(start, start_cost);
+    frontier.push(FrontierItem {
+        cost: 0,
+        position: jitter.hide(start),
+    });
+    let mut expanded = 0u32;
+    let mut peak_frontier = 1usize;
+    let mut termination = TerminationReason::Exhausted;
+    let start_h = heuristic.estimate(start, end, width);
+    let weighting = options.weighting;
+    let dynamic = weighting != Weighting::None;
+    let counts_steps = options.counts_steps();
+    // the fewest steps between a cell and the goal, walls aside
+    let steps_to_end = |cell: u32| {
+        let dx = (cell % width).abs_diff(end % width);
+        let dy = (cell / width).abs_diff(end / width);
+        if cardinal_directions {
+            dx + dy
+        } else {
+            dx.max(dy)
+        }
+    };
+    let max_steps = options.max_steps.unwrap_or(u32::MAX);
+    let mut cut_short = false;
+    // whether cells come off the frontier in order of what routes through
+    // them cost at least, and whether any were given up on as too costly
+    let inflation = heuristic.inflation_percent();
+    let in_order = !dynamic && inflation <= 100;
+    let mut over_budget = false;
+    if steps_to_end(start) > max_steps {
+        // nothing within reach, so nothing to search
+        frontier.clear();
+        cut_short = true;
+    }
+    let priority_of = |cost: u32, h: u32, depth: u32| match weighting {
+        Weighting::None => cost.saturating_add(h),
+        Weighting::Dynamic { epsilon_percent } => {
+            let depth = depth.min(start_h) as u64;
+            let inflation = match start_h {
+                0 => 0,
+                _ => epsilon_percent as u64 * (start_h as u64 - depth) / start_h as u64,
+            };
+            let h = h as u64 * (100 + inflation) / 100;
+            cost.saturating_add(h.min(u32::MAX as u64) as u32)
+        }
+    };
+    // (heuristic, accumulated cost, cell) of the closest cell reached so far
+    let mut closest = (start_h, start_cost, start);
+    let len = grid.len();
+    let mut steps = NeighborBuffer::<N>::new();
+    let mut estimates = SmallVec::<[u32; N]>::new();
+    'search: while let Some(FrontierItem {
+        position: current_position,
+        cost: priority,
+    }) = frontier.pop()
+    {
+        stopwatch.lap(|timings| &mut timings.heap);
+        let current_position = jitter.reveal(current_position);
+        let current_h = heuristic.estimate(current_position, end, width);
+        let g = match cost_so_far.get(&current_position) {
+            Some(&cost) => cost,
+            None => {
+                termination = InternalError::UnreachedCell {
+                    cell: current_position,
+                }
+                .into();
+                break;
+            }
+        };
+        // a cheaper route to this cell was found after this entry was pushed,
+        // and the cell has already been expanded through it
+        let current_depth = if counts_steps {
+            depth.get(&current_position).copied().unwrap_or(0)
+        } else {
+            0
+        };
+        if current_position != start && priority > priority_of(g, current_h, current_depth) {
+            continue;
+        }
+        // inflated estimates are scaled back down, so that only cells no
+        // route within the limit passes through are given up on
+        let admissible = current_h as u64 * 100 / inflation.max(100) as u64;
+        let least = g.saturating_add(admissible as u32);
+        if options.max_cost.is_some_and(|max| least > max) {
+            if in_order {
+                // cells come off cheapest first, so the rest cost more too
+                termination = TerminationReason::CostLimit;
+                break;
+            }
+            over_budget = true;
+            continue;
+        }
+        let is_goal = current_position == end;
+        if !is_goal && options.max_expansions.is_some_and(|max| expanded >= max) {
+            termination = TerminationReason::ExpansionLimit;
+            #[cfg(feature = "tracing")]
+            tracing::debug!(expanded, "expansion limit hit");
+            break;
+        }
+        if !is_goal {
+            if hooks.wants_progress(expanded) {
+                let partial_path = match reconstruct(came_from, start, closest.2, false) {
+                    Ok(path) => path,
+                    Err(error) => {
+                        termination = error.into();
+                        break;
+                    }
+                };
+                let wanted = hooks.frontier_entries();
+                let mut entries = Vec::new();
+                if wanted > 0 {
+                    // entries a cheaper route made stale would be skipped
+                    // when popped, so they are left out here too
+                    let live = frontier.items().filter(|item| {
+                        let cell = jitter.reveal(item.position);
+                        let steps = if dynamic {
+                            depth.get(&cell).copied().unwrap_or(0)
+                        } else {
+                            0
+                        };
+                        let h = heuristic.estimate(cell, end, width);
+                        cost_so_far
+                            .get(&cell)
+                            .is_some_and(|&cost| item.cost <= priority_of(cost, h, steps))
+                    });
+                    let current = FrontierItem {
+                        cost: priority,
+                        position: jitter.hide(current_position),
+                    };
+                    let mut items: Vec<FrontierItem> =
+                        std::iter::once(current).chain(live).collect();
+                    // the greatest items are popped first
+                    if items.len() > wanted {
+                        items.select_nth_unstable_by(wanted - 1, |a, b| b.cmp(a));
+                        items.truncate(wanted);
+                    }
+                    items.sort_unstable_by(|a, b| b.cmp(a));
+                    entries = items
+                        .into_iter()
+                        .map(|item| {
+                            let cell = jitter.reveal(item.position);
+                            FrontierEntry {
+                                cell,
+                                g: cost_so_far.get(&cell).copied().unwrap_or(0),
+                                f: item.cost,
+                            }
+                        })
+                        .collect();
+                }
+                hooks.progress(Progress {
+                    expanded,
+                    best_f: priority,
+                    closest: closest.2,
+                    partial_path,
+                    frontier: entries,
+                });
+            }
+            if let Some(reason) = hooks.interrupt(expanded) {
+                termination = reason;
+                break;
+            }
+        }
+        let parent = came_from.get(&current_position).copied();
+        hooks.expanded(Expansion {
+            cell: current_position,
+            g,
+            f: g.saturating_add(current_h),
+            parent,
+        });
+        if is_goal {
+            termination = TerminationReason::GoalReached;
+            break;
+        }
+        let state = SearchState {
+            cell: current_position,
+            g,
+            h: current_h,
+            parent,
+            expanded,
+        };
+        if hooks.stop_at(&state) {
+            termination = TerminationReason::Stopped;
+            closest.2 = current_position;
+            break;
+        }
+        expanded += 1;
+        stopwatch.lap(|timings| &mut timings.bookkeeping);
+        steps.clear();
+        if !hooks.cached_neighbors(current_position, &mut steps) {
+            for neighbor in get_neighbor_coords(current_position, grid, cardinal_directions) {
+                steps.push(neighbor, step_cost(grid, current_position, neighbor));
+            }
+        }
+        let (neighbor_coords, step_costs) = (steps.cells(), steps.costs());
+        // bits of the neighbors, by their position in `neighbor_coords`,
+        // that a canonical ordering leaves out; a cell of a grid has at most
+        // eight, and only grids are pruned
+        let mut pruned = 0u8;
+        if let (Some(parent), true, true) = (parent, canonical, neighbor_coords.len() <= 8) {
+            let cost = grid.cost(current_position);
+            let mut open = 0u8;
+            let mut directions = [0u8; 8];
+            let mut uniform = true;
+            for ((slot, &neighbor), &step) in
+                directions.iter_mut().zip(neighbor_coords).zip(step_costs)
+            {
+                *slot = direction(current_position, neighbor, width);
+                open |= 1 << *slot;
+                // diagonal directions have odd indices and cost one more
+                uniform &= step == cost.saturating_add(1 + (*slot & 1) as u32);
+            }
+            if uniform {
+                let keep = canonical::successors(direction(parent, current_position, width), open);
+                for (index, &slot) in directions.iter().enumerate().take(neighbor_coords.len()) {
+                    if keep & 1 << slot == 0 {
+                        pruned |= 1 << index;
+                    }
+                }
+            }
+        }
+        estimates.clear();
+        estimates.resize(neighbor_coords.len(), 0);
+        heuristic.estimate_neighbors(
+            current_position,
+            neighbor_coords,
+            end,
+            width,
+            &mut estimates,
+        );
+        stopwatch.lap(|timings| &mut timings.neighbors);
+        for (index, ((&neighbor, &step), &h)) in neighbor_coords
+            .iter()
+            .zip(step_costs)
+            .zip(&estimates)
+            .enumerate()
+        {
+            if neighbor >= len {
+                termination = InternalError::NeighborOutOfBounds {
+                    cell: current_position,
+                    neighbor,
+                }
+                .into();
+                break 'search;
+            }
+            if index < 8 && pruned & 1 << index != 0 {
+                continue;
+            }
+            if !hooks.allow_step(current_position, neighbor) {
+                continue;
+            }
+            if (current_depth + 1).saturating_add(steps_to_end(neighbor)) > max_steps {
+                cut_short = true;
+                continue;
+            }
+            // saturate rather than overflow on absurd cell costs
+            let cost = g
+                .saturating_add(step)
+                .saturating_add(hooks.extra_cost(current_position, neighbor));
+            if cost_so_far.get(&neighbor).is_none_or(|&known| cost < known) {
+                cost_so_far.insert(neighbor, cost);
+                if (h, cost, neighbor) < closest {
+                    closest = (h, cost, neighbor);
+                }
+                if counts_steps {
+                    depth.insert(neighbor, current_depth + 1);
+                }
+                came_from.insert(neighbor, current_position);
+                let priority = priority_of(cost, h, current_depth + 1);
+                stopwatch.lap(|timings| &mut timings.bookkeeping);
+                frontier.push(FrontierItem {
+                    cost: priority,
+                    position: jitter.hide(neighbor),
+                });
+                peak_frontier = peak_frontier.max(frontier.len());
+                stopwatch.lap(|timings| &mut timings.heap);
+            }
+        }
+    }
+    stopwatch.lap(|timings| &mut timings.bookkeeping);
+    if termination == TerminationReason::Exhausted && cut_short {
+        termination = TerminationReason::StepLimit;
+    }
+    if termination == TerminationReason::Exhausted && over_budget {
+        termination = TerminationReason::CostLimit;
+    }
+    let mut path: Vec<u32> = Vec::new();
+    let mut cost = None;
+    let mut remaining_cost = None;
+    let mut bound = bound_percent(weighting, heuristic);
+    // costs of cells on a reconstructed path are all known
+    let g_of = |cell: &u32| cost_so_far.get(cell).copied().unwrap_or(0);
+    if termination == TerminationReason::GoalReached && !options.lazy_path {
+        match reconstruct(came_from, start, end, options.reverse_path) {
+            Ok(found) => path = found,
+            Err(error) => termination = error.into(),
+        }
+    }
+    if termination == TerminationReason::GoalReached {
+        let found = g_of(&end);
+        cost = Some(found);
+        if bound > 100 {
+            bound = bound.min(proven_bound(
+                found,
+                frontier,
+                |cell| g_of(&cell),
+                |cell| heuristic.estimate(cell, end, width),
+                heuristic.inflation_percent(),
+                &jitter,
+            ));
+        }
+        if let Some(budget) = options.path_budget {
+            let within = |cell: &u32| g_of(cell) <= budget;
+            if options.reverse_path {
+                path.drain(..path.partition_point(|cell| !within(cell)));
+            } else {
+                path.truncate(path.partition_point(within));
+            }
+        }
+        let last = if options.lazy_path {
+            // the last cell within the budget, walking back from the goal
+            let mut last = end;
+            while options
+                .path_budget
+                .is_some_and(|budget| g_of(&last) > budget)
+            {
+                match came_from.get(&last) {
+                    Some(&parent) if last != start => last = parent,
+                    _ => break,
+                }
+            }
+            Some(last)
+        } else if options.reverse_path {
+            path.first().copied()
+        } else {
+            path.last().copied()
+        };
+        let spent = last.map_or(start_cost, |cell| g_of(&cell));
+        remaining_cost = cost.map(|cost| cost.saturating_sub(spent));
+        closest = (0, 0, end);
+    } else if !options.lazy_path
+        && (termination == TerminationReason::Stopped
+            || (options.partial_path || hooks.wants_partial_path())
+                && !matches!(termination, TerminationReason::Internal(_)))
+    {
+        match reconstruct(came_from, start, closest.2, options.reverse_path) {
+            Ok(found) => path = found,
+            Err(error) => termination = error.into(),
+        }
+    }
+    stopwatch.lap(|timings| &mut timings.reconstruction);
+    let stored = cost_so_far.len() + came_from.len() + depth.len();
+    let stats = SearchStats {
+        expanded,
+        reached: cost_so_far.len() as u32,
+        peak_frontier: peak_frontier as u32,
+        stored: stored as u32,
+        bytes: peak_frontier * mem::size_of::<FrontierItem>()
+            + stored * mem::size_of::<(u32, u32)>(),
+        timings: stopwatch.finish(),
+    };
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        expanded,
+        termination = termination.as_str(),
+        cost,
+        steps = path.len(),
+        "search finished"
+    );
+    #[cfg(feature = "metrics")]
+    record_metrics(started, expanded, termination);
+    SearchResult {
+        path,
+        cost,
+        remaining_cost,
+        bound_percent: bound,
+        termination,
+        closest: closest.2,
+        stats,
+        trace: None,
+    }
+}
+
+/// The most a search weighted by `weighting` with `heuristic` can return,
+/// in percent of the cheapest path, before it has run.
+fn bound_percent<E: Heuristic + ?Sized>(weighting: Weighting, heuristic: &E) -> u32 {
+    let percent =
+        (weighting.bound_percent() as u64 * heuristic.inflation_percent() as u64).div_ceil(100);
+    percent.min(u32::MAX as u64) as u32
+}
+
+/// How many percent of the cheapest path a path costing `cost` proves to
+/// be at most, once an inflated search has reached the goal. Cells on a
+/// cheapest route are still on the frontier whenever the goal was reached
+/// the wrong way, at their cheapest cost, so the lowest cost so far plus
+/// uninflated estimate among them bounds the cheapest path from below.
+fn proven_bound<F: Frontier>(
+    cost: u32,
+    frontier: &F,
+    g_of: impl Fn(u32) -> u32,
+    estimate: impl Fn(u32) -> u32,
+    inflation_percent: u32,
+    jitter: &Jitter,
+) -> u32 {
+    let lower = frontier
+        .items()
+        .map(|item| {
+            let cell = jitter.reveal(item.position);
+            let h = estimate(cell) as u64 * 100 / inflation_percent.max(100) as u64;
+            g_of(cell) as u64 + h
+        })
+        .fold(cost as u64, u64::min);
+    match lower {
+        0 => 100,
+        lower => (cost as u64 * 100)
+            .div_ceil(lower)
+            .clamp(100, u32::MAX as u64) as u32,
+    }
+}
+
+/// Reports a finished search to whatever recorder the `metrics` crate has installed.
+#[cfg(feature = "metrics")]
+fn record_metrics(started: std::time::Instant, expanded: u32, termination: TerminationReason) {
+    metrics::counter!("simple_astar_searches_total").increment(1);
+    metrics::counter!("simple_astar_nodes_expanded_total").increment(expanded as u64);
+    metrics::histogram!("simple_astar_nodes_expanded").record(expanded as f64);
+    metrics::histogram!("simple_astar_search_duration_seconds").record(started.elapsed());
+    if termination != TerminationReason::GoalReached {
+        metrics::counter!(
+            "simple_astar_search_failures_total",
+            "reason" => termination.as_str()
+        )
+        .increment(1);
+    }
+}
+
+/// A* from `start` to `end`, configured by `options`.
+pub fn search<G: GridSource + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+) -> SearchResult {
+    search_with(start, end, grid, options, &Manhattan, ())
+}
+
+/// [`search`] guided by `heuristic` instead of [`Manhattan`]. An estimate
+/// that overestimates can make the returned path suboptimal, and deviating
+/// from [`Manhattan`] changes which of several equally cheap paths is found.
+pub fn search_with_heuristic<G: GridSource + ?Sized, E: Heuristic + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    heuristic: &E,
+) -> SearchResult {
+    search_with(start, end, grid, options, heuristic, ())
+}
+
+/// [`search`] with extra hooks, recording a trace on top of them when asked to.
+pub(crate) fn search_with<G: GridSource + ?Sized, E: Heuristic + ?Sized, H: Hooks>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    heuristic: &E,
+    hooks: H,
+) -> SearchResult {
+    let mut scratch = Scratch::default();
+    search_in(&mut scratch, start, end, grid, options, heuristic, hooks)
+}
+
+/// [`search_with`] in reused buffers.
+pub(crate) fn search_in<G: GridSource + ?Sized, E: Heuristic + ?Sized, H: Hooks>(
+    scratch: &mut Scratch,
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    heuristic: &E,
+    hooks: H,
+) -> SearchResult {
+    if !options.record_trace {
+        let mut hooks = hooks;
+        return run_in::<_, _, _, 8>(scratch, start, end, grid, options, heuristic, &mut hooks);
+    }
+    let mut hooks = (hooks, Vec::new());
+    let mut result =
+        run_in::<_, _, _, 8>(scratch, start, end, grid, options, heuristic, &mut hooks);
+    result.trace = Some(SearchTrace {
+        width: grid.width(),
+        start,
+        goal: end,
+        expansions: hooks.1,
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_grid, Grid};
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_reports_path_costs() {
+        let grid = Grid::new(vec![
+            1, 1, 1,
+            1, 0, 5,
+            1, 1, 1,
+        ], 3).unwrap();
+        let options = SearchOptions { cardinal_directions: true, ..SearchOptions::default() };
+        let result = search(0, 5, &grid, &options);
+        assert_eq!(result.path, astar_grid(0, 5, &grid, true));
+        assert_eq!(result.cost, Some(10));
+        assert_eq!(result.trace, None);
+        assert_eq!(search(0, 0, &grid, &options).cost, Some(0));
+        assert_eq!(search(0, 4, &grid, &options).cost, None);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_explains_unreachable_goals() {
+        let grid = Grid::new(vec![
+            1, 1, 1, 0, 1,
+            1, 1, 1, 0, 1,
+            1, 1, 1, 0, 1,
+        ], 5).unwrap();
+        let result = search(0, 9, &grid, &SearchOptions::default());
+        assert_eq!(result.termination, TerminationReason::Exhausted);
+        assert_eq!(result.path, Vec::<u32>::new());
+        assert_eq!(result.cost, None);
+        assert_eq!(result.closest, 7);
+        assert_eq!(result.stats, SearchStats { expanded: 9, reached: 9, ..result.stats });
+    }
+
+    #[test]
+    fn it_sizes_buffers_by_distance_and_keeps_them() {
+        let grid = Grid::filled(500, 500, 1).unwrap();
+        let options = SearchOptions::default();
+        let mut scratch = Scratch::default();
+        search_in(&mut scratch, 0, 2, &grid, &options, &Manhattan, ());
+        let short = scratch.frontier.capacity();
+        assert!(short < 100, "{}", short);
+        search_in(&mut scratch, 0, 499 * 501, &grid, &options, &Manhattan, ());
+        let long = scratch.frontier.capacity();
+        assert!(long > short);
+        search_in(&mut scratch, 0, 2, &grid, &options, &Manhattan, ());
+        assert_eq!(scratch.frontier.capacity(), long);
+        let mut scratch = Scratch::default();
+        let sized = SearchOptions {
+            initial_capacity: Some(5000),
+            ..options
+        };
+        search_in(&mut scratch, 0, 2, &grid, &sized, &Manhattan, ());
+        assert!(scratch.frontier.capacity() >= 5000);
+    }
+
+    #[test]
+    fn every_backend_finds_the_same_paths() {
+        let mut rng = crate::Rng::new(1);
+        let grid = rng.grid(30, 20, 25, 5);
+        let mut dense = Scratch::default();
+        let mut sparse = Scratch::default();
+        let mut buckets = Scratch::default();
+        for round in 0..40 {
+            let (start, end) = (rng.range(0, grid.len()), rng.range(0, grid.len()));
+            let options = SearchOptions {
+                cardinal_directions: round % 2 == 0,
+                record_trace: true,
+                partial_path: true,
+                weighting: match round % 3 {
+                    0 => Weighting::Dynamic {
+                        epsilon_percent: 50,
+                    },
+                    _ => Weighting::None,
+                },
+                ..SearchOptions::default()
+            };
+            let on = |backend| SearchOptions {
+                backend,
+                ..options.clone()
+            };
+            let expected = search(start, end, &grid, &on(Backend::Sparse));
+            let reused = search_in(
+                &mut dense,
+                start,
+                end,
+                &grid,
+                &on(Backend::Dense),
+                &Manhattan,
+                (),
+            );
+            assert_eq!(reused, expected);
+            let reused = search_in(
+                &mut sparse,
+                start,
+                end,
+                &grid,
+                &on(Backend::Sparse),
+                &Manhattan,
+                (),
+            );
+            assert_eq!(reused, expected);
+            let reused = search_in(
+                &mut buckets,
+                start,
+                end,
+                &grid,
+                &on(Backend::Buckets),
+                &Manhattan,
+                (),
+            );
+            assert_eq!(reused, expected);
+            assert_eq!(search(start, end, &grid, &on(Backend::Auto)), expected);
+        }
+    }
+
+    #[test]
+    fn bucket_queues_spill_costly_entries_into_a_heap() {
+        let mut rng = crate::Rng::new(2);
+        // dear enough that most priorities are past the last bucket
+        let cells: Vec<u32> = (0..16 * 12)
+            .map(|_| {
+                if rng.chance(1, 5) {
+                    0
+                } else {
+                    rng.range(1, 6) * 9000
+                }
+            })
+            .collect();
+        let grid = Grid::new(cells, 16).unwrap();
+        for _ in 0..20 {
+            let (start, end) = (rng.below(grid.len()), rng.below(grid.len()));
+            let on = |backend| SearchOptions {
+                backend,
+                ..SearchOptions::default()
+            };
+            let heap = search(start, end, &grid, &on(Backend::Dense));
+            assert_eq!(search(start, end, &grid, &on(Backend::Buckets)), heap);
+        }
+    }
+
+    #[test]
+    fn it_stops_at_the_expansion_limit() {
+        let grid = Grid::filled(10, 1, 1).unwrap();
+        let options = SearchOptions {
+            max_expansions: Some(3),
+            ..SearchOptions::default()
+        };
+        let result = search(0, 9, &grid, &options);
+        assert_eq!(result.termination, TerminationReason::ExpansionLimit);
+        assert!(result.path.is_empty());
+        assert_eq!(result.closest, 3);
+        assert_eq!(result.stats.expanded, 3);
+        let enough = SearchOptions {
+            max_expansions: Some(9),
+            ..SearchOptions::default()
+        };
+        let result = search(0, 9, &grid, &enough);
+        assert_eq!(result.termination, TerminationReason::GoalReached);
+        assert_eq!(result.closest, 9);
+        assert_eq!(result.path.len(), 9);
+    }
+
+    #[test]
+    fn it_cuts_paths_to_the_budget() {
+        let grid = Grid::filled(10, 1, 1).unwrap();
+        let options = SearchOptions {
+            path_budget: Some(7),
+            ..SearchOptions::default()
+        };
+        let result = search(0, 9, &grid, &options);
+        assert_eq!(result.path, vec![1, 2, 3]);
+        assert_eq!(result.cost, Some(18));
+        assert_eq!(result.remaining_cost, Some(12));
+        assert!(!result.is_partial());
+        let whole = search(0, 9, &grid, &SearchOptions::default());
+        assert_eq!(whole.remaining_cost, Some(0));
+        assert_eq!(whole.path.len(), 9);
+    }
+
+    #[test]
+    fn it_gives_up_on_goals_too_many_steps_away() {
+        let grid = Grid::filled(40, 40, 1).unwrap();
+        let near = SearchOptions {
+            max_steps: Some(30),
+            ..SearchOptions::default()
+        };
+        let far = search(0, 31, &grid, &near);
+        assert_eq!(far.termination, TerminationReason::StepLimit);
+        assert_eq!(far.termination.as_str(), "step_limit");
+        assert_eq!(far.stats.expanded, 0);
+        let reached = search(0, 30 * 40 + 30, &grid, &near);
+        assert_eq!(reached.path.len(), 30);
+        assert_eq!(
+            reached.cost,
+            search(0, 30 * 40 + 30, &grid, &SearchOptions::default()).cost
+        );
+        // the way around the wall takes more steps than the limit leaves
+        let walled = crate::grid![
+            1, 0, 1;
+            1, 0, 1;
+            1, 1, 1;
+        ];
+        let short = SearchOptions {
+            max_steps: Some(3),
+            ..SearchOptions::default()
+        };
+        assert_eq!(
+            search(0, 2, &walled, &short).termination,
+            TerminationReason::StepLimit
+        );
+        let enough = SearchOptions {
+            max_steps: Some(4),
+            ..short
+        };
+        assert_eq!(search(0, 2, &walled, &enough).path, vec![3, 7, 5, 2]);
+    }
+
+    #[test]
+    fn the_start_can_be_charged_for() {
+        let grid = crate::grid![3, 1, 1, 1;];
+        let free = search(0, 3, &grid, &SearchOptions::default());
+        let charged = SearchOptions {
+            pay_for_start: true,
+            path_budget: Some(7),
+            ..SearchOptions::default()
+        };
+        let paid = search(0, 3, &grid, &charged);
+        assert_eq!((free.cost, paid.cost), (Some(6), Some(9)));
+        assert_eq!(paid.path, vec![1, 2]);
+        assert_eq!(paid.remaining_cost, Some(2));
+        let limited = SearchOptions {
+            max_cost: Some(8),
+            ..charged.clone()
+        };
+        assert_eq!(
+            search(0, 3, &grid, &limited).termination,
+            TerminationReason::CostLimit
+        );
+        assert_eq!(search(0, 0, &grid, &charged).cost, Some(3));
+    }
+
+    #[test]
+    fn it_returns_paths_backwards_when_asked() {
+        let grid = Grid::filled(10, 1, 1).unwrap();
+        let backwards = SearchOptions {
+            reverse_path: true,
+            ..SearchOptions::default()
+        };
+        let result = search(0, 9, &grid, &backwards);
+        assert_eq!(result.path, (1..=9).rev().collect::<Vec<_>>());
+        let budget = SearchOptions {
+            path_budget: Some(7),
+            ..backwards.clone()
+        };
+        let result = search(0, 9, &grid, &budget);
+        assert_eq!(result.path, vec![3, 2, 1]);
+        assert_eq!(result.remaining_cost, Some(12));
+        let partial = SearchOptions {
+            max_expansions: Some(3),
+            partial_path: true,
+            ..backwards
+        };
+        assert_eq!(search(0, 9, &grid, &partial).path, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn jitter_spreads_units_over_equally_cheap_paths() {
+        let grid = Grid::filled(8, 8, 1).unwrap();
+        let options = SearchOptions {
+            cardinal_directions: true,
+            ..SearchOptions::default()
+        };
+        let plain = search(0, 63, &grid, &options);
+        let seeded = |seed| SearchOptions {
+            jitter: Some(seed),
+            ..options.clone()
+        };
+        let mut paths = Vec::new();
+        for seed in 0..8 {
+            let result = search(0, 63, &grid, &seeded(seed));
+            assert_eq!(result.cost, plain.cost);
+            assert_eq!(search(0, 63, &grid, &seeded(seed)), result);
+            paths.push(result.path);
+        }
+        paths.sort();
+        paths.dedup();
+        assert!(paths.len() > 1);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn it_stops_at_the_cost_limit() {
+        let grid = Grid::new(vec![
+            1, 1, 1, 1, 1,
+            1, 0, 0, 0, 1,
+            1, 9, 1, 1, 1,
+        ], 5).unwrap();
+        let options = SearchOptions { cardinal_directions: true, ..SearchOptions::default() };
+        let cost = search(0, 12, &grid, &options).cost.unwrap();
+        assert_eq!(cost, 16);
+        let within = SearchOptions { max_cost: Some(cost), ..options.clone() };
+        assert_eq!(search(0, 12, &grid, &within).cost, Some(cost));
+        let below = SearchOptions { max_cost: Some(cost - 1), partial_path: true, ..options };
+        let result = search(0, 12, &grid, &below);
+        assert_eq!(result.termination, TerminationReason::CostLimit);
+        assert_eq!(result.cost, None);
+        assert!(result.is_partial());
+        assert_eq!(result.path.last(), Some(&result.closest));
+    }
+
+    #[test]
+    fn weighted_searches_keep_to_the_cost_limit() {
+        let grid = Grid::filled(10, 1, 1).unwrap();
+        let within = SearchOptions {
+            max_cost: Some(18),
+            ..SearchOptions::default()
+        };
+        let below = SearchOptions {
+            max_cost: Some(17),
+            ..SearchOptions::default()
+        };
+        let weighted = crate::Weighted::new(Manhattan, 3, 1);
+        let found = search_with_heuristic(0, 9, &grid, &within, &weighted);
+        assert_eq!(
+            (found.cost, found.termination),
+            (Some(18), TerminationReason::GoalReached)
+        );
+        let over = search_with_heuristic(0, 9, &grid, &below, &weighted);
+        assert_eq!(
+            (over.cost, over.termination),
+            (None, TerminationReason::CostLimit)
+        );
+        let dynamic = |options: SearchOptions| SearchOptions {
+            weighting: Weighting::Dynamic {
+                epsilon_percent: 200,
+            },
+            ..options
+        };
+        assert_eq!(search(0, 9, &grid, &dynamic(within)).cost, Some(18));
+        assert_eq!(
+            search(0, 9, &grid, &dynamic(below)).termination,
+            TerminationReason::CostLimit
+        );
+    }
+
+    /// Claims fewer cells than its width and height hold.
+    struct Shrunk;
+
+    impl GridSource for Shrunk {
+        fn width(&self) -> u32 {
+            3
+        }
+
+        fn height(&self) -> u32 {
+            3
+        }
+
+        fn cost(&self, _index: u32) -> u32 {
+            1
+        }
+
+        fn len(&self) -> u32 {
+            5
+        }
+    }
+
+    #[test]
+    fn broken_grids_stop_the_search_instead_of_panicking() {
+        let options = SearchOptions {
+            partial_path: true,
+            ..SearchOptions::default()
+        };
+        let result = search(4, 0, &Shrunk, &options);
+        let error = InternalError::NeighborOutOfBounds {
+            cell: 4,
+            neighbor: 5,
+        };
+        assert_eq!(result.termination, TerminationReason::Internal(error));
+        assert_eq!(result.termination.as_str(), "internal_error");
+        assert_eq!((result.path, result.cost), (Vec::new(), None));
+    }
+
+    #[test]
+    fn it_reports_memory_high_water_marks() {
+        let corridor = Grid::filled(5, 1, 1).unwrap();
+        let stats = search(0, 4, &corridor, &SearchOptions::default()).stats;
+        assert_eq!((stats.peak_frontier, stats.stored, stats.bytes), (1, 9, 80));
+        let open = Grid::filled(5, 5, 1).unwrap();
+        let stats = search(12, 0, &open, &SearchOptions::default()).stats;
+        assert_eq!(stats.peak_frontier, 12);
+        let weighted = SearchOptions {
+            weighting: Weighting::Dynamic {
+                epsilon_percent: 50,
+            },
+            backend: Backend::Sparse,
+            ..SearchOptions::default()
+        };
+        let stats = search(0, 4, &corridor, &weighted).stats;
+        assert_eq!(stats.stored, 13);
+    }
+
+    #[test]
+    fn it_times_phases_when_asked() {
+        let grid = Grid::filled(40, 40, 1).unwrap();
+        let untimed = search(0, 1599, &grid, &SearchOptions::default());
+        assert_eq!(untimed.stats.timings, None);
+        let options = SearchOptions {
+            time_phases: true,
+            ..SearchOptions::default()
+        };
+        let started = Instant::now();
+        let timed = search(0, 1599, &grid, &options);
+        let elapsed = started.elapsed();
+        let timings = timed.stats.timings.unwrap();
+        assert!(timings.total() > Duration::from_secs(0));
+        assert!(timings.total() <= elapsed);
+        assert_eq!(timed.path, untimed.path);
+        assert_eq!(timed.stats.expanded, untimed.stats.expanded);
+    }
+
+    #[test]
+    fn dynamic_weighting_stays_within_its_bound() {
+        let mut rng = crate::Rng::new(3);
+        let grid = rng.grid(50, 50, 20, 2);
+        let weighted = SearchOptions {
+            weighting: Weighting::Dynamic {
+                epsilon_percent: 200,
+            },
+            ..SearchOptions::default()
+        };
+        let mut fewer = 0;
+        for _ in 0..30 {
+            let start = rng.below(grid.len());
+            let end = rng.below(grid.len());
+            let exact = search(start, end, &grid, &SearchOptions::default());
+            let fast = search(start, end, &grid, &weighted);
+            assert_eq!(exact.bound_percent, 100);
+            assert!(fast.bound_percent <= 300);
+            assert_eq!(exact.cost.is_some(), fast.cost.is_some());
+            if let (Some(exact_cost), Some(fast_cost)) = (exact.cost, fast.cost) {
+                assert!(fast_cost >= exact_cost);
+                assert!(fast_cost as u64 * 100 <= exact_cost as u64 * fast.bound_percent as u64);
+                fewer += (fast.stats.expanded < exact.stats.expanded) as u32;
+            }
+        }
+        assert!(fewer > 15);
+    }
+
+    #[test]
+    fn weighted_heuristics_prove_how_close_they_came() {
+        let mut rng = crate::Rng::new(4);
+        let grid = rng.grid(40, 40, 20, 4);
+        let heuristic = crate::Weighted::new(Manhattan, 5, 2);
+        assert_eq!(heuristic.inflation_percent(), 250);
+        let mut tightened = 0;
+        for _ in 0..30 {
+            let (start, end) = (rng.below(grid.len()), rng.below(grid.len()));
+            let exact = search(start, end, &grid, &SearchOptions::default());
+            let fast =
+                search_with_heuristic(start, end, &grid, &SearchOptions::default(), &heuristic);
+            assert!(fast.bound_percent >= 100 && fast.bound_percent <= 250);
+            if let (Some(exact_cost), Some(fast_cost)) = (exact.cost, fast.cost) {
+                assert!(fast_cost as u64 * 100 <= exact_cost as u64 * fast.bound_percent as u64);
+                tightened += (fast.bound_percent < 250) as u32;
+            } else {
+                assert_eq!(fast.bound_percent, 250);
+            }
+        }
+        assert!(tightened > 15);
+        let both = SearchOptions {
+            weighting: Weighting::Dynamic {
+                epsilon_percent: 100,
+            },
+            ..SearchOptions::default()
+        };
+        assert_eq!(
+            search_with_heuristic(0, 0, &grid, &both, &heuristic).bound_percent,
+            100
+        );
+        assert_eq!(
+            search_with_heuristic(0, 1600, &grid, &both, &heuristic).bound_percent,
+            500
+        );
+    }
+
+    #[test]
+    fn it_returns_partial_paths_when_asked() {
+        let grid = Grid::filled(10, 1, 1).unwrap();
+        let options = SearchOptions {
+            max_expansions: Some(3),
+            partial_path: true,
+            ..SearchOptions::default()
+        };
+        let result = search(0, 9, &grid, &options);
+        assert_eq!(result.termination, TerminationReason::ExpansionLimit);
+        assert_eq!(result.path, vec![1, 2, 3]);
+        assert!(result.is_partial());
+        assert_eq!(result.path, search(0, 9, &grid, &options).path);
+    }
+
+    #[test]
+    fn it_records_the_expansion_order() {
+        let grid = Grid::filled(4, 1, 1).unwrap();
+        let options = SearchOptions {
+            record_trace: true,
+            ..SearchOptions::default()
+        };
+        let trace = search(0, 3, &grid, &options).trace.unwrap();
+        assert_eq!((trace.width, trace.start, trace.goal), (4, 0, 3));
+        let expansions = &trace.expansions;
+        assert_eq!(expansions.len(), 4);
+        assert_eq!(
+            expansions[0],
+            Expansion {
+                cell: 0,
+                g: 0,
+                f: 3,
+                parent: None
+            }
+        );
+        assert_eq!(
+            expansions[3],
+            Expansion {
+                cell: 3,
+                g: 6,
+                f: 6,
+                parent: Some(2)
+            }
+        );
+    }
+
+    /// FNV-1a over every path, so a change to any of them changes the result.
+    fn fingerprint(paths: &[Vec<u32>]) -> u64 {
+        let mut hash = 0xcbf2_9ce4_8422_2325u64;
+        for path in paths {
+            for &cell in path.iter().chain(std::iter::once(&u32::MAX)) {
+                for byte in cell.to_le_bytes().iter() {
+                    hash ^= *byte as u64;
+                    hash = hash.wrapping_mul(0x0100_0000_01b3);
+                }
+            }
+        }
+        hash
+    }
+
+    #[test]
+    fn ties_are_broken_the_same_way_everywhere() {
+        let grid = Grid::filled(5, 5, 1).unwrap();
+        assert_eq!(
+            astar_grid(0, 24, &grid, true),
+            vec![5, 10, 15, 20, 21, 22, 23, 24]
+        );
+        assert_eq!(
+            astar_grid(4, 20, &grid, true),
+            vec![9, 14, 19, 24, 23, 22, 21, 20]
+        );
+        assert_eq!(astar_grid(0, 14, &grid, false), vec![6, 12, 13, 14]);
+        assert_eq!(astar_grid(24, 2, &grid, false), vec![19, 14, 8, 2]);
+    }
+
+    #[test]
+    fn golden_paths_on_seeded_grids() {
+        let mut rng = crate::Rng::new(5);
+        let mut paths = Vec::new();
+        for _ in 0..200 {
+            let width = rng.range(1, 20);
+            let height = rng.range(1, 20);
+            let grid = rng.grid(width, height, 25, 5);
+            let start = rng.below(grid.len());
+            let end = rng.below(grid.len());
+            paths.push(astar_grid(start, end, &grid, rng.chance(1, 2)));
+        }
+        assert_eq!(paths.iter().filter(|path| !path.is_empty()).count(), 124);
+        assert_eq!(fingerprint(&paths), 9_291_308_639_833_307_786);
+    }
+}
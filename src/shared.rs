@@ -0,0 +1,179 @@
+use crate::{Cost, Grid, GridSource};
+use std::sync::{Arc, RwLock};
+
+/// A grid edited by one thread and searched by many, which only see edits
+/// once they are published.
+///
+/// Edits go to a private draft. [`SharedGrid::publish`] copies the draft into
+/// a new immutable [`Snapshot`] and hands it to every [`GridReader`]; searches
+/// already running keep the snapshot they started with, so none of them ever
+/// sees half an update.
+#[derive(Debug)]
+pub struct SharedGrid<T = u32> {
+    draft: Grid<T>,
+    dirty: bool,
+    published: Arc<RwLock<Snapshot<T>>>,
+}
+
+impl<T: Clone> SharedGrid<T> {
+    /// Publishes `grid` as the first snapshot, at epoch `0`.
+    pub fn new(grid: Grid<T>) -> Self {
+        let snapshot = Snapshot {
+            grid: Arc::new(grid.clone()),
+            epoch: 0,
+        };
+        SharedGrid {
+            draft: grid,
+            dirty: false,
+            published: Arc::new(RwLock::new(snapshot)),
+        }
+    }
+
+    /// The draft, with every edit made so far, published or not.
+    pub fn draft(&self) -> &Grid<T> {
+        &self.draft
+    }
+
+    /// Edits the draft. Readers don't see the edits until the next publish.
+    pub fn draft_mut(&mut self) -> &mut Grid<T> {
+        self.dirty = true;
+        &mut self.draft
+    }
+
+    /// Replaces a cell of the draft, returning the previous one, or `None`
+    /// when the coordinates are outside the grid.
+    pub fn set(&mut self, x: u32, y: u32, value: T) -> Option<T> {
+        self.draft_mut().set(x, y, value)
+    }
+
+    /// Whether the draft was edited since the last publish.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Makes the draft the snapshot readers get from now on, returning its
+    /// epoch. Does nothing when the draft is unchanged.
+    pub fn publish(&mut self) -> u64 {
+        let mut published = self
+            .published
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if self.dirty {
+            *published = Snapshot {
+                grid: Arc::new(self.draft.clone()),
+                epoch: published.epoch + 1,
+            };
+            self.dirty = false;
+        }
+        published.epoch
+    }
+
+    /// A handle other threads can take snapshots through.
+    pub fn reader(&self) -> GridReader<T> {
+        GridReader {
+            published: Arc::clone(&self.published),
+        }
+    }
+}
+
+/// Takes snapshots of a [`SharedGrid`] from any thread.
+#[derive(Debug)]
+pub struct GridReader<T = u32> {
+    published: Arc<RwLock<Snapshot<T>>>,
+}
+
+impl<T> Clone for GridReader<T> {
+    fn clone(&self) -> Self {
+        GridReader {
+            published: Arc::clone(&self.published),
+        }
+    }
+}
+
+impl<T> GridReader<T> {
+    /// The latest published snapshot. This only briefly locks to copy a
+    /// pointer, never while a search runs.
+    pub fn snapshot(&self) -> Snapshot<T> {
+        self.published
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// The epoch of the latest published snapshot.
+    pub fn epoch(&self) -> u64 {
+        self.published
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .epoch
+    }
+}
+
+/// One published state of a [`SharedGrid`], which never changes.
+#[derive(Debug)]
+pub struct Snapshot<T = u32> {
+    grid: Arc<Grid<T>>,
+    epoch: u64,
+}
+
+impl<T> Clone for Snapshot<T> {
+    fn clone(&self) -> Self {
+        Snapshot {
+            grid: Arc::clone(&self.grid),
+            epoch: self.epoch,
+        }
+    }
+}
+
+impl<T> Snapshot<T> {
+    pub fn grid(&self) -> &Arc<Grid<T>> {
+        &self.grid
+    }
+
+    /// How many publishes with edits came before this snapshot.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+}
+
+impl<T: Cost> GridSource for Snapshot<T> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.grid.width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.grid.height()
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        self.grid.cost(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, SearchOptions};
+    use std::thread;
+
+    #[test]
+    fn readers_only_see_published_edits() {
+        let mut shared = SharedGrid::new(Grid::filled(5, 1, 1).unwrap());
+        let reader = shared.reader();
+        let before = reader.snapshot();
+        shared.set(2, 0, 0);
+        assert!(shared.is_dirty());
+        let options = SearchOptions::default();
+        assert!(search(0, 4, &reader.snapshot(), &options).cost.is_some());
+        assert_eq!(shared.publish(), 1);
+        assert_eq!(shared.publish(), 1);
+        let after = thread::spawn(move || reader.snapshot()).join().unwrap();
+        assert_eq!(after.epoch(), 1);
+        assert_eq!(search(0, 4, &after, &options).cost, None);
+        assert_eq!(before.epoch(), 0);
+        assert!(search(0, 4, &before, &options).cost.is_some());
+    }
+}
@@ -0,0 +1,172 @@
+//! Batched kernels for the innermost loop of a search: estimating the
+//! neighbors of an expanded cell, and finding which of a run of cells can be
+//! walked on. With the `simd` feature on x86_64 they work four cells at a
+//! time with SSE2, which every x86_64 processor has; everywhere else they
+//! fall back to the scalar loops, which give the same answers.
+
+use crate::{Heuristic, Manhattan};
+
+/// Writes the [`Manhattan`] estimate from each of `neighbors`, which must
+/// all neighbor `cell`, to `goal` into `out`.
+#[inline]
+pub(crate) fn manhattan_neighbors(
+    cell: u32,
+    neighbors: &[u32],
+    goal: u32,
+    width: u32,
+    out: &mut [u32],
+) {
+    // on grids this narrow the offset to a neighbor doesn't say which one
+    // it is, so only the scalar version can tell them apart
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if (3..=i32::MAX as u32).contains(&width) {
+        return sse2::manhattan_neighbors(cell, neighbors, goal, width, out);
+    }
+    let _ = cell;
+    scalar::manhattan_neighbors(neighbors, goal, width, out)
+}
+
+/// A mask of which of up to 32 `costs` can be walked on, the first in the
+/// lowest bit.
+#[inline]
+pub(crate) fn walkable(costs: &[u32]) -> u32 {
+    debug_assert!(costs.len() <= 32);
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    return sse2::walkable(costs);
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    scalar::walkable(costs)
+}
+
+mod scalar {
+    use super::*;
+
+    #[inline(always)]
+    pub(super) fn manhattan_neighbors(neighbors: &[u32], goal: u32, width: u32, out: &mut [u32]) {
+        for (&neighbor, out) in neighbors.iter().zip(out) {
+            *out = Manhattan.estimate(neighbor, goal, width);
+        }
+    }
+
+    #[cfg_attr(all(feature = "simd", target_arch = "x86_64"), allow(dead_code))]
+    #[inline(always)]
+    pub(super) fn walkable(costs: &[u32]) -> u32 {
+        costs
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (lane, &cost)| mask | ((cost > 0) as u32) << lane)
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use super::scalar;
+    use std::arch::x86_64::*;
+
+    // SAFETY for every block below: SSE2 is part of the x86_64 baseline, and
+    // all loads and stores are unaligned ones within a four-lane chunk.
+
+    /// Recovers each neighbor's offset from the difference of indices, which
+    /// on grids at least three wide is unique to it, so that no lane needs a
+    /// division.
+    pub(super) fn manhattan_neighbors(
+        cell: u32,
+        neighbors: &[u32],
+        goal: u32,
+        width: u32,
+        out: &mut [u32],
+    ) {
+        let chunks = neighbors.len().min(out.len()) / 4 * 4;
+        unsafe {
+            let origin = _mm_set1_epi32(cell as i32);
+            let width_lanes = _mm_set1_epi32(width as i32);
+            let x = _mm_set1_epi32((cell % width) as i32);
+            let y = _mm_set1_epi32((cell / width) as i32);
+            let goal_x = _mm_set1_epi32((goal % width) as i32);
+            let goal_y = _mm_set1_epi32((goal / width) as i32);
+            let one = _mm_set1_epi32(1);
+            let minus_one = _mm_set1_epi32(-1);
+            for start in (0..chunks).step_by(4) {
+                let lanes = _mm_loadu_si128(neighbors[start..].as_ptr() as *const __m128i);
+                let offset = _mm_sub_epi32(lanes, origin);
+                // all ones where the neighbor is on the row below or above
+                let below = _mm_cmpgt_epi32(offset, one);
+                let above = _mm_cmplt_epi32(offset, minus_one);
+                let dy = _mm_sub_epi32(above, below);
+                let row = _mm_sub_epi32(
+                    _mm_and_si128(below, width_lanes),
+                    _mm_and_si128(above, width_lanes),
+                );
+                let dx = _mm_sub_epi32(offset, row);
+                let h = _mm_add_epi32(
+                    abs(_mm_sub_epi32(_mm_add_epi32(x, dx), goal_x)),
+                    abs(_mm_sub_epi32(_mm_add_epi32(y, dy), goal_y)),
+                );
+                _mm_storeu_si128(out[start..].as_mut_ptr() as *mut __m128i, h);
+            }
+        }
+        scalar::manhattan_neighbors(&neighbors[chunks..], goal, width, &mut out[chunks..]);
+    }
+
+    /// SSE2 has no absolute value, so flip negative lanes by hand.
+    #[inline(always)]
+    unsafe fn abs(lanes: __m128i) -> __m128i {
+        let sign = _mm_srai_epi32(lanes, 31);
+        _mm_sub_epi32(_mm_xor_si128(lanes, sign), sign)
+    }
+
+    pub(super) fn walkable(costs: &[u32]) -> u32 {
+        let chunks = costs.len() / 4 * 4;
+        let mut mask = 0;
+        unsafe {
+            let zero = _mm_setzero_si128();
+            for start in (0..chunks).step_by(4) {
+                let lanes = _mm_loadu_si128(costs[start..].as_ptr() as *const __m128i);
+                let walls = _mm_movemask_ps(_mm_castsi128_ps(_mm_cmpeq_epi32(lanes, zero)));
+                mask |= (!walls as u32 & 0b1111) << start;
+            }
+        }
+        mask | scalar::walkable(&costs[chunks..])
+            .checked_shl(chunks as u32)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_neighbor_coords, Grid, GridSource, Rng};
+
+    #[test]
+    fn neighbor_estimates_match_the_heuristic() {
+        let mut rng = Rng::new(1);
+        for &width in &[1, 2, 3, 4, 7, 16] {
+            let height = rng.range(1, 6);
+            let grid = Grid::<u32>::filled(width, height, 1).unwrap();
+            for cell in 0..grid.len() {
+                let goal = rng.range(0, grid.len());
+                for &cardinal in &[false, true] {
+                    let neighbors = get_neighbor_coords(cell, &grid, cardinal);
+                    let mut out = [0; 8];
+                    manhattan_neighbors(cell, &neighbors, goal, width, &mut out);
+                    for (&neighbor, &h) in neighbors.iter().zip(&out) {
+                        assert_eq!(h, Manhattan.estimate(neighbor, goal, width));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn walkable_masks_every_lane() {
+        let mut rng = Rng::new(2);
+        for len in 0..=32 {
+            let costs: Vec<u32> = (0..len).map(|_| rng.range(0, 3)).collect();
+            let expected = scalar::walkable(&costs);
+            assert_eq!(walkable(&costs), expected);
+            assert_eq!(
+                expected.count_ones() as usize,
+                costs.iter().filter(|&&c| c > 0).count()
+            );
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{
+    get_neighbor_coords, search, search_with_heuristic, GridSource, Heuristic, Manhattan,
+    PivotHeuristic, SearchOptions, SearchResult, SearchStats, TerminationReason,
+};
+use std::collections::VecDeque;
+
+/// Maps with at least this many cells get pivots when their costs vary.
+const LARGE_MAP: u32 = 256 * 256;
+/// Pivots built by [`Strategy::Pivots`].
+const PIVOTS: usize = 8;
+/// Queries shorter than this skip the pivots; plain A* is already quick.
+const SHORT_QUERY: u32 = 32;
+/// Uniform maps with at least this share of walls, in percent, are treated
+/// as mazes, where the heuristic buys little over a breadth-first search.
+const MAZE_WALLS: u32 = 30;
+
+/// The ways a [`Solver`] can answer queries. The choice made by
+/// [`Solver::auto`] may change between releases as strategies are added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Breadth-first search, which skips all cost bookkeeping. Only chosen
+    /// for cardinal movement over walkable cells that all cost the same,
+    /// where it finds paths exactly as cheap as A*.
+    BreadthFirst,
+    AStar,
+    /// A* guided by a [`PivotHeuristic`], falling back to plain A* for short
+    /// queries.
+    Pivots,
+}
+
+/// What [`Solver::auto`] looked at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapStats {
+    pub cells: u32,
+    pub walls: u32,
+    /// The cost shared by every walkable cell, if they all cost the same.
+    pub uniform_cost: Option<u32>,
+}
+
+impl MapStats {
+    pub fn new<G: GridSource + ?Sized>(grid: &G) -> Self {
+        let mut walls = 0;
+        let mut uniform_cost = None;
+        let mut uniform = true;
+        for index in 0..grid.len() {
+            match grid.cost(index) {
+                0 => walls += 1,
+                cost if uniform_cost.is_none() => uniform_cost = Some(cost),
+                cost => uniform &= uniform_cost == Some(cost),
+            }
+        }
+        MapStats {
+            cells: grid.len(),
+            walls,
+            uniform_cost: uniform_cost.filter(|_| uniform),
+        }
+    }
+
+    /// The share of walls, in percent.
+    pub fn wall_percent(&self) -> u32 {
+        if self.cells == 0 {
+            0
+        } else {
+            (self.walls as u64 * 100 / self.cells as u64) as u32
+        }
+    }
+}
+
+/// Answers path queries on one grid with a strategy chosen for it.
+///
+/// All strategies find equally cheap paths, though not necessarily the same
+/// one among several that tie. Anything precomputed is only valid while the
+/// grid's costs don't change.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    strategy: Strategy,
+    cardinal_directions: bool,
+    stats: MapStats,
+    pivots: Option<PivotHeuristic>,
+}
+
+impl Solver {
+    /// Inspects the grid's size, wall density and cost uniformity to pick a
+    /// strategy.
+    pub fn auto<G: GridSource + ?Sized>(grid: &G, cardinal_directions: bool) -> Self {
+        let stats = MapStats::new(grid);
+        let strategy = if cardinal_directions
+            && stats.uniform_cost.is_some()
+            && stats.wall_percent() >= MAZE_WALLS
+        {
+            Strategy::BreadthFirst
+        } else if stats.cells >= LARGE_MAP && stats.uniform_cost.is_none() {
+            Strategy::Pivots
+        } else {
+            Strategy::AStar
+        };
+        Self::build(grid, strategy, cardinal_directions, stats)
+    }
+
+    /// Uses `strategy` regardless of the grid. Asking for
+    /// [`Strategy::BreadthFirst`] where it wouldn't find the cheapest paths
+    /// gives [`Strategy::AStar`] instead.
+    pub fn with_strategy<G: GridSource + ?Sized>(
+        grid: &G,
+        strategy: Strategy,
+        cardinal_directions: bool,
+    ) -> Self {
+        let stats = MapStats::new(grid);
+        let strategy = match strategy {
+            Strategy::BreadthFirst if !cardinal_directions || stats.uniform_cost.is_none() => {
+                Strategy::AStar
+            }
+            strategy => strategy,
+        };
+        Self::build(grid, strategy, cardinal_directions, stats)
+    }
+
+    fn build<G: GridSource + ?Sized>(
+        grid: &G,
+        strategy: Strategy,
+        cardinal_directions: bool,
+        stats: MapStats,
+    ) -> Self {
+        let pivots = if strategy == Strategy::Pivots {
+            Some(PivotHeuristic::new(
+                grid,
+                PIVOTS,
+                cardinal_directions,
+                grid.len() as u64,
+            ))
+        } else {
+            None
+        };
+        Solver {
+            strategy,
+            cardinal_directions,
+            stats,
+            pivots,
+        }
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    pub fn stats(&self) -> &MapStats {
+        &self.stats
+    }
+
+    /// Finds the cheapest path from `start` to `end` on `grid`, which must be
+    /// the grid the solver was built for.
+    pub fn find_path<G: GridSource + ?Sized>(
+        &self,
+        start: u32,
+        end: u32,
+        grid: &G,
+    ) -> SearchResult {
+        let options = SearchOptions {
+            cardinal_directions: self.cardinal_directions,
+            ..SearchOptions::default()
+        };
+        match (&self.pivots, self.strategy) {
+            (_, Strategy::BreadthFirst) => breadth_first(start, end, grid),
+            (Some(pivots), Strategy::Pivots)
+                if start < grid.len()
+                    && end < grid.len()
+                    && Manhattan.estimate(start, end, grid.width()) >= SHORT_QUERY =>
+            {
+                search_with_heuristic(start, end, grid, &options, pivots)
+            }
+            _ => search(start, end, grid, &options),
+        }
+    }
+}
+
+/// Cardinal breadth-first search, for grids where every walkable cell costs
+/// the same.
+fn breadth_first<G: GridSource + ?Sized>(start: u32, end: u32, grid: &G) -> SearchResult {
+    let len = grid.len();
+    let mut result = SearchResult {
+        path: Vec::new(),
+        cost: None,
+        remaining_cost: None,
+        bound_percent: 100,
+        termination: TerminationReason::Exhausted,
+        closest: start,
+        stats: SearchStats::default(),
+        trace: None,
+    };
+    if start >= len || end >= len {
+        return result;
+    }
+    let width = grid.width();
+    let mut came_from = vec![u32::MAX; len as usize];
+    came_from[start as usize] = start;
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    let mut closest = (Manhattan.estimate(start, end, width), start);
+    result.stats.reached = 1;
+    while let Some(current) = queue.pop_front() {
+        if current == end {
+            result.termination = TerminationReason::GoalReached;
+            break;
+        }
+        result.stats.expanded += 1;
+        for &neighbor in get_neighbor_coords(current, grid, true).iter() {
+            if came_from[neighbor as usize] == u32::MAX {
+                came_from[neighbor as usize] = current;
+                result.stats.reached += 1;
+                closest = closest.min((Manhattan.estimate(neighbor, end, width), neighbor));
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    result.closest = closest.1;
+    if result.termination == TerminationReason::GoalReached {
+        let mut cell = end;
+        let mut cost = 0u32;
+        while cell != start {
+            result.path.push(cell);
+            cost = cost.saturating_add(grid.cost(cell).saturating_add(1));
+            cell = came_from[cell as usize];
+        }
+        result.path.reverse();
+        result.cost = Some(cost);
+        result.remaining_cost = Some(0);
+        result.closest = end;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rng;
+
+    #[test]
+    fn it_picks_a_strategy_from_the_map() {
+        let mut rng = Rng::new(1);
+        let maze = rng.grid(40, 40, 35, 1);
+        assert_eq!(Solver::auto(&maze, true).strategy(), Strategy::BreadthFirst);
+        assert_eq!(Solver::auto(&maze, false).strategy(), Strategy::AStar);
+        let weighted = rng.grid(40, 40, 10, 5);
+        assert_eq!(Solver::auto(&weighted, true).strategy(), Strategy::AStar);
+        let large = rng.grid(256, 256, 10, 5);
+        assert_eq!(Solver::auto(&large, false).strategy(), Strategy::Pivots);
+        assert_eq!(
+            Solver::with_strategy(&weighted, Strategy::BreadthFirst, true).strategy(),
+            Strategy::AStar
+        );
+    }
+
+    #[test]
+    fn every_strategy_finds_the_cheapest_paths() {
+        let mut rng = Rng::new(2);
+        for &(walls, max_cost) in [(35, 1), (20, 1), (15, 6)].iter() {
+            let grid = rng.grid(48, 40, walls, max_cost);
+            for &cardinal_directions in [true, false].iter() {
+                let options = SearchOptions {
+                    cardinal_directions,
+                    ..SearchOptions::default()
+                };
+                for &strategy in [Strategy::BreadthFirst, Strategy::AStar, Strategy::Pivots].iter()
+                {
+                    let solver = Solver::with_strategy(&grid, strategy, cardinal_directions);
+                    for _ in 0..20 {
+                        let start = rng.below(grid.len());
+                        let end = rng.below(grid.len());
+                        let expected = search(start, end, &grid, &options);
+                        let result = solver.find_path(start, end, &grid);
+                        assert_eq!(result.cost, expected.cost);
+                        assert_eq!(result.termination, expected.termination);
+                    }
+                }
+            }
+        }
+    }
+}
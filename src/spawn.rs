@@ -0,0 +1,103 @@
+use crate::{DijkstraMap, GridSource, Regions, Rng};
+
+/// Where [`random_reachable_cell`] may pick from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpawnConstraints {
+    /// Only cells whose cheapest path from the reference cell costs at
+    /// least this much, to keep spawns out of sight.
+    pub min_distance: Option<u32>,
+    /// Only cells whose cheapest path from the reference cell costs at most
+    /// this much.
+    pub max_distance: Option<u32>,
+}
+
+/// A walkable cell other than `from` that a path from `from` leads to,
+/// picked uniformly from those `constraints` allow, or `None` when there is
+/// none. `regions` must be up to date with `grid`; its movement mode is the
+/// one paths are measured in.
+///
+/// Without distance limits the pick only looks at region labels, so a cell
+/// walled off from `from` can never come up, however the map is shaped.
+/// With them, path costs come from one Dijkstra flood out of `from`.
+pub fn random_reachable_cell<G: GridSource + ?Sized>(
+    grid: &G,
+    regions: &Regions,
+    from: u32,
+    rng: &mut Rng,
+    constraints: &SpawnConstraints,
+) -> Option<u32> {
+    let region = regions.region(from)?;
+    if constraints.min_distance.is_none() && constraints.max_distance.is_none() {
+        let count = regions.size(region) - 1;
+        if count == 0 {
+            return None;
+        }
+        let pick = rng.below(count) as usize;
+        return regions.cells(region).filter(|&cell| cell != from).nth(pick);
+    }
+    let map = DijkstraMap::new(grid, &[from], regions.cardinal_directions());
+    let min = constraints.min_distance.unwrap_or(0);
+    let max = constraints.max_distance.unwrap_or(u32::MAX);
+    let allowed = |cell: u32| {
+        cell != from
+            && map
+                .distance(cell)
+                .is_some_and(|distance| (min..=max).contains(&distance))
+    };
+    let count = regions.cells(region).filter(|&cell| allowed(cell)).count() as u32;
+    if count == 0 {
+        return None;
+    }
+    let pick = rng.below(count) as usize;
+    regions
+        .cells(region)
+        .filter(|&cell| allowed(cell))
+        .nth(pick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    fn it_only_spawns_where_a_path_leads() {
+        let mut grid = Grid::filled(10, 10, 1).unwrap();
+        // a walled-off pocket in the corner
+        for i in 0..4 {
+            grid.set(3, i, 0);
+            grid.set(i, 3, 0);
+        }
+        let regions = Regions::new(&grid, true);
+        let mut rng = Rng::new(1);
+        let anywhere = SpawnConstraints::default();
+        for _ in 0..200 {
+            let cell = random_reachable_cell(&grid, &regions, 99, &mut rng, &anywhere).unwrap();
+            assert!(regions.connected(99, cell) && cell != 99);
+        }
+        let near = SpawnConstraints {
+            min_distance: Some(4),
+            max_distance: Some(6),
+        };
+        let map = DijkstraMap::new(&grid, &[99], true);
+        for _ in 0..50 {
+            let cell = random_reachable_cell(&grid, &regions, 99, &mut rng, &near).unwrap();
+            assert!((4..=6).contains(&map.distance(cell).unwrap()));
+        }
+        let far = SpawnConstraints {
+            min_distance: Some(1000),
+            ..anywhere
+        };
+        assert_eq!(
+            random_reachable_cell(&grid, &regions, 99, &mut rng, &far),
+            None
+        );
+        assert_eq!(
+            random_reachable_cell(&grid, &regions, 3, &mut rng, &anywhere),
+            None
+        );
+        let pocket = random_reachable_cell(&grid, &regions, 0, &mut rng, &anywhere).unwrap();
+        assert!(pocket % 10 < 3 && pocket / 10 < 3);
+    }
+}
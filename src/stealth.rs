@@ -0,0 +1,113 @@
+use crate::{
+    field_of_view, search, CostLayer, GridSource, Layered, Observer, SearchOptions, SearchResult,
+};
+
+/// How many observers see each cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExposureMap {
+    counts: Vec<u32>,
+}
+
+impl ExposureMap {
+    pub fn new<G: GridSource + ?Sized>(grid: &G, observers: &[Observer]) -> Self {
+        let mut counts = vec![0; grid.len() as usize];
+        for observer in observers {
+            for cell in field_of_view(grid, observer) {
+                counts[cell as usize] += 1;
+            }
+        }
+        ExposureMap { counts }
+    }
+
+    /// Observers that can see `index`, `0` outside the map.
+    pub fn exposure(&self, index: u32) -> u32 {
+        self.counts.get(index as usize).copied().unwrap_or(0)
+    }
+
+    /// Weighs exposure by `alpha` as a [`CostLayer`].
+    pub fn weighted(&self, alpha: u32) -> WeightedExposure<'_> {
+        WeightedExposure { map: self, alpha }
+    }
+}
+
+/// Adds `alpha` per observer who can see a cell to the cost of entering it.
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedExposure<'a> {
+    map: &'a ExposureMap,
+    alpha: u32,
+}
+
+impl<'a> CostLayer for WeightedExposure<'a> {
+    #[inline(always)]
+    fn extra_cost(&self, index: u32) -> u32 {
+        self.map.exposure(index).saturating_mul(self.alpha)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StealthPath {
+    /// The search over the grid with exposure costs added, so
+    /// `result.cost` is length plus `alpha` times exposure.
+    pub result: SearchResult,
+    /// How many observers see each cell of `result.path`.
+    pub exposure: Vec<u32>,
+}
+
+impl StealthPath {
+    pub fn total_exposure(&self) -> u32 {
+        self.exposure.iter().sum()
+    }
+}
+
+/// Finds the path minimizing its cost plus `alpha` for every observer that
+/// sees each cell entered. An `alpha` of `0` gives the plain shortest path;
+/// comparing [`StealthPath::total_exposure`] across a few values lets an AI
+/// decide whether sneaking is worth the detour.
+pub fn stealth_path<G: GridSource + ?Sized>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    exposure: &ExposureMap,
+    alpha: u32,
+    options: &SearchOptions,
+) -> StealthPath {
+    let layered = Layered::new(grid, exposure.weighted(alpha));
+    let result = search(start, end, &layered, options);
+    let exposure = result
+        .path
+        .iter()
+        .map(|&cell| exposure.exposure(cell))
+        .collect();
+    StealthPath { result, exposure }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    #[test]
+    #[rustfmt::skip]
+    fn sneaking_trades_length_for_cover() {
+        let grid = Grid::new(vec![
+            1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1,
+            0, 1, 0, 0, 0, 1, 0,
+            1, 1, 1, 1, 1, 1, 1,
+        ], 7).unwrap();
+        // a guard at the top, watching the near gap
+        let observers = [Observer::new(1, 0, 3).facing((0, 1), 60.0)];
+        let exposure = ExposureMap::new(&grid, &observers);
+        assert_eq!(exposure.exposure(15), 1);
+        assert_eq!(exposure.exposure(19), 0);
+        let options = SearchOptions { cardinal_directions: true, ..SearchOptions::default() };
+        let sprint = stealth_path(9, 23, &grid, &exposure, 0, &options);
+        assert!(sprint.result.path.contains(&15));
+        assert!(sprint.total_exposure() > 0);
+        let sneak = stealth_path(9, 23, &grid, &exposure, 20, &options);
+        assert!(sneak.result.path.contains(&19));
+        assert_eq!(sneak.total_exposure(), 0);
+        assert_eq!(sneak.exposure.len(), sneak.result.path.len());
+    }
+}
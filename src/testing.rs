@@ -0,0 +1,175 @@
+//! Helpers for property testing searches, enabled by the `testing` feature.
+//!
+//! The strategies generate random grids and queries for `proptest`, and the
+//! checkers compare a path against the crate's movement rules and against
+//! the exact costs of a [`DijkstraMap`]. They work with any [`GridSource`],
+//! so they can be pointed at custom grid implementations too.
+
+use crate::{astar_grid, get_neighbor_coords, step_cost, DijkstraMap, Grid, GridSource};
+use proptest::prelude::*;
+use std::fmt;
+
+/// Grids between `1 * 1` and `max_width * max_height` cells, with roughly a
+/// quarter of the cells walls and the rest costing between 1 and 9.
+pub fn grids(max_width: u32, max_height: u32) -> impl Strategy<Value = Grid> {
+    (1..=max_width.max(1), 1..=max_height.max(1)).prop_flat_map(|(width, height)| {
+        let cell = prop_oneof![1 => Just(0u32), 3 => 1..=9u32];
+        prop::collection::vec(cell, (width * height) as usize)
+            .prop_map(move |cells| Grid::new(cells, width).expect("cells fill every row"))
+    })
+}
+
+/// A grid from [`grids`] along with a start and end index inside it.
+pub fn queries(max_width: u32, max_height: u32) -> impl Strategy<Value = (Grid, u32, u32)> {
+    grids(max_width, max_height).prop_flat_map(|grid| {
+        let len = grid.len();
+        (Just(grid), 0..len, 0..len)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// A step leaves the grid.
+    OutOfBounds { step: usize, index: u32 },
+    /// A step doesn't move to a walkable neighbor of the previous cell.
+    IllegalStep { step: usize, from: u32, to: u32 },
+    /// The path doesn't finish on the goal.
+    WrongEnd { last: u32 },
+    /// The path is empty even though the goal can be reached.
+    Missing { optimal: u32 },
+    /// The path is legal but more expensive than the cheapest one.
+    Suboptimal { cost: u32, optimal: u32 },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::OutOfBounds { step, index } => {
+                write!(f, "step {} goes to {}, outside the grid", step, index)
+            }
+            PathError::IllegalStep { step, from, to } => {
+                write!(
+                    f,
+                    "step {} from {} to {} is not a legal move",
+                    step, from, to
+                )
+            }
+            PathError::WrongEnd { last } => write!(f, "path ends on {} instead of the goal", last),
+            PathError::Missing { optimal } => {
+                write!(f, "no path returned, but one costing {} exists", optimal)
+            }
+            PathError::Suboptimal { cost, optimal } => {
+                write!(f, "path costs {}, but the cheapest costs {}", cost, optimal)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Checks that every step of `path` (which excludes `start`) is a legal move
+/// and returns the path's total cost.
+pub fn path_cost<G: GridSource + ?Sized>(
+    grid: &G,
+    start: u32,
+    path: &[u32],
+    cardinal_directions: bool,
+) -> Result<u32, PathError> {
+    let mut cost = 0;
+    let mut from = start;
+    for (step, &to) in path.iter().enumerate() {
+        if to >= grid.len() {
+            return Err(PathError::OutOfBounds { step, index: to });
+        }
+        if !get_neighbor_coords(from, grid, cardinal_directions).contains(&to) {
+            return Err(PathError::IllegalStep { step, from, to });
+        }
+        cost = step_cost(grid, from, to).saturating_add(cost);
+        from = to;
+    }
+    Ok(cost)
+}
+
+/// Checks that `path` is a legal, cheapest path from `start` to `end`, or
+/// empty when there is none, and returns its cost.
+pub fn verify_path<G: GridSource + ?Sized>(
+    grid: &G,
+    start: u32,
+    end: u32,
+    path: &[u32],
+    cardinal_directions: bool,
+) -> Result<u32, PathError> {
+    let cost = path_cost(grid, start, path, cardinal_directions)?;
+    let optimal = DijkstraMap::new(grid, &[start], cardinal_directions).distance(end);
+    match (path.last(), optimal) {
+        (None, None) => Ok(0),
+        (None, Some(0)) => Ok(0),
+        (None, Some(optimal)) => Err(PathError::Missing { optimal }),
+        (Some(&last), _) if last != end => Err(PathError::WrongEnd { last }),
+        (Some(_), Some(optimal)) if cost != optimal => Err(PathError::Suboptimal { cost, optimal }),
+        (Some(_), _) => Ok(cost),
+    }
+}
+
+/// Runs [`astar_grid`] and panics unless it returns a legal, cheapest path.
+pub fn assert_optimal<G: GridSource + ?Sized>(
+    grid: &G,
+    start: u32,
+    end: u32,
+    cardinal_directions: bool,
+) {
+    let path = astar_grid(start, end, grid, cardinal_directions);
+    if let Err(error) = verify_path(grid, start, end, &path, cardinal_directions) {
+        panic!(
+            "path from {} to {} is wrong: {} (path: {:?})",
+            start, end, error, path
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn astar_finds_cheapest_paths((grid, start, end) in queries(12, 12), cardinal in any::<bool>()) {
+            assert_optimal(&grid, start, end, cardinal);
+        }
+
+        #[test]
+        fn astar_finds_cheapest_paths_in_views((grid, start, end) in queries(12, 12)) {
+            let view = grid.view(0, 0, grid.width(), grid.height()).unwrap();
+            assert_optimal(&view, start, end, false);
+        }
+    }
+
+    #[test]
+    fn it_reports_bad_paths() {
+        let grid = Grid::new(vec![1, 1, 1, 1, 0, 1, 1, 1, 1], 3).unwrap();
+        assert_eq!(
+            verify_path(&grid, 0, 8, &[4, 8], false),
+            Err(PathError::IllegalStep {
+                step: 0,
+                from: 0,
+                to: 4
+            })
+        );
+        assert_eq!(
+            verify_path(&grid, 0, 8, &[1, 2], false),
+            Err(PathError::WrongEnd { last: 2 })
+        );
+        assert_eq!(
+            verify_path(&grid, 0, 8, &[], false),
+            Err(PathError::Missing { optimal: 7 })
+        );
+        assert_eq!(verify_path(&grid, 0, 8, &[1, 2, 5, 8], true), Ok(8));
+        assert_eq!(
+            verify_path(&grid, 0, 2, &[3, 6, 7, 8, 5, 2], true),
+            Err(PathError::Suboptimal {
+                cost: 12,
+                optimal: 4
+            })
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{search, DijkstraMap, GridSource, SearchOptions};
+use std::error::Error;
+use std::fmt;
+
+/// The most waypoints [`visit_all`] orders exactly. Held–Karp takes time
+/// and memory exponential in the count, and this keeps both reasonable.
+pub const MAX_WAYPOINTS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TourError {
+    TooManyWaypoints {
+        count: usize,
+    },
+    /// Some waypoint, or the end, can't be reached.
+    Unreachable,
+}
+
+impl fmt::Display for TourError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TourError::TooManyWaypoints { count } => write!(
+                f,
+                "{} waypoints is more than the {} that can be ordered",
+                count, MAX_WAYPOINTS
+            ),
+            TourError::Unreachable => write!(f, "not every waypoint can be reached"),
+        }
+    }
+}
+
+impl Error for TourError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tour {
+    /// Indices into the waypoints, in the order they are visited.
+    pub order: Vec<usize>,
+    /// The whole route, excluding the start, like a path from
+    /// [`crate::astar`].
+    pub path: Vec<u32>,
+    pub cost: u32,
+}
+
+/// The cheapest route from `start` through every cell of `waypoints`, in
+/// whatever order is cheapest, finishing at `end` when given or at the last
+/// waypoint otherwise.
+///
+/// Pairwise costs come from one Dijkstra flood per waypoint, the visiting
+/// order is solved exactly with Held–Karp, and the legs are then searched
+/// and stitched together. The floods only go by
+/// [`SearchOptions::cardinal_directions`], so the legs are searched with
+/// that, [`SearchOptions::jitter`] and [`SearchOptions::backend`] alone,
+/// and cost what [`Tour::cost`] says; [`SearchOptions::reverse_path`]
+/// reverses the whole route. The other options are ignored.
+pub fn visit_all<G: GridSource + ?Sized>(
+    start: u32,
+    waypoints: &[u32],
+    end: Option<u32>,
+    grid: &G,
+    options: &SearchOptions,
+) -> Result<Tour, TourError> {
+    let count = waypoints.len();
+    if count > MAX_WAYPOINTS {
+        return Err(TourError::TooManyWaypoints { count });
+    }
+    let len = grid.len();
+    if start >= len
+        || end.is_some_and(|end| end >= len)
+        || waypoints.iter().any(|&waypoint| waypoint >= len)
+    {
+        return Err(TourError::Unreachable);
+    }
+    let cardinal_directions = options.cardinal_directions;
+    let from_start = DijkstraMap::new(grid, &[start], cardinal_directions);
+    let from_waypoints: Vec<DijkstraMap> = waypoints
+        .iter()
+        .map(|&waypoint| DijkstraMap::new(grid, &[waypoint], cardinal_directions))
+        .collect();
+    const UNREACHABLE: u32 = u32::MAX;
+    let distance = |map: &DijkstraMap, to: u32| map.distance(to).unwrap_or(UNREACHABLE);
+    let full = (1usize << count) - 1;
+    // best[mask * count + last]: cheapest cost from the start through the
+    // waypoints in `mask`, ending at waypoint `last`
+    let mut best = vec![UNREACHABLE; (full + 1) * count.max(1)];
+    let mut previous = vec![usize::MAX; (full + 1) * count.max(1)];
+    for (i, &waypoint) in waypoints.iter().enumerate() {
+        best[(1 << i) * count + i] = distance(&from_start, waypoint);
+    }
+    for mask in 1..=full {
+        for last in 0..count {
+            let here = best[mask * count + last];
+            if mask & (1 << last) == 0 || here == UNREACHABLE {
+                continue;
+            }
+            for (next, &waypoint) in waypoints.iter().enumerate() {
+                if mask & (1 << next) != 0 {
+                    continue;
+                }
+                let step = distance(&from_waypoints[last], waypoint);
+                if step == UNREACHABLE {
+                    continue;
+                }
+                let slot = (mask | 1 << next) * count + next;
+                let cost = here.saturating_add(step);
+                if cost < best[slot] {
+                    best[slot] = cost;
+                    previous[slot] = last;
+                }
+            }
+        }
+    }
+    let mut order = Vec::with_capacity(count);
+    let cost = if count == 0 {
+        match end {
+            Some(end) => from_start.distance(end).ok_or(TourError::Unreachable)?,
+            None => 0,
+        }
+    } else {
+        let mut finish = (UNREACHABLE, usize::MAX);
+        for last in 0..count {
+            let here = best[full * count + last];
+            let tail = match end {
+                Some(end) => distance(&from_waypoints[last], end),
+                None => 0,
+            };
+            if here != UNREACHABLE && tail != UNREACHABLE {
+                finish = finish.min((here.saturating_add(tail), last));
+            }
+        }
+        if finish.0 == UNREACHABLE {
+            return Err(TourError::Unreachable);
+        }
+        let (mut mask, mut last) = (full, finish.1);
+        while last != usize::MAX {
+            order.push(last);
+            let before = previous[mask * count + last];
+            mask &= !(1 << last);
+            last = before;
+        }
+        order.reverse();
+        finish.0
+    };
+    let mut stops: Vec<u32> = order.iter().map(|&i| waypoints[i]).collect();
+    stops.extend(end);
+    let leg = leg_options(options);
+    let mut path = Vec::new();
+    let mut from = start;
+    for &stop in stops.iter() {
+        path.extend(search(from, stop, grid, &leg).path);
+        from = stop;
+    }
+    if options.reverse_path {
+        path.reverse();
+    }
+    Ok(Tour { order, path, cost })
+}
+
+/// The options tour legs are searched with: what the Dijkstra floods go
+/// by, and what only chooses between paths of the same cost. Legs only
+/// join up end to end in walking order, so they are never reversed.
+fn leg_options(options: &SearchOptions) -> SearchOptions {
+    SearchOptions {
+        cardinal_directions: options.cardinal_directions,
+        jitter: options.jitter,
+        backend: options.backend,
+        ..SearchOptions::default()
+    }
+}
+
+/// A closed route through every one of `points` and back, for guards
+/// walking a patrol. It starts and ends at the first point, and
+/// [`Tour::order`] says which point comes when, beginning with `0`.
+///
+/// Unlike [`visit_all`] the order isn't solved exactly, so any number of
+/// points can be given: it is built nearest point first from the pairwise
+/// costs of one Dijkstra flood per point, then improved by reversing
+/// stretches of it (2-opt) for as long as that makes the loop cheaper.
+/// Options are honoured as by [`visit_all`].
+pub fn patrol_loop<G: GridSource + ?Sized>(
+    points: &[u32],
+    grid: &G,
+    options: &SearchOptions,
+) -> Result<Tour, TourError> {
+    let count = points.len();
+    if points.iter().any(|&point| point >= grid.len()) {
+        return Err(TourError::Unreachable);
+    }
+    if count == 0 {
+        return Ok(Tour {
+            order: Vec::new(),
+            path: Vec::new(),
+            cost: 0,
+        });
+    }
+    // costs[from * count + to]
+    let mut costs = vec![0u32; count * count];
+    for (from, &point) in points.iter().enumerate() {
+        let map = DijkstraMap::new(grid, &[point], options.cardinal_directions);
+        for (to, &other) in points.iter().enumerate() {
+            costs[from * count + to] = map.distance(other).ok_or(TourError::Unreachable)?;
+        }
+    }
+    let loop_cost = |order: &[usize]| {
+        (0..count)
+            .map(|i| costs[order[i] * count + order[(i + 1) % count]])
+            .fold(0, u32::saturating_add)
+    };
+    let mut order = vec![0];
+    let mut left: Vec<usize> = (1..count).collect();
+    while let Some(&last) = order.last() {
+        let nearest = left
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &next)| (costs[last * count + next], next))
+            .map(|(i, _)| i);
+        match nearest {
+            Some(i) => order.push(left.remove(i)),
+            None => break,
+        }
+    }
+    // the first point stays put, so the loop still starts there
+    let mut cost = loop_cost(&order);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for first in 1..count {
+            for last in first + 1..count {
+                order[first..=last].reverse();
+                let shorter = loop_cost(&order);
+                if shorter < cost {
+                    cost = shorter;
+                    improved = true;
+                } else {
+                    order[first..=last].reverse();
+                }
+            }
+        }
+    }
+    let leg = leg_options(options);
+    let mut path = Vec::new();
+    if count > 1 {
+        for i in 0..count {
+            let (from, to) = (points[order[i]], points[order[(i + 1) % count]]);
+            path.extend(search(from, to, grid, &leg).path);
+        }
+    }
+    if options.reverse_path {
+        path.reverse();
+    }
+    Ok(Tour { order, path, cost })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{step_cost, Grid, Rng, Weighting};
+
+    fn cost_of(grid: &Grid, start: u32, path: &[u32]) -> u32 {
+        let steps = std::iter::once(&start).chain(path).zip(path);
+        steps.map(|(&from, &to)| step_cost(grid, from, to)).sum()
+    }
+
+    /// Options that would cut a leg short or make it costlier.
+    fn hostile_options() -> SearchOptions {
+        SearchOptions {
+            max_expansions: Some(3),
+            path_budget: Some(4),
+            max_steps: Some(2),
+            lazy_path: true,
+            pay_for_start: true,
+            weighting: Weighting::Dynamic {
+                epsilon_percent: 200,
+            },
+            ..SearchOptions::default()
+        }
+    }
+
+    #[test]
+    fn it_finds_the_cheapest_order() {
+        let grid = Grid::filled(10, 1, 1).unwrap();
+        let options = SearchOptions::default();
+        let tour = visit_all(4, &[9, 0, 6, 2], None, &grid, &options).unwrap();
+        assert_eq!(tour.order, vec![3, 1, 2, 0]);
+        assert_eq!(tour.cost, 2 * 13);
+        assert_eq!(tour.path.len(), 13);
+        assert_eq!(tour.path.last(), Some(&9));
+        let ending = visit_all(4, &[9, 0, 6, 2], Some(0), &grid, &options).unwrap();
+        assert_eq!(ending.order, vec![2, 0, 3, 1]);
+        assert_eq!(ending.cost, 2 * 14);
+        assert_eq!(ending.path.len(), 14);
+    }
+
+    #[test]
+    fn tours_cost_what_their_paths_do() {
+        let mut rng = Rng::new(1);
+        let grid = rng.grid(12, 9, 0, 5);
+        let tour = visit_all(0, &[40, 107, 11, 66], Some(96), &grid, &hostile_options()).unwrap();
+        assert_eq!(cost_of(&grid, 0, &tour.path), tour.cost);
+        assert_eq!(tour.path.last(), Some(&96));
+    }
+
+    #[test]
+    fn patrols_cost_what_their_paths_do() {
+        let mut rng = Rng::new(2);
+        let grid = rng.grid(12, 9, 0, 5);
+        let patrol = patrol_loop(&[30, 107, 11, 66, 90], &grid, &hostile_options()).unwrap();
+        assert_eq!(cost_of(&grid, 30, &patrol.path), patrol.cost);
+        assert_eq!(patrol.path.last(), Some(&30));
+    }
+
+    #[test]
+    fn it_checks_its_inputs() {
+        let grid = Grid::new(vec![1, 0, 1], 3).unwrap();
+        let options = SearchOptions::default();
+        assert_eq!(
+            visit_all(0, &[2], None, &grid, &options),
+            Err(TourError::Unreachable)
+        );
+        assert_eq!(
+            visit_all(0, &[0; 17], None, &grid, &options),
+            Err(TourError::TooManyWaypoints { count: 17 })
+        );
+        let empty = visit_all(0, &[], Some(0), &grid, &options).unwrap();
+        assert_eq!((empty.cost, empty.path.len()), (0, 0));
+    }
+
+    #[test]
+    fn it_closes_a_patrol_loop() {
+        let grid = Grid::filled(20, 1, 1).unwrap();
+        let options = SearchOptions::default();
+        // nearest first would wander 10, 12, 7, 0 before crossing to 19
+        let patrol = patrol_loop(&[10, 12, 7, 19, 0], &grid, &options).unwrap();
+        assert_eq!(patrol.order[0], 0);
+        assert_eq!(patrol.cost, 2 * 38);
+        assert_eq!(patrol.path.len(), 38);
+        assert_eq!(patrol.path.last(), Some(&10));
+        assert!([0, 7, 12, 19].iter().all(|cell| patrol.path.contains(cell)));
+        let alone = patrol_loop(&[3], &grid, &options).unwrap();
+        assert_eq!((alone.order, alone.path, alone.cost), (vec![0], vec![], 0));
+        let walled = Grid::new(vec![1, 0, 1], 3).unwrap();
+        assert_eq!(
+            patrol_loop(&[0, 2], &walled, &options),
+            Err(TourError::Unreachable)
+        );
+    }
+}
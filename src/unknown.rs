@@ -0,0 +1,97 @@
+use crate::{GridSource, UNKNOWN, WALL};
+
+/// What searches make of [`UNKNOWN`] cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnknownCells {
+    /// As a cost like any other, the highest there is, so that paths only
+    /// cross them when there is no other way.
+    #[default]
+    Cost,
+    /// As open ground costing `cost`, the freespace assumption of
+    /// exploring agents, which plan through what they haven't seen and
+    /// plan again once they do. A `cost` of [`WALL`] makes them walls.
+    Open { cost: u32 },
+    /// As walls, for cautious agents that only go where they know they can.
+    Blocked,
+}
+
+impl UnknownCells {
+    /// `grid` with its unknown cells read this way, for flow fields and
+    /// everything else that doesn't take [`crate::SearchOptions`].
+    pub fn over<G: GridSource>(self, grid: G) -> Believed<G> {
+        let unknown = match self {
+            UnknownCells::Cost => UNKNOWN,
+            UnknownCells::Open { cost } => cost,
+            UnknownCells::Blocked => WALL,
+        };
+        Believed { grid, unknown }
+    }
+}
+
+/// A grid read with [`UNKNOWN`] cells costing something else, made by
+/// [`UnknownCells::over`].
+#[derive(Debug, Clone, Copy)]
+pub struct Believed<G> {
+    grid: G,
+    unknown: u32,
+}
+
+impl<G: GridSource> GridSource for Believed<G> {
+    #[inline(always)]
+    fn width(&self) -> u32 {
+        self.grid.width()
+    }
+
+    #[inline(always)]
+    fn height(&self) -> u32 {
+        self.grid.height()
+    }
+
+    #[inline(always)]
+    fn cost(&self, index: u32) -> u32 {
+        match self.grid.cost(index) {
+            UNKNOWN => self.unknown,
+            cost => cost,
+        }
+    }
+
+    #[inline(always)]
+    fn has_wall_border(&self) -> bool {
+        self.grid.has_wall_border()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid, search, FlowField, SearchOptions};
+
+    #[test]
+    fn unknown_cells_are_open_blocked_or_costly() {
+        // the way through the middle hasn't been seen, the way round has
+        let grid = grid![
+            1, 1, 1, 1, 1;
+            1, 0, 0, 0, 1;
+            1, UNKNOWN, UNKNOWN, UNKNOWN, 1;
+        ];
+        let options = |unknown_cells| SearchOptions {
+            cardinal_directions: true,
+            unknown_cells,
+            ..SearchOptions::default()
+        };
+        let optimistic = search(10, 14, &grid, &options(UnknownCells::Open { cost: 1 }));
+        assert_eq!(optimistic.path, vec![11, 12, 13, 14]);
+        assert_eq!(optimistic.cost, Some(8));
+        let cautious = search(10, 14, &grid, &options(UnknownCells::Blocked));
+        assert_eq!(cautious.path, vec![5, 0, 1, 2, 3, 4, 9, 14]);
+        assert_eq!(
+            search(10, 12, &grid, &options(UnknownCells::Blocked)).cost,
+            None
+        );
+        let costly = search(10, 14, &grid, &options(UnknownCells::Cost));
+        assert_eq!(costly.path, cautious.path);
+        let field = FlowField::new(&UnknownCells::Blocked.over(&grid), &[12], true);
+        assert_eq!(field.distance(10), None);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::search::{search_with, Hooks};
+use crate::{GridSource, Manhattan, SearchOptions, SearchResult};
+use std::ops::ControlFlow;
+
+/// A cell a search has taken off the frontier, as shown to the condition
+/// given to [`search_until`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchState {
+    pub cell: u32,
+    /// Cost of the cheapest route from the start to `cell`.
+    pub g: u32,
+    /// The heuristic estimate from `cell` to the goal.
+    pub h: u32,
+    /// The cell `cell` was reached from, `None` for the start.
+    pub parent: Option<u32>,
+    /// Cells expanded before this one.
+    pub expanded: u32,
+}
+
+struct Until<F>(F);
+
+impl<F: FnMut(&SearchState) -> ControlFlow<()>> Hooks for Until<F> {
+    fn stop_at(&mut self, state: &SearchState) -> bool {
+        (self.0)(state).is_break()
+    }
+}
+
+/// [`search`](crate::search) that shows `condition` every cell it takes
+/// off the frontier, other than the goal, and stops as soon as it breaks,
+/// for policies the options don't cover: stopping on the first cell holding
+/// some item, or once `g` passes the action points a unit has left.
+///
+/// Cells come in the order plain A* expands them, so with the heuristic
+/// guiding the search toward `end` the first cell to match isn't always the
+/// closest one; a search whose goal can't be reached, such as a wall, never
+/// stops early on account of it and so finds the cheapest match. Stopping
+/// ends with [`TerminationReason::Stopped`](crate::TerminationReason::Stopped)
+/// and the cheapest path to the cell stopped at, whatever
+/// [`SearchOptions::partial_path`] says.
+pub fn search_until<G, F>(
+    start: u32,
+    end: u32,
+    grid: &G,
+    options: &SearchOptions,
+    condition: F,
+) -> SearchResult
+where
+    G: GridSource + ?Sized,
+    F: FnMut(&SearchState) -> ControlFlow<()>,
+{
+    search_with(start, end, grid, options, &Manhattan, Until(condition))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid, TerminationReason};
+
+    #[test]
+    fn it_stops_where_the_condition_says() {
+        let grid = grid![
+            1, 1, 1, 1, 1;
+            1, 0, 0, 0, 1;
+            1, 1, 5, 1, 1;
+        ];
+        let options = SearchOptions {
+            cardinal_directions: true,
+            ..SearchOptions::default()
+        };
+        // the wall at 6 can't be reached, so the search floods outward
+        let found = search_until(0, 6, &grid, &options, |state| {
+            if state.cell == 12 || state.cell == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(found.termination, TerminationReason::Stopped);
+        assert_eq!(found.path, vec![1, 2, 3]);
+        assert_eq!(found.closest, 3);
+        let tired = search_until(0, 14, &grid, &options, |state| {
+            if state.g > 6 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(tired.termination, TerminationReason::Stopped);
+        assert_eq!(tired.cost, None);
+        let unbothered = search_until(0, 14, &grid, &options, |_| ControlFlow::Continue(()));
+        assert_eq!(unbothered, crate::search(0, 14, &grid, &options));
+    }
+}
@@ -0,0 +1,472 @@
+//! Compact binary formats for shipping maps in asset bundles and sending
+//! results to clients that run no searches of their own. Each starts with a
+//! four-byte tag naming the format and its version, so that readers can
+//! turn down data they don't know how to read; integers are little-endian.
+
+use crate::artifact::valid_move;
+use crate::dijkstra::NO_MOVE;
+use crate::{direction, neighbor_in, Artifact, FlowField, Grid, GridSource, DIRECTIONS};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+/// Why bytes couldn't be read back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bytes aren't in the format asked for.
+    WrongFormat,
+    /// The format is right but its version is newer than this crate knows.
+    UnsupportedVersion { version: u8 },
+    /// The bytes end before the data they describe does.
+    Truncated,
+    /// The bytes hold a value that can't be right, such as a move that
+    /// leaves the grid.
+    Corrupt,
+    /// The bytes were built for a grid that has changed since, going by
+    /// [`crate::map_hash`].
+    StaleMap { expected: u64, found: u64 },
+    /// The bytes are well formed, but describe more than there is memory
+    /// to hold.
+    TooLarge,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::WrongFormat => write!(f, "not data of the expected format"),
+            DecodeError::UnsupportedVersion { version } => {
+                write!(f, "format version {} is not supported", version)
+            }
+            DecodeError::Truncated => write!(f, "data ends early"),
+            DecodeError::Corrupt => write!(f, "data holds impossible values"),
+            DecodeError::StaleMap { expected, found } => write!(
+                f,
+                "data was built for map {:016x}, not this one ({:016x})",
+                found, expected
+            ),
+            DecodeError::TooLarge => write!(f, "data is too large to hold"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Checks the tag at the start of `bytes` and returns what follows it.
+pub(crate) fn untag<'a>(
+    bytes: &'a [u8],
+    format: &[u8; 3],
+    version: u8,
+) -> Result<&'a [u8], DecodeError> {
+    if bytes.len() < 4 {
+        return Err(DecodeError::Truncated);
+    }
+    if &bytes[..3] != format {
+        return Err(DecodeError::WrongFormat);
+    }
+    if bytes[3] != version {
+        return Err(DecodeError::UnsupportedVersion { version: bytes[3] });
+    }
+    Ok(&bytes[4..])
+}
+
+pub(crate) fn read_u32(bytes: &[u8], at: usize) -> Result<u32, DecodeError> {
+    let word = bytes.get(at..at + 4).ok_or(DecodeError::Truncated)?;
+    Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+}
+
+/// The first move from every cell toward one goal, one byte per cell, for
+/// thin clients to predict how units move between server updates.
+///
+/// Moves are indices into the eight directions clockwise from up: `0` is
+/// up, `1` up and right, `2` right and so on to `7`, up and left.
+/// [`FirstMoveTable::NONE`] marks the goal, walls, and cells that can't
+/// reach it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirstMoveTable {
+    width: u32,
+    height: u32,
+    moves: Vec<u8>,
+}
+
+impl FirstMoveTable {
+    pub const NONE: u8 = NO_MOVE;
+    const FORMAT: &'static [u8; 3] = b"SAF";
+    const VERSION: u8 = 1;
+
+    /// The moves toward `goal` on `grid`.
+    pub fn new<G: GridSource + ?Sized>(grid: &G, goal: u32, cardinal_directions: bool) -> Self {
+        Self::from_flow_field(&FlowField::new(grid, &[goal], cardinal_directions))
+    }
+
+    /// The moves of an already integrated field, toward whichever of its
+    /// goals is nearest.
+    pub fn from_flow_field(field: &FlowField) -> Self {
+        FirstMoveTable {
+            width: field.width(),
+            height: field.height(),
+            moves: field.moves().to_vec(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Every cell's move, in row-major order.
+    pub fn moves(&self) -> &[u8] {
+        &self.moves
+    }
+
+    /// The cell to step into from `cell`, or `None` at the goal and
+    /// wherever it can't be reached.
+    pub fn next_step(&self, cell: u32) -> Option<u32> {
+        match self.moves.get(cell as usize) {
+            Some(&first) if first != Self::NONE => Some(neighbor_in(cell, first, self.width)),
+            _ => None,
+        }
+    }
+
+    /// Encodes the table in half a byte per cell after a twelve-byte
+    /// header: the tag, then the width and height.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + self.moves.len().div_ceil(2));
+        bytes.extend_from_slice(Self::FORMAT);
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend(self.moves.chunks(2).map(|pair| {
+            // the low half holds the first cell of the pair
+            pair[0] | pair.get(1).map_or(0, |&second| second << 4)
+        }));
+        bytes
+    }
+
+    /// Reads a table back from [`FirstMoveTable::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let body = untag(bytes, Self::FORMAT, Self::VERSION)?;
+        let (width, height) = (read_u32(body, 0)?, read_u32(body, 4)?);
+        let len = width as u64 * height as u64;
+        let packed = &body[8..];
+        if (packed.len() as u64) < len.div_ceil(2) {
+            return Err(DecodeError::Truncated);
+        }
+        let moves: Vec<u8> = (0..len as usize)
+            .map(|cell| packed[cell / 2] >> (cell % 2 * 4) & 0xf)
+            .collect();
+        let table = FirstMoveTable {
+            width,
+            height,
+            moves,
+        };
+        let valid = table
+            .moves
+            .iter()
+            .enumerate()
+            .all(|(cell, &first)| valid_move(cell as u32, first, width, height));
+        if !valid {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(table)
+    }
+}
+
+impl Artifact for FirstMoveTable {
+    const KIND: [u8; 3] = *b"fmt";
+    const VERSION: u8 = 1;
+
+    fn write_payload(&self, bytes: &mut Vec<u8>) {
+        bytes.extend(self.to_bytes());
+    }
+
+    fn read_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        FirstMoveTable::from_bytes(payload)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads a varint at the start of `bytes`, returning it and what follows.
+fn read_varint(bytes: &[u8]) -> Result<(u32, &[u8]), DecodeError> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(5) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            let value = u32::try_from(value).map_err(|_| DecodeError::Corrupt)?;
+            return Ok((value, &bytes[i + 1..]));
+        }
+    }
+    if bytes.len() < 5 {
+        Err(DecodeError::Truncated)
+    } else {
+        Err(DecodeError::Corrupt)
+    }
+}
+
+const PATH_FORMAT: &[u8; 3] = b"SAP";
+const PATH_VERSION: u8 = 1;
+
+/// Encodes a path leaving `start` on a grid `width` cells wide as the start
+/// and the direction of each step in three bits, with the start and the
+/// number of steps as varints after the tag. A 60-step path on a map of up
+/// to two million cells takes at most 31 bytes. `None` when two cells of
+/// the path, or the start and the first, aren't neighbors, as on a grid
+/// with no width.
+pub fn encode_path(start: u32, path: &[u32], width: u32) -> Option<Vec<u8>> {
+    if width == 0 && !path.is_empty() {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(4 + 10 + (path.len() * 3).div_ceil(8));
+    bytes.extend_from_slice(PATH_FORMAT);
+    bytes.push(PATH_VERSION);
+    write_varint(&mut bytes, start);
+    write_varint(&mut bytes, path.len() as u32);
+    let (mut bits, mut pending) = (0u32, 0u32);
+    let mut from = start;
+    for &to in path {
+        let (dx, dy) = (
+            (to % width) as i64 - (from % width) as i64,
+            (to / width) as i64 - (from / width) as i64,
+        );
+        if dx.abs() > 1 || dy.abs() > 1 || (dx, dy) == (0, 0) {
+            return None;
+        }
+        pending |= (direction(from, to, width) as u32) << bits;
+        bits += 3;
+        while bits >= 8 {
+            bytes.push(pending as u8);
+            pending >>= 8;
+            bits -= 8;
+        }
+        from = to;
+    }
+    if bits > 0 {
+        bytes.push(pending as u8);
+    }
+    Some(bytes)
+}
+
+/// Reads back a path from [`encode_path`] on a grid `width` cells wide,
+/// returning its start and its cells.
+pub fn decode_path(bytes: &[u8], width: u32) -> Result<(u32, Vec<u32>), DecodeError> {
+    let body = untag(bytes, PATH_FORMAT, PATH_VERSION)?;
+    let (start, body) = read_varint(body)?;
+    let (steps, packed) = read_varint(body)?;
+    if (packed.len() as u64) < (steps as u64 * 3).div_ceil(8) {
+        return Err(DecodeError::Truncated);
+    }
+    // no step stays on a grid with no width
+    if width == 0 && steps > 0 {
+        return Err(DecodeError::Corrupt);
+    }
+    let mut path = Vec::with_capacity(steps as usize);
+    let mut from = start;
+    for step in 0..steps as usize {
+        let bit = step * 3;
+        let pair =
+            packed[bit / 8] as u32 | (packed.get(bit / 8 + 1).copied().unwrap_or(0) as u32) << 8;
+        let first = (pair >> (bit % 8) & 0b111) as u8;
+        let (dx, dy) = DIRECTIONS[first as usize];
+        let (x, y) = (
+            (from % width) as i64 + dx as i64,
+            (from / width) as i64 + dy as i64,
+        );
+        if x < 0 || y < 0 || x >= width as i64 || y * width as i64 + x > u32::MAX as i64 {
+            return Err(DecodeError::Corrupt);
+        }
+        from = neighbor_in(from, first, width);
+        path.push(from);
+    }
+    Ok((start, path))
+}
+
+const GRID_FORMAT: &[u8; 3] = b"SAG";
+const GRID_VERSION: u8 = 1;
+
+/// Encodes the costs of `grid` for shipping with other assets: the tag,
+/// the width and height, then runs of equal costs in row-major order, each
+/// a varint length followed by a varint cost. Walls and open floor come in
+/// long runs, so most maps shrink to a small part of four bytes a cell.
+pub fn save_grid<G: GridSource + ?Sized>(grid: &G) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(GRID_FORMAT);
+    bytes.push(GRID_VERSION);
+    bytes.extend_from_slice(&grid.width().to_le_bytes());
+    bytes.extend_from_slice(&grid.height().to_le_bytes());
+    let mut cell = 0;
+    while cell < grid.len() {
+        let cost = grid.cost(cell);
+        let run = (cell..grid.len())
+            .take_while(|&next| grid.cost(next) == cost)
+            .count() as u32;
+        write_varint(&mut bytes, run);
+        write_varint(&mut bytes, cost);
+        cell += run;
+    }
+    bytes
+}
+
+/// Reads a grid back from [`save_grid`]. A few bytes can describe a huge
+/// grid, so every run is checked before any room is made for the cells, and
+/// grids there isn't the memory for fail with [`DecodeError::TooLarge`]
+/// rather than abort.
+pub fn load_grid(bytes: &[u8]) -> Result<Grid<u32>, DecodeError> {
+    let body = untag(bytes, GRID_FORMAT, GRID_VERSION)?;
+    let (width, height) = (read_u32(body, 0)?, read_u32(body, 4)?);
+    let len = width as u64 * height as u64;
+    if len > u32::MAX as u64 {
+        return Err(DecodeError::Corrupt);
+    }
+    let runs = |mut runs: &[u8], mut each: Option<&mut Vec<u32>>| {
+        let mut filled = 0u64;
+        while filled < len {
+            let (run, rest) = read_varint(runs)?;
+            let (cost, rest) = read_varint(rest)?;
+            if run == 0 || filled + run as u64 > len {
+                return Err(DecodeError::Corrupt);
+            }
+            if let Some(cells) = each.as_mut() {
+                cells.resize(cells.len() + run as usize, cost);
+            }
+            filled += run as u64;
+            runs = rest;
+        }
+        Ok(())
+    };
+    runs(&body[8..], None)?;
+    let mut cells = Vec::new();
+    cells
+        .try_reserve_exact(len as usize)
+        .map_err(|_| DecodeError::TooLarge)?;
+    runs(&body[8..], Some(&mut cells))?;
+    Grid::new(cells, width).map_err(|_| DecodeError::Corrupt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search, Grid, Rng, SearchOptions};
+
+    #[test]
+    fn first_moves_round_trip_and_follow_cheapest_paths() {
+        let mut rng = Rng::new(1);
+        let grid = rng.grid(9, 7, 20, 3);
+        let goal = (0..grid.len()).find(|&cell| grid.cost(cell) != 0).unwrap();
+        let table = FirstMoveTable::new(&grid, goal, false);
+        let bytes = table.to_bytes();
+        assert_eq!(bytes.len(), 12 + 32);
+        assert_eq!(FirstMoveTable::from_bytes(&bytes), Ok(table.clone()));
+        for start in (0..grid.len()).filter(|&cell| grid.cost(cell) != 0) {
+            let expected = search(start, goal, &grid, &SearchOptions::default());
+            let (mut cell, mut cost) = (start, 0);
+            while let Some(next) = table.next_step(cell) {
+                cost += crate::step_cost(&grid, cell, next);
+                cell = next;
+            }
+            match expected.cost {
+                Some(expected) if start != goal => assert_eq!((cell, cost), (goal, expected)),
+                _ => assert_eq!(cost, 0),
+            }
+        }
+        assert_eq!(
+            FirstMoveTable::from_bytes(&bytes[..20]),
+            Err(DecodeError::Truncated)
+        );
+        let mut newer = bytes.clone();
+        newer[3] = 2;
+        assert_eq!(
+            FirstMoveTable::from_bytes(&newer),
+            Err(DecodeError::UnsupportedVersion { version: 2 })
+        );
+        let mut off_grid = bytes;
+        // the first cell is in the top row, so it can't step up
+        off_grid[12] &= 0xf0;
+        assert_eq!(
+            FirstMoveTable::from_bytes(&off_grid),
+            Err(DecodeError::Corrupt)
+        );
+        assert_eq!(
+            FirstMoveTable::from_bytes(b"PNG?"),
+            Err(DecodeError::WrongFormat)
+        );
+    }
+
+    #[test]
+    fn paths_round_trip_in_three_bits_a_step() {
+        let grid = Grid::filled(1000, 1000, 1).unwrap();
+        let (start, end) = (1000 * 500 + 20, 1000 * 530 + 80);
+        let path = search(start, end, &grid, &SearchOptions::default()).path;
+        assert_eq!(path.len(), 60);
+        let bytes = encode_path(start, &path, 1000).unwrap();
+        assert!(bytes.len() <= 31);
+        assert_eq!(decode_path(&bytes, 1000), Ok((start, path.clone())));
+        assert_eq!(encode_path(start, &path[1..], 1000), None);
+        assert_eq!(
+            decode_path(&bytes[..bytes.len() - 1], 1000),
+            Err(DecodeError::Truncated)
+        );
+        assert_eq!(
+            decode_path(&encode_path(7, &[], 9).unwrap(), 9),
+            Ok((7, vec![]))
+        );
+        // stepping left off the first column
+        let left = encode_path(1, &[0], 4).unwrap();
+        assert_eq!(decode_path(&left, 1), Err(DecodeError::Corrupt));
+        assert_eq!(decode_path(&left, 0), Err(DecodeError::Corrupt));
+        assert_eq!(encode_path(1, &[0], 0), None);
+        assert_eq!(
+            decode_path(&encode_path(1, &[], 0).unwrap(), 0),
+            Ok((1, vec![]))
+        );
+        assert_eq!(
+            decode_path(
+                &FirstMoveTable::new(&grid.view(0, 0, 3, 3).unwrap(), 0, false).to_bytes(),
+                3
+            ),
+            Err(DecodeError::WrongFormat)
+        );
+    }
+
+    #[test]
+    fn grids_round_trip_in_runs() {
+        let mut grid = Grid::filled(64, 64, 1).unwrap();
+        for x in 0..64 {
+            grid.set(x, 20, 0);
+        }
+        grid.set(5, 40, 7);
+        let bytes = save_grid(&grid);
+        assert!(bytes.len() < 40);
+        assert_eq!(load_grid(&bytes), Ok(grid));
+        assert_eq!(
+            load_grid(&bytes[..bytes.len() - 2]),
+            Err(DecodeError::Truncated)
+        );
+        let mut overlong = bytes[..12].to_vec();
+        write_varint(&mut overlong, 64 * 64 + 1);
+        write_varint(&mut overlong, 1);
+        assert_eq!(load_grid(&overlong), Err(DecodeError::Corrupt));
+        // a huge grid whose runs give out is turned down before it is filled
+        let mut hostile = GRID_FORMAT.to_vec();
+        hostile.push(GRID_VERSION);
+        hostile.extend_from_slice(&65536u32.to_le_bytes());
+        hostile.extend_from_slice(&65535u32.to_le_bytes());
+        write_varint(&mut hostile, 65536 * 65535 - 1);
+        write_varint(&mut hostile, 1);
+        assert_eq!(load_grid(&hostile), Err(DecodeError::Truncated));
+        write_varint(&mut hostile, u32::MAX);
+        write_varint(&mut hostile, 1);
+        assert_eq!(load_grid(&hostile), Err(DecodeError::Corrupt));
+        let empty = Grid::<u32>::new(vec![], 5).unwrap();
+        assert_eq!(load_grid(&save_grid(&empty)), Ok(empty));
+    }
+}
@@ -0,0 +1,167 @@
+//! Differential test against `bracket-pathfinding`, enabled by the
+//! `differential` feature:
+//!
+//! ```text
+//! cargo test --features differential --test differential
+//! ```
+//!
+//! Every case is generated from its seed, so a reported mismatch can be
+//! replayed on its own with `DIFFERENTIAL_SEED=<seed>`. `DIFFERENTIAL_CASES`
+//! changes how many seeds are tried.
+#![cfg(feature = "differential")]
+
+use bracket_pathfinding::prelude::{a_star_search, BaseMap, DijkstraMap, SmallVec};
+use simple_astar::testing::path_cost;
+use simple_astar::{astar_grid, Grid, GridSource, Rng};
+use std::env;
+
+/// Exposes a grid to bracket-pathfinding with the same movement rules and
+/// step costs as this crate, written out independently of its internals.
+struct BracketMap<'a> {
+    grid: &'a Grid,
+    cardinal_directions: bool,
+}
+
+impl<'a> BaseMap for BracketMap<'a> {
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let width = self.grid.width() as i32;
+        let height = self.grid.height() as i32;
+        let (x, y) = (idx as i32 % width, idx as i32 / width);
+        let mut exits = SmallVec::new();
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let diagonal = dx != 0 && dy != 0;
+                if (dx == 0 && dy == 0) || (diagonal && self.cardinal_directions) {
+                    continue;
+                }
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                    continue;
+                }
+                let cost = self.grid.get(nx as u32, ny as u32).unwrap();
+                if cost > 0 {
+                    let step = cost + if diagonal { 2 } else { 1 };
+                    exits.push(((ny * width + nx) as usize, step as f32));
+                }
+            }
+        }
+        exits
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        let width = self.grid.width() as i32;
+        let (x1, y1) = (idx1 as i32 % width, idx1 as i32 / width);
+        let (x2, y2) = (idx2 as i32 % width, idx2 as i32 / width);
+        ((x1 - x2).abs() + (y1 - y2).abs()) as f32
+    }
+}
+
+struct Case {
+    grid: Grid,
+    start: u32,
+    end: u32,
+    cardinal_directions: bool,
+}
+
+fn case(seed: u64) -> Case {
+    let mut rng = Rng::new(seed);
+    let width = rng.range(1, 24);
+    let height = rng.range(1, 24);
+    let cells = (0..width * height)
+        .map(|_| {
+            if rng.chance(1, 4) {
+                0
+            } else {
+                rng.range(1, 10)
+            }
+        })
+        .collect::<Vec<u32>>();
+    let grid = Grid::new(cells, width).unwrap();
+    let start = rng.below(grid.len());
+    let end = rng.below(grid.len());
+    Case {
+        grid,
+        start,
+        end,
+        cardinal_directions: rng.chance(1, 2),
+    }
+}
+
+/// Returns a description of the disagreement, if any.
+fn compare(seed: u64) -> Option<String> {
+    let Case {
+        grid,
+        start,
+        end,
+        cardinal_directions,
+    } = case(seed);
+    if start == end {
+        return None;
+    }
+    let map = BracketMap {
+        grid: &grid,
+        cardinal_directions,
+    };
+    let path = astar_grid(start, end, &grid, cardinal_directions);
+    let ours = match path_cost(&grid, start, &path, cardinal_directions) {
+        Ok(_) if path.is_empty() => None,
+        Ok(cost) => Some(cost),
+        Err(error) => return Some(format!("seed {}: illegal path: {}", seed, error)),
+    };
+    let dijkstra = DijkstraMap::new(
+        grid.width(),
+        grid.height(),
+        &[start as usize],
+        &map,
+        f32::MAX,
+    );
+    let exact = Some(dijkstra.map[end as usize])
+        .filter(|&distance| distance < f32::MAX)
+        .map(|distance| distance as u32);
+    let theirs = Some(a_star_search(start as usize, end as usize, &map))
+        .filter(|result| result.success)
+        .map(|result| {
+            let steps: Vec<u32> = result.steps[1..].iter().map(|&i| i as u32).collect();
+            path_cost(&grid, start, &steps, cardinal_directions).unwrap()
+        });
+    // bracket's A* isn't guaranteed to be optimal, so it only counts as a
+    // mismatch when it finds something cheaper than this crate did
+    let astar_beats_us = match (ours, theirs) {
+        (Some(ours), Some(theirs)) => theirs < ours,
+        (None, Some(_)) => true,
+        _ => false,
+    };
+    if ours != exact || astar_beats_us {
+        Some(format!(
+            "seed {}: {} to {} ({}) costs {:?} here, {:?} with bracket's dijkstra, {:?} with bracket's a*",
+            seed,
+            start,
+            end,
+            if cardinal_directions { "cardinal" } else { "diagonal" },
+            ours,
+            exact,
+            theirs
+        ))
+    } else {
+        None
+    }
+}
+
+fn env_u64(name: &str) -> Option<u64> {
+    env::var(name).ok().map(|value| value.parse().expect(name))
+}
+
+#[test]
+fn costs_match_bracket_pathfinding() {
+    let seeds: Vec<u64> = match env_u64("DIFFERENTIAL_SEED") {
+        Some(seed) => vec![seed],
+        None => (0..env_u64("DIFFERENTIAL_CASES").unwrap_or(2000)).collect(),
+    };
+    let mismatches: Vec<String> = seeds.into_iter().filter_map(compare).collect();
+    assert!(
+        mismatches.is_empty(),
+        "{} mismatches:\n{}",
+        mismatches.len(),
+        mismatches.join("\n")
+    );
+}
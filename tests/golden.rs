@@ -0,0 +1,139 @@
+//! Golden-file regression tests.
+//!
+//! Every `tests/fixtures/<name>.map` is an ASCII map: `.` is floor, `#` is a
+//! wall, `1` to `9` are floor with that cost, and `S` and `G` mark the start
+//! and goal. The path from `S` to `G` is searched with both movement modes and
+//! compared against `tests/fixtures/<name>.golden`, which draws the path onto
+//! the map so that behavior changes are easy to review.
+//!
+//! After an intentional change, regenerate the goldens with
+//!
+//! ```text
+//! UPDATE_GOLDENS=1 cargo test --test golden
+//! ```
+//!
+//! and review the diff before committing it.
+
+use simple_astar::{search, Grid, SearchOptions};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+struct Fixture {
+    rows: Vec<Vec<u8>>,
+    grid: Grid,
+    start: u32,
+    goal: u32,
+}
+
+fn parse(source: &str) -> Fixture {
+    let rows: Vec<Vec<u8>> = source
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.bytes().collect())
+        .collect();
+    let width = rows[0].len() as u32;
+    let mut cells = Vec::new();
+    let mut start = None;
+    let mut goal = None;
+    for (y, row) in rows.iter().enumerate() {
+        assert_eq!(row.len() as u32, width, "row {} has the wrong width", y);
+        for &symbol in row {
+            let index = cells.len() as u32;
+            cells.push(match symbol {
+                b'#' => 0,
+                b'.' => 1,
+                b'S' => {
+                    start = Some(index);
+                    1
+                }
+                b'G' => {
+                    goal = Some(index);
+                    1
+                }
+                b'1'..=b'9' => (symbol - b'0') as u32,
+                _ => panic!("unknown map symbol {:?}", symbol as char),
+            });
+        }
+    }
+    Fixture {
+        rows,
+        grid: Grid::new(cells, width).unwrap(),
+        start: start.expect("map has no S"),
+        goal: goal.expect("map has no G"),
+    }
+}
+
+fn render(fixture: &Fixture) -> String {
+    let mut out = String::new();
+    for &cardinal_directions in [true, false].iter() {
+        let options = SearchOptions {
+            cardinal_directions,
+            ..SearchOptions::default()
+        };
+        let result = search(fixture.start, fixture.goal, &fixture.grid, &options);
+        let mode = if cardinal_directions {
+            "cardinal"
+        } else {
+            "diagonal"
+        };
+        match result.cost {
+            Some(cost) => out.push_str(&format!("{}: cost {}\n", mode, cost)),
+            None => out.push_str(&format!("{}: unreachable\n", mode)),
+        }
+        let mut rows = fixture.rows.clone();
+        for &cell in result.path.iter().filter(|&&cell| cell != fixture.goal) {
+            let (x, y) = (cell % fixture.grid.width(), cell / fixture.grid.width());
+            rows[y as usize][x as usize] = b'*';
+        }
+        for row in rows {
+            out.push_str(std::str::from_utf8(&row).unwrap());
+            out.push('\n');
+        }
+        out.push_str("path:");
+        for cell in result.path.iter() {
+            out.push_str(&format!(" {}", cell));
+        }
+        out.push_str("\n\n");
+    }
+    out
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut maps: Vec<PathBuf> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "map"))
+        .collect();
+    maps.sort();
+    maps
+}
+
+#[test]
+fn paths_match_goldens() {
+    let update = env::var_os("UPDATE_GOLDENS").is_some();
+    let mut failures = Vec::new();
+    for map in fixtures() {
+        let actual = render(&parse(&fs::read_to_string(&map).unwrap()));
+        let golden = map.with_extension("golden");
+        if update {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden).unwrap_or_default();
+        if expected != actual {
+            failures.push(format!(
+                "{}\n--- expected\n{}--- actual\n{}",
+                golden.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "paths changed; rerun with UPDATE_GOLDENS=1 if this is intended\n\n{}",
+        failures.join("\n")
+    );
+}
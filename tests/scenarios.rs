@@ -0,0 +1,52 @@
+//! Runs every `tests/fixtures/<name>.scenario.json`, enabled by the
+//! `scenario` feature:
+//!
+//! ```text
+//! cargo test --features scenario --test scenarios
+//! ```
+//!
+//! After an intentional change, record the new costs with
+//!
+//! ```text
+//! cargo run --features scenario --bin simple_astar_scenario -- --record tests/fixtures/*.scenario.json
+//! ```
+//!
+//! and review the diff before committing it.
+#![cfg(feature = "scenario")]
+
+use simple_astar::scenario::Scenario;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn scenarios_cost_what_they_record() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut paths: Vec<_> = fs::read_dir(&fixtures)
+        .expect("fixtures directory exists")
+        .map(|entry| entry.expect("fixture is readable").path())
+        .filter(|path| path.to_string_lossy().ends_with(".scenario.json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no scenarios in {}", fixtures.display());
+    let mut failures = Vec::new();
+    for path in &paths {
+        let (scenario, grid) =
+            Scenario::load(path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+        let mismatches = scenario
+            .run(&grid)
+            .unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+        for mismatch in mismatches {
+            let query = &scenario.queries[mismatch.query];
+            failures.push(format!(
+                "{}: query {} from {:?} to {:?} expected {:?}, found {:?}",
+                path.display(),
+                mismatch.query,
+                query.start,
+                query.goal,
+                mismatch.expected,
+                mismatch.found
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}